@@ -0,0 +1,111 @@
+//! Compares the amount of allocations made when deserializing many small documents into
+//! `Value`s and into an arena.
+//!
+//! The samples resemble a directory of chunks, each of which is parsed separately.
+//!
+//! Run with `cargo bench --bench arena --features arena`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use bumpalo::Bump;
+use nbtx::{Compound, Value};
+
+const CHUNKS: i32 = 256;
+
+/// Allocator that counts the amount of allocations made through it.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn chunk(x: i32, z: i32) -> Value {
+    let sections = (0..24)
+        .map(|y| {
+            let palette = (0..8)
+                .map(|i| {
+                    Value::Compound(Compound::from_iter([
+                        (
+                            "Name".to_owned(),
+                            Value::String(format!("minecraft:block_{}", (x + y + i) % 64)),
+                        ),
+                        (
+                            "Properties".to_owned(),
+                            Value::Compound(Compound::from_iter([(
+                                "axis".to_owned(),
+                                Value::String("y".to_owned()),
+                            )])),
+                        ),
+                    ]))
+                })
+                .collect();
+
+            Value::Compound(Compound::from_iter([
+                ("Y".to_owned(), Value::Byte(y as i8)),
+                ("palette".to_owned(), Value::List(palette)),
+                ("data".to_owned(), Value::LongArray(vec![y as i64; 64])),
+            ]))
+        })
+        .collect();
+
+    Value::Compound(Compound::from_iter([
+        ("xPos".to_owned(), Value::Int(x)),
+        ("zPos".to_owned(), Value::Int(z)),
+        (
+            "Status".to_owned(),
+            Value::String("minecraft:full".to_owned()),
+        ),
+        ("sections".to_owned(), Value::List(sections)),
+    ]))
+}
+
+fn bench(name: &str, chunks: &[Vec<u8>], mut f: impl FnMut(&[u8])) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for chunk in chunks {
+        f(black_box(chunk));
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    println!(
+        "{name:>6}: {allocations} allocations, {:?} per chunk",
+        elapsed / chunks.len() as u32
+    );
+}
+
+fn main() {
+    let chunks: Vec<_> = (0..CHUNKS)
+        .map(|i| nbtx::to_be_bytes(&chunk(i % 16, i / 16)).unwrap())
+        .collect();
+
+    bench("Value", &chunks, |bytes| {
+        black_box(nbtx::from_be_slice::<Value>(bytes).unwrap());
+    });
+
+    let mut arena = Bump::new();
+    bench("arena", &chunks, |bytes| {
+        black_box(nbtx::arena::from_slice_in::<nbtx::BigEndian>(bytes, &arena).unwrap());
+        arena.reset();
+    });
+}
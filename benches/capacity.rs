@@ -0,0 +1,47 @@
+//! Compares serializing a deeply nested compound with and without pre-sizing the output buffer.
+//!
+//! Run with `cargo bench --bench capacity`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use nbtx::{Compound, LittleEndian, Value};
+
+const ITERATIONS: u32 = 200;
+
+fn sample(depth: usize) -> Value {
+    let mut entries = Compound::from_iter([
+        ("Name".to_owned(), Value::String(format!("level_{depth}"))),
+        ("Data".to_owned(), Value::LongArray(vec![depth as i64; 64])),
+        ("Id".to_owned(), Value::Int(depth as i32)),
+    ]);
+    if depth > 0 {
+        entries.insert("Child".to_owned(), sample(depth - 1));
+        entries.insert("Sibling".to_owned(), sample(depth / 2));
+    }
+
+    Value::Compound(entries)
+}
+
+fn bench(name: &str, value: &Value, f: impl Fn(&Value) -> Vec<u8>) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f(black_box(value)));
+    }
+    let elapsed = start.elapsed();
+
+    println!("{name:>12}: {:?} per document", elapsed / ITERATIONS);
+}
+
+fn main() {
+    let value = sample(12);
+    let len = nbtx::to_le_bytes(&value).unwrap().len();
+    println!("{len} bytes");
+
+    bench("no hint", &value, |value| {
+        nbtx::to_bytes::<LittleEndian>(value).unwrap()
+    });
+    bench("with hint", &value, |value| {
+        nbtx::to_bytes_with_capacity::<LittleEndian>(value, len).unwrap()
+    });
+}
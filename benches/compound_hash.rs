@@ -0,0 +1,60 @@
+//! Measures deserializing compound-heavy data into `Value`s with the hasher that is selected
+//! for `Compound`.
+//!
+//! The sample resembles a chunk with many block entities, each of which is a compound with
+//! short keys. Compare the default hasher against `FxBuildHasher` by running
+//! `cargo bench --bench compound_hash` and `cargo bench --bench compound_hash --features fxhash`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use nbtx::{Compound, CompoundHasher, Value};
+
+const ITERATIONS: u32 = 100;
+
+fn sample() -> Value {
+    let entities = (0..4096)
+        .map(|i| {
+            let items = (0..4)
+                .map(|slot| {
+                    Value::Compound(Compound::from_iter([
+                        ("Slot".to_owned(), Value::Byte(slot)),
+                        ("id".to_owned(), Value::String("minecraft:stone".to_owned())),
+                        ("Count".to_owned(), Value::Byte(64)),
+                    ]))
+                })
+                .collect();
+
+            Value::Compound(Compound::from_iter([
+                ("id".to_owned(), Value::String("minecraft:chest".to_owned())),
+                ("x".to_owned(), Value::Int(i % 16)),
+                ("y".to_owned(), Value::Int(i / 256)),
+                ("z".to_owned(), Value::Int(i / 16 % 16)),
+                ("keepPacked".to_owned(), Value::Byte(0)),
+                ("Items".to_owned(), Value::List(items)),
+            ]))
+        })
+        .collect();
+
+    Value::Compound(Compound::from_iter([
+        ("DataVersion".to_owned(), Value::Int(3953)),
+        ("block_entities".to_owned(), Value::List(entities)),
+    ]))
+}
+
+fn main() {
+    let bytes = nbtx::to_be_bytes(&sample()).unwrap();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(nbtx::from_be_slice::<Value>(black_box(&bytes)).unwrap());
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{}: {:?} per document ({} bytes)",
+        std::any::type_name::<CompoundHasher>(),
+        elapsed / ITERATIONS,
+        bytes.len()
+    );
+}
@@ -0,0 +1,110 @@
+//! Measures deserializing a struct that keeps 2 of the 50 entries of a large compound.
+//!
+//! The remaining entries are ignored, so they should be skipped without allocating. Deserializing
+//! the whole compound into a `Value` is shown for comparison.
+//!
+//! Run with `cargo bench --bench ignored_any`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use nbtx::{Compound, Value};
+use serde::Deserialize;
+
+const ITERATIONS: u32 = 1000;
+
+/// Allocator that counts the amount of allocations made through it.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Header {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    name: String,
+}
+
+fn sample() -> Value {
+    let mut compound = Compound::from_iter([
+        ("DataVersion".to_owned(), Value::Int(3953)),
+        ("name".to_owned(), Value::String("world".to_owned())),
+    ]);
+
+    for i in 0..48 {
+        let value = match i % 4 {
+            0 => Value::String(format!("minecraft:entry_{i}")),
+            1 => Value::ByteArray(vec![i as u8; 1024]),
+            2 => Value::List(
+                (0..16)
+                    .map(|j| {
+                        Value::Compound(Compound::from_iter([
+                            ("id".to_owned(), Value::String(format!("item_{j}"))),
+                            ("Count".to_owned(), Value::Byte(j as i8)),
+                        ]))
+                    })
+                    .collect(),
+            ),
+            _ => Value::Compound(Compound::from_iter([
+                ("data".to_owned(), Value::IntArray(vec![i; 64])),
+                ("label".to_owned(), Value::String("nested".to_owned())),
+            ])),
+        };
+        compound.insert(format!("field_{i}"), value);
+    }
+
+    Value::Compound(compound)
+}
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    println!(
+        "{name:>13}: {} allocations, {:?} per document",
+        allocations / ITERATIONS as usize,
+        elapsed / ITERATIONS
+    );
+}
+
+fn main() {
+    let encoded = nbtx::to_be_bytes(&sample()).unwrap();
+
+    bench("Header, slice", || {
+        black_box(nbtx::from_be_slice::<Header>(black_box(&encoded)).unwrap());
+    });
+    bench("Header, read", || {
+        let mut reader = black_box(encoded.as_slice());
+        black_box(nbtx::from_be_bytes::<Header, _>(&mut reader).unwrap());
+    });
+    bench("Value", || {
+        black_box(nbtx::from_be_slice::<Value>(black_box(&encoded)).unwrap());
+    });
+}
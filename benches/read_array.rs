@@ -0,0 +1,48 @@
+//! Compares deserializing a large long array into a `Vec<i64>`, which decodes each element,
+//! against deserializing it into a `LongArray`, which copies little endian data at once.
+//!
+//! The sample is a 4 MB long array, encoded in the little endian format.
+//!
+//! Run with `cargo bench --bench read_array --features bytemuck`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use nbtx::LongArray;
+use serde::Deserialize;
+
+const ITERATIONS: u32 = 100;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Elements {
+    data: Vec<i64>,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Bulk {
+    data: LongArray,
+}
+
+fn bench<T>(name: &str, bytes: &[u8], f: impl Fn(&[u8]) -> T) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f(black_box(bytes)));
+    }
+    let elapsed = start.elapsed();
+
+    println!("{name:>10}: {:?} per document", elapsed / ITERATIONS);
+}
+
+fn main() {
+    let data = LongArray((0..512 * 1024).collect());
+    let bytes = nbtx::to_le_bytes(&std::collections::HashMap::from([("data", data)])).unwrap();
+
+    bench("Vec<i64>", &bytes, |bytes| {
+        nbtx::from_le_slice::<Elements>(bytes).unwrap()
+    });
+    bench("LongArray", &bytes, |bytes| {
+        nbtx::from_le_slice::<Bulk>(bytes).unwrap()
+    });
+}
@@ -0,0 +1,67 @@
+//! Compares deserializing from a byte slice against deserializing through `std::io::Read`.
+//!
+//! The sample resembles the block palette sent by a Bedrock server, encoded in the network format.
+//!
+//! Run with `cargo bench --bench slice`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use nbtx::{Compound, Value};
+
+const ITERATIONS: u32 = 200;
+
+fn sample() -> Value {
+    let blocks = (0..1024)
+        .map(|i| {
+            Value::Compound(Compound::from_iter([
+                (
+                    "name".to_owned(),
+                    Value::String(format!("minecraft:block_{i}")),
+                ),
+                (
+                    "states".to_owned(),
+                    Value::Compound(Compound::from_iter([
+                        ("facing_direction".to_owned(), Value::Int(i % 6)),
+                        ("open_bit".to_owned(), Value::Byte((i % 2) as i8)),
+                        (
+                            "color".to_owned(),
+                            Value::String(["red", "green", "blue"][i as usize % 3].to_owned()),
+                        ),
+                    ])),
+                ),
+                ("version".to_owned(), Value::Int(18_163_713)),
+            ]))
+        })
+        .collect();
+
+    Value::Compound(Compound::from_iter([(
+        "blocks".to_owned(),
+        Value::List(blocks),
+    )]))
+}
+
+fn bench(name: &str, bytes: &[u8], f: impl Fn(&[u8]) -> Value) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f(black_box(bytes)));
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{name:>12}: {:?} per document ({} bytes)",
+        elapsed / ITERATIONS,
+        bytes.len()
+    );
+}
+
+fn main() {
+    let bytes = nbtx::to_net_bytes(&sample()).unwrap();
+
+    bench("Read", &bytes, |mut bytes| {
+        nbtx::from_net_bytes(&mut bytes).unwrap()
+    });
+    bench("slice", &bytes, |bytes| {
+        nbtx::from_net_slice(bytes).unwrap()
+    });
+}
@@ -0,0 +1,65 @@
+//! Compares the structural validator against deserializing into `serde::de::IgnoredAny`.
+//!
+//! Run with `cargo bench --bench validate`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use nbtx::{Compound, LittleEndian, Value};
+use serde::de::IgnoredAny;
+
+const ITERATIONS: u32 = 200;
+
+fn sample() -> Value {
+    let sections = (0..64)
+        .map(|i| {
+            Value::Compound(Compound::from_iter([
+                ("Y".to_owned(), Value::Byte(i as i8)),
+                ("BlockStates".to_owned(), Value::LongArray(vec![i; 256])),
+                (
+                    "Palette".to_owned(),
+                    Value::List(
+                        (0..16)
+                            .map(|j| {
+                                Value::Compound(Compound::from_iter([(
+                                    "Name".to_owned(),
+                                    Value::String(format!("minecraft:block_{j}")),
+                                )]))
+                            })
+                            .collect(),
+                    ),
+                ),
+            ]))
+        })
+        .collect();
+
+    Value::Compound(Compound::from_iter([
+        ("Sections".to_owned(), Value::List(sections)),
+        ("Biomes".to_owned(), Value::IntArray(vec![1; 1024])),
+    ]))
+}
+
+fn bench(name: &str, bytes: &[u8], f: impl Fn(&mut &[u8])) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f(&mut black_box(bytes));
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{name:>12}: {:?} per document ({} bytes)",
+        elapsed / ITERATIONS,
+        bytes.len()
+    );
+}
+
+fn main() {
+    let bytes = nbtx::to_le_bytes(&sample()).unwrap();
+
+    bench("validate", &bytes, |reader| {
+        nbtx::validate::<LittleEndian, _>(reader).unwrap()
+    });
+    bench("IgnoredAny", &bytes, |reader| {
+        nbtx::from_le_bytes::<IgnoredAny, _>(reader).unwrap();
+    });
+}
@@ -0,0 +1,63 @@
+//! Measures the amount of writes issued when serializing a large array into an unbuffered sink.
+//!
+//! Elements of lists are encoded in bulk, so serializing a 1M-element array results in a few
+//! hundred writes rather than one write per element.
+//!
+//! Run with `cargo bench --bench write_array`.
+
+use std::hint::black_box;
+use std::io::{self, Write};
+use std::time::Instant;
+
+use nbtx::{BigEndian, Compound, Value};
+
+const ITERATIONS: u32 = 20;
+
+/// Writer that simulates an unbuffered sink, such as a file, by counting every write call.
+struct CountingSink {
+    writes: usize,
+}
+
+impl Write for CountingSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes += 1;
+        Ok(black_box(buf).len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn bench(name: &str, value: &Value, f: impl Fn(&mut CountingSink, &Value)) {
+    let mut sink = CountingSink { writes: 0 };
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f(&mut sink, value);
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{name:>12}: {:?} per document ({} writes)",
+        elapsed / ITERATIONS,
+        sink.writes / ITERATIONS as usize
+    );
+}
+
+fn main() {
+    let value = Value::Compound(Compound::from_iter([
+        ("Ints".to_owned(), Value::IntArray((0..1_000_000).collect())),
+        (
+            "Longs".to_owned(),
+            Value::LongArray((0..1_000_000).collect()),
+        ),
+    ]));
+
+    bench("unbuffered", &value, |sink, value| {
+        nbtx::to_bytes_in::<BigEndian>(sink, value).unwrap()
+    });
+    bench("buffered", &value, |sink, value| {
+        nbtx::to_bytes_buffered_in::<BigEndian>(sink, value).unwrap()
+    });
+}
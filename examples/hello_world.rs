@@ -1,10 +1,9 @@
 use byteorder::BigEndian;
-use nbtx::Value;
-use std::collections::HashMap;
+use nbtx::{Compound, Value};
 use std::io::Cursor;
 
 fn main() {
-    let value = Value::Compound(HashMap::from([(
+    let value = Value::Compound(Compound::from_iter([(
         "Hello World".to_string(),
         Value::String("Helloooo World!".to_string()),
     )]));
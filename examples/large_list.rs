@@ -0,0 +1,42 @@
+use std::io::{self, Write};
+
+use nbtx::{FieldType, Serializer};
+
+/// Amount of ints that are generated.
+const LEN: usize = 10_000_000;
+
+/// Writer that discards its input, but records how much data it received.
+#[derive(Default)]
+struct Counter {
+    total: usize,
+    largest_write: usize,
+}
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.total += buf.len();
+        self.largest_write = self.largest_write.max(buf.len());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut ser = Serializer::<_, nbtx::LittleEndian>::new(Counter::default());
+
+    // The elements are generated one at a time, instead of being collected into a `Vec` of 40 MB.
+    let mut list = ser.begin_list(FieldType::Int, LEN).unwrap();
+    for i in 0..LEN {
+        list.write_element(&(i as i32)).unwrap();
+    }
+    list.end_list().unwrap();
+
+    let counter = ser.into_inner().unwrap();
+    println!(
+        "Wrote {} ints in {} bytes, at most {} bytes at a time",
+        LEN, counter.total, counter.largest_write
+    );
+}
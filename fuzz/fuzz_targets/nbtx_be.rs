@@ -5,7 +5,10 @@ use libfuzzer_sys::fuzz_target;
 use nbtx::BigEndian;
 
 fuzz_target!(|data: &[u8]| {
+    // Malformed input must produce an error, never a panic.
     let mut reader = Cursor::new(data);
+    let _ = nbtx::from_bytes::<BigEndian, nbtx::Value>(&mut reader);
 
-    let val: nbtx::Value = nbtx::from_bytes::<BigEndian, _>(&mut reader).unwrap();
+    let _ = nbtx::from_slice::<BigEndian, nbtx::Value>(data);
+    let _ = nbtx::validate::<BigEndian, _>(&mut Cursor::new(data));
 });
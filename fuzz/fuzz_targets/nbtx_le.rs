@@ -5,7 +5,10 @@ use libfuzzer_sys::fuzz_target;
 use nbtx::LittleEndian;
 
 fuzz_target!(|data: &[u8]| {
+    // Malformed input must produce an error, never a panic.
     let mut reader = Cursor::new(data);
+    let _ = nbtx::from_bytes::<LittleEndian, nbtx::Value>(&mut reader);
 
-    let _: nbtx::Value = nbtx::from_bytes::<LittleEndian, _>(&mut reader).unwrap();
+    let _ = nbtx::from_slice::<LittleEndian, nbtx::Value>(data);
+    let _ = nbtx::validate::<LittleEndian, _>(&mut Cursor::new(data));
 });
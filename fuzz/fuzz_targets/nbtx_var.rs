@@ -5,7 +5,10 @@ use libfuzzer_sys::fuzz_target;
 use nbtx::NetworkLittleEndian;
 
 fuzz_target!(|data: &[u8]| {
+    // Malformed input must produce an error, never a panic.
     let mut reader = Cursor::new(data);
+    let _ = nbtx::from_bytes::<NetworkLittleEndian, nbtx::Value>(&mut reader);
 
-    let _: nbtx::Value = nbtx::from_bytes::<NetworkLittleEndian, _>(&mut reader).unwrap();
+    let _ = nbtx::from_slice::<NetworkLittleEndian, nbtx::Value>(data);
+    let _ = nbtx::validate::<NetworkLittleEndian, _>(&mut Cursor::new(data));
 });
@@ -0,0 +1,363 @@
+//! Deserializing NBT into a tree that is allocated in a [`bumpalo`] arena.
+//!
+//! Deserializing many small documents into [`Value`]s allocates every string, list and compound
+//! separately. [`ValueRef`] instead places all of them in a [`Bump`] arena, so that a whole
+//! batch of documents can be freed at once by resetting the arena.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use nbtx::Compound;
+//! use bumpalo::Bump;
+//!
+//! let value = nbtx::Value::Compound(Compound::from_iter([
+//!     ("name".to_owned(), nbtx::Value::String("Steve".to_owned())),
+//! ]));
+//! let encoded = nbtx::to_net_bytes(&value).unwrap();
+//!
+//! let mut arena = Bump::new();
+//! for _ in 0..3 {
+//!     let value = nbtx::arena::from_slice_in::<nbtx::NetworkLittleEndian>(&encoded, &arena)
+//!         .unwrap();
+//!     assert_eq!(value.get("name").and_then(|v| v.as_string()), Some("Steve"));
+//!
+//!     arena.reset();
+//! }
+//! ```
+
+use std::fmt;
+
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+use serde::de::{DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{de, Deserializer, Serialize, Serializer};
+
+use crate::{Compound, EndiannessImpl, NbtError, SliceDeserializer, SliceReader, Value};
+
+/// Largest amount of elements that is reserved up front, since lengths come from the input.
+const MAX_PREALLOCATION: usize = 4096;
+
+/// NBT value whose strings, arrays, lists and compounds are borrowed from an arena.
+///
+/// This mirrors [`Value`], except that compounds are stored as a slice of entries in the
+/// order in which they were read. Create one using [`from_slice_in`] or [`ValueSeed`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueRef<'a> {
+    /// A signed byte.
+    Byte(i8),
+    /// A signed short.
+    Short(i16),
+    /// A signed int.
+    Int(i32),
+    /// A signed long.
+    Long(i64),
+    /// A signed float.
+    Float(f32),
+    /// A signed double.
+    Double(f64),
+    /// A byte array.
+    ByteArray(&'a [u8]),
+    /// A UTF-8 string.
+    String(&'a str),
+    /// A list of values of the same type.
+    List(&'a [ValueRef<'a>]),
+    /// The entries of a compound, in the order in which they were read.
+    Compound(&'a [(&'a str, ValueRef<'a>)]),
+    /// An array of integers.
+    ///
+    /// Int arrays are deserialized as lists, so this is only used for values built manually.
+    IntArray(&'a [i32]),
+    /// An array of longs.
+    ///
+    /// Long arrays are deserialized as lists, so this is only used for values built manually.
+    LongArray(&'a [i64]),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Returns the value of the given compound entry, if this is a compound that contains it.
+    ///
+    /// Entries are searched linearly.
+    pub fn get(&self, key: &str) -> Option<&'a ValueRef<'a>> {
+        match self {
+            ValueRef::Compound(entries) => entries.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Returns the string, if this is a string.
+    #[inline]
+    pub fn as_string(&self) -> Option<&'a str> {
+        match self {
+            ValueRef::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements, if this is a list.
+    #[inline]
+    pub fn as_list(&self) -> Option<&'a [ValueRef<'a>]> {
+        match self {
+            ValueRef::List(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries, if this is a compound.
+    #[inline]
+    pub fn as_compound(&self) -> Option<&'a [(&'a str, ValueRef<'a>)]> {
+        match self {
+            ValueRef::Compound(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Copies the value out of the arena into an owned [`Value`].
+    pub fn to_value(&self) -> Value {
+        match *self {
+            ValueRef::Byte(v) => Value::Byte(v),
+            ValueRef::Short(v) => Value::Short(v),
+            ValueRef::Int(v) => Value::Int(v),
+            ValueRef::Long(v) => Value::Long(v),
+            ValueRef::Float(v) => Value::Float(v),
+            ValueRef::Double(v) => Value::Double(v),
+            ValueRef::ByteArray(v) => Value::ByteArray(v.to_vec()),
+            ValueRef::String(v) => Value::String(v.to_owned()),
+            ValueRef::List(v) => Value::List(v.iter().map(ValueRef::to_value).collect()),
+            ValueRef::Compound(v) => Value::Compound(
+                v.iter()
+                    .map(|(k, v)| ((*k).to_owned(), v.to_value()))
+                    .collect::<Compound>(),
+            ),
+            ValueRef::IntArray(v) => Value::IntArray(v.to_vec()),
+            ValueRef::LongArray(v) => Value::LongArray(v.to_vec()),
+        }
+    }
+}
+
+#[inline]
+fn serialize_seq<T, S>(ser: S, seq: &[T]) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    let mut seq_ser = ser.serialize_seq(Some(seq.len()))?;
+    for element in seq {
+        seq_ser.serialize_element(element)?;
+    }
+    seq_ser.end()
+}
+
+impl Serialize for ValueRef<'_> {
+    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            ValueRef::Byte(byte) => ser.serialize_i8(*byte),
+            ValueRef::Short(short) => ser.serialize_i16(*short),
+            ValueRef::Int(int) => ser.serialize_i32(*int),
+            ValueRef::Long(long) => ser.serialize_i64(*long),
+            ValueRef::Float(float) => ser.serialize_f32(*float),
+            ValueRef::Double(double) => ser.serialize_f64(*double),
+            ValueRef::ByteArray(array) => ser.serialize_bytes(array),
+            ValueRef::String(string) => ser.serialize_str(string),
+            ValueRef::List(seq) => serialize_seq(ser, seq),
+            ValueRef::Compound(entries) => {
+                let mut map_ser = ser.serialize_map(Some(entries.len()))?;
+                for (k, v) in *entries {
+                    map_ser.serialize_entry(k, v)?;
+                }
+                map_ser.end()
+            }
+            ValueRef::IntArray(seq) => serialize_seq(ser, seq),
+            ValueRef::LongArray(seq) => serialize_seq(ser, seq),
+        }
+    }
+}
+
+/// Deserializes a [`ValueRef`] into the given arena.
+///
+/// This can be used with any deserializer through [`DeserializeSeed`].
+#[derive(Debug, Clone, Copy)]
+pub struct ValueSeed<'a>(pub &'a Bump);
+
+impl<'de, 'a> DeserializeSeed<'de> for ValueSeed<'a> {
+    type Value = ValueRef<'a>;
+
+    #[inline]
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ValueSeed<'a> {
+    type Value = ValueRef<'a>;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid NBT value")
+    }
+
+    #[inline]
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Byte(v as i8))
+    }
+
+    #[inline]
+    fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Byte(v))
+    }
+
+    #[inline]
+    fn visit_i16<E>(self, v: i16) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Short(v))
+    }
+
+    #[inline]
+    fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Int(v))
+    }
+
+    #[inline]
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Long(v))
+    }
+
+    #[inline]
+    fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Float(v))
+    }
+
+    #[inline]
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::Double(v))
+    }
+
+    #[inline]
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::String(self.0.alloc_str(v)))
+    }
+
+    #[inline]
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ValueRef::ByteArray(self.0.alloc_slice_copy(v)))
+    }
+
+    #[inline]
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_bytes(&v)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let hint = seq.size_hint().unwrap_or(0).min(MAX_PREALLOCATION);
+        let mut out = BumpVec::with_capacity_in(hint, self.0);
+
+        while let Some(element) = seq.next_element_seed(self)? {
+            out.push(element);
+        }
+
+        Ok(ValueRef::List(out.into_bump_slice()))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let hint = map.size_hint().unwrap_or(0).min(MAX_PREALLOCATION);
+        let mut out = BumpVec::with_capacity_in(hint, self.0);
+
+        while let Some(key) = map.next_key_seed(KeySeed(self.0))? {
+            let value = map.next_value_seed(self)?;
+            out.push((key, value));
+        }
+
+        Ok(ValueRef::Compound(out.into_bump_slice()))
+    }
+}
+
+/// Deserializes a compound key into the arena.
+#[derive(Clone, Copy)]
+struct KeySeed<'a>(&'a Bump);
+
+impl<'de, 'a> DeserializeSeed<'de> for KeySeed<'a> {
+    type Value = &'a str;
+
+    #[inline]
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for KeySeed<'a> {
+    type Value = &'a str;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a compound key")
+    }
+
+    #[inline]
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.0.alloc_str(v))
+    }
+}
+
+/// Reads a single value from the given slice into `arena`.
+///
+/// Unlike [`from_slice`](crate::from_slice), the returned value does not borrow from `data`,
+/// so the input buffer can be reused while the value is alive.
+pub fn from_slice_in<'a, F>(data: &[u8], arena: &'a Bump) -> Result<ValueRef<'a>, NbtError>
+where
+    F: EndiannessImpl,
+{
+    let mut reader = SliceReader::new(data);
+    let mut deserializer = SliceDeserializer::<F>::new(&mut reader)?;
+    let output = ValueSeed(arena)
+        .deserialize(&mut deserializer)
+        .map_err(|e| deserializer.locate(e))?;
+
+    Ok(output)
+}
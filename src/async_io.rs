@@ -0,0 +1,98 @@
+use std::future::poll_fn;
+use std::io::{self, ErrorKind};
+use std::pin::Pin;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::{from_net_slice, to_net_bytes, NbtError};
+
+/// Size of the chunks that are read from the reader at a time.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Serializes the given data in network little endian format and writes it to the async
+/// `writer`.
+///
+/// Streaming is coarse-grained: the data is fully encoded into memory first, like
+/// [`to_net_bytes`], and then written and flushed. Nothing is written if serialization fails.
+///
+/// # Example
+///
+/// ```rust
+/// # #[derive(serde::Serialize)]
+/// # struct Data {
+/// #     value: String,
+/// # }
+/// # let data = Data { value: "Hello, World!".to_owned() };
+/// # let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # rt.block_on(async {
+/// let mut socket = Vec::new();
+/// nbtx::to_net_bytes_async(&mut socket, &data).await.unwrap();
+///
+/// assert_eq!(socket, nbtx::to_net_bytes(&data).unwrap());
+/// # });
+/// ```
+pub async fn to_net_bytes_async<T, W>(writer: &mut W, v: &T) -> Result<(), NbtError>
+where
+    W: AsyncWrite + Unpin,
+    T: ?Sized + Serialize,
+{
+    let encoded = to_net_bytes(v)?;
+
+    let mut written = 0;
+    while written < encoded.len() {
+        let n = poll_fn(|cx| Pin::new(&mut *writer).poll_write(cx, &encoded[written..])).await?;
+        if n == 0 {
+            return Err(io::Error::from(ErrorKind::WriteZero).into());
+        }
+        written += n;
+    }
+    poll_fn(|cx| Pin::new(&mut *writer).poll_flush(cx)).await?;
+
+    Ok(())
+}
+
+/// Reads a single object of type `T` in network little endian format from the async `reader`.
+///
+/// Streaming is coarse-grained: the reader is read until it reaches the end of the stream, and
+/// the buffered data is then deserialized using [`from_net_slice`]. Any data after the root
+/// compound is ignored. To read a value from a connection that stays open, read the frame that
+/// contains it first and pass that to [`from_net_slice`] instead.
+///
+/// # Example
+///
+/// ```rust
+/// # #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+/// # struct Data {
+/// #     value: String,
+/// # }
+/// # let data = Data { value: "Hello, World!".to_owned() };
+/// # let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # rt.block_on(async {
+/// let encoded = nbtx::to_net_bytes(&data).unwrap();
+///
+/// let mut reader = encoded.as_slice();
+/// let decoded: Data = nbtx::from_net_bytes_async(&mut reader).await.unwrap();
+///
+/// assert_eq!(decoded, data);
+/// # });
+/// ```
+pub async fn from_net_bytes_async<T, R>(reader: &mut R) -> Result<T, NbtError>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut data = Vec::new();
+    let mut chunk = [0; CHUNK_SIZE];
+    loop {
+        let mut buf = ReadBuf::new(&mut chunk);
+        poll_fn(|cx| Pin::new(&mut *reader).poll_read(cx, &mut buf)).await?;
+        if buf.filled().is_empty() {
+            break;
+        }
+        data.extend_from_slice(buf.filled());
+    }
+
+    from_net_slice(&data)
+}
@@ -0,0 +1,69 @@
+use std::io::Read;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde::Deserialize;
+
+use crate::{from_bytes, EndiannessImpl, FieldType, NbtError};
+
+/// First two bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// First byte of a zlib stream that uses deflate with the default window size.
+const ZLIB_MAGIC: u8 = 0x78;
+
+/// Reads a single object of type `T` from the given reader, which may be compressed.
+///
+/// The compression is detected from the first bytes of the data:
+///
+/// - `1f 8b` is decompressed as gzip, which Java Edition uses for `level.dat` and structure
+///   files.
+/// - `78` is decompressed as zlib, which Java Edition uses for chunks in region files.
+/// - `0a`, the tag type of a compound, is read as uncompressed data.
+///
+/// Any other data returns [`NbtError::UnknownCompression`]. Since only a root compound is
+/// detected as uncompressed data, other root values must be read using [`from_bytes`].
+///
+/// # Example
+///
+/// ```rust
+/// # use std::io::Write;
+/// # use flate2::{write::GzEncoder, Compression};
+/// # #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+/// # struct Data {
+/// #     value: String,
+/// # }
+/// # let data = Data { value: "Hello, World!".to_owned() };
+/// let encoded = nbtx::to_be_bytes(&data).unwrap();
+///
+/// let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+/// encoder.write_all(&encoded).unwrap();
+/// let compressed = encoder.finish().unwrap();
+///
+/// for input in [encoded, compressed] {
+///     let decoded: Data =
+///         nbtx::from_reader_auto::<nbtx::BigEndian, _, _>(input.as_slice()).unwrap();
+///     assert_eq!(decoded, data);
+/// }
+/// ```
+pub fn from_reader_auto<'de, F, T, R>(mut reader: R) -> Result<T, NbtError>
+where
+    R: Read,
+    T: Deserialize<'de>,
+    F: EndiannessImpl + 'de,
+{
+    let mut magic = [0; 2];
+    reader.read_exact(&mut magic[..1])?;
+
+    match magic[0] {
+        b if b == GZIP_MAGIC[0] => {
+            reader.read_exact(&mut magic[1..])?;
+            if magic != GZIP_MAGIC {
+                return Err(NbtError::UnknownCompression(magic[0]));
+            }
+
+            from_bytes::<F, T>(&mut GzDecoder::new(magic.chain(reader)))
+        }
+        ZLIB_MAGIC => from_bytes::<F, T>(&mut ZlibDecoder::new(magic[..1].chain(reader))),
+        b if b == FieldType::Compound as u8 => from_bytes::<F, T>(&mut magic[..1].chain(reader)),
+        b => Err(NbtError::UnknownCompression(b)),
+    }
+}
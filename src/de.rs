@@ -1,13 +1,24 @@
 use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::{self, Write};
+use std::io::{self, Read};
 use std::marker::PhantomData;
 
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use paste::paste;
-use serde::de::{DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::de::value::{BorrowedStrDeserializer, StrDeserializer};
+use serde::de::{DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
 use serde::{de, Deserialize};
-use varint_rs::VarintReader;
 
-use crate::{EndiannessImpl, FieldType, NbtError, NetworkLittleEndian, Variant};
+use crate::read::sealed::Reference;
+use crate::ser::LEVELDAT_HEADER_LEN;
+#[cfg(feature = "heapless")]
+use crate::serde_helpers::heapless_string::HEAPLESS_STRING_TOKEN;
+use crate::wrapper::{INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN};
+use crate::{
+    BoolTag, ByteArrayLenWidth, Compound, EndiannessImpl, FieldType, NbtError, NbtRead,
+    NetworkLittleEndian, SliceReader, StreamError, StringLenWidth, TagMask, Value, Variant,
+};
 
 /// Verifies that the deserialized type is equal to the expected type.
 macro_rules! is_ty {
@@ -38,54 +49,1099 @@ macro_rules! forward_unsupported {
     }
 }
 
+/// Reader that keeps track of the amount of bytes consumed from the wrapped reader.
+#[derive(Debug)]
+pub(crate) struct PositionReader<'re, R> {
+    inner: &'re mut R,
+    offset: u64,
+    /// Largest amount of bytes that may be read in total.
+    limit: u64,
+}
+
+impl<'re, R> PositionReader<'re, R> {
+    #[inline]
+    pub(crate) fn new(inner: &'re mut R) -> Self {
+        Self {
+            inner,
+            offset: 0,
+            limit: u64::MAX,
+        }
+    }
+
+    /// Returns the amount of bytes that have been read so far.
+    #[inline]
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Fails if reading another `len` bytes would exceed the limit.
+    #[inline]
+    fn check_limit(&self, len: usize) -> Result<(), NbtError> {
+        if self.offset.saturating_add(len as u64) > self.limit {
+            return Err(NbtError::ByteLimitExceeded { max: self.limit });
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> Read for PositionReader<'_, R>
+where
+    R: Read,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+/// Generates methods that read a fixed-size number in the given byte order.
+macro_rules! read_number {
+    ($($ty: ident),+) => {
+        paste! {$(
+            #[inline]
+            pub(crate) fn [<read_ $ty>]<B: ByteOrder>(&mut self) -> Result<$ty, NbtError> {
+                let buf = self.read_array::<{ core::mem::size_of::<$ty>() }>()?;
+                Ok(B::[<read_ $ty>](&buf))
+            }
+        )+}
+    }
+}
+
+impl<'de, R> PositionReader<'_, R>
+where
+    R: NbtRead<'de>,
+{
+    /// Fills the entire buffer with data from the input.
+    #[inline]
+    pub(crate) fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), NbtError> {
+        self.check_limit(buf.len())?;
+        self.inner.read_into(buf)?;
+        self.offset += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Reads `len` bytes, borrowing them from the input if possible.
+    #[inline]
+    pub(crate) fn read_slice<'s>(
+        &'s mut self,
+        len: usize,
+        scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's>, NbtError> {
+        self.check_limit(len)?;
+        let data = self.inner.read_slice(len, scratch)?;
+        self.offset += len as u64;
+        Ok(data)
+    }
+
+    /// Discards the next `len` bytes.
+    #[inline]
+    pub(crate) fn skip(&mut self, len: usize) -> Result<(), NbtError> {
+        self.check_limit(len)?;
+        self.inner.skip(len)?;
+        self.offset += len as u64;
+        Ok(())
+    }
+
+    #[inline]
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], NbtError> {
+        let mut buf = [0; N];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    #[inline]
+    pub(crate) fn read_u8(&mut self) -> Result<u8, NbtError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    #[inline]
+    pub(crate) fn read_i8(&mut self) -> Result<i8, NbtError> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    read_number!(u16, u32, i16, i32, i64, f32, f64);
+
+    /// Reads an unsigned LEB128 varint of at most `max_bits` bits.
+    ///
+    /// The last byte that can occur may only contain the remaining bits and no continuation
+    /// bit, so that varints with more bits than the type has are rejected rather than truncated.
+    fn read_varint(&mut self, max_bits: u32) -> Result<u64, NbtError> {
+        let mut value = 0u64;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.read_u8()?;
+            if shift + 7 >= max_bits && byte as u64 >> (max_bits - shift) != 0 {
+                return Err(NbtError::VarintTooLong { bits: max_bits });
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    #[inline]
+    pub(crate) fn read_u32_varint(&mut self) -> Result<u32, NbtError> {
+        Ok(self.read_varint(u32::BITS)? as u32)
+    }
+
+    #[inline]
+    pub(crate) fn read_i32_varint(&mut self) -> Result<i32, NbtError> {
+        let n = self.read_u32_varint()?;
+        Ok((n >> 1) as i32 ^ -((n & 1) as i32))
+    }
+
+    #[inline]
+    pub(crate) fn read_i64_varint(&mut self) -> Result<i64, NbtError> {
+        let n = self.read_varint(u64::BITS)?;
+        Ok((n >> 1) as i64 ^ -((n & 1) as i64))
+    }
+}
+
+/// Converts an unknown tag error for the type of the root value into
+/// [`NbtError::LikelyWrongVariant`].
+///
+/// The root tag is the first byte of the input, so an unknown tag there usually means that the
+/// data is not uncompressed NBT of the expected variant.
+pub(crate) fn wrong_variant<F: EndiannessImpl>(err: NbtError) -> NbtError {
+    match err {
+        NbtError::UnknownTag(byte) => NbtError::LikelyWrongVariant {
+            byte,
+            variant: F::AS_ENUM,
+        },
+        err => err,
+    }
+}
+
+/// Appends a compound key to the given path.
+///
+/// This takes the fields of the deserializer separately, so that the key can be borrowed from
+/// its scratch buffer.
+fn push_key(path: &mut String, path_marks: &mut Vec<usize>, key: &str) {
+    path_marks.push(path.len());
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(key);
+}
+
+/// Decodes a string, replacing invalid UTF-8 with `U+FFFD` if `lenient` is set.
+#[inline]
+fn decode_utf8(data: &[u8], lenient: bool) -> Result<Cow<'_, str>, NbtError> {
+    if lenient {
+        Ok(String::from_utf8_lossy(data))
+    } else {
+        Ok(Cow::Borrowed(std::str::from_utf8(data)?))
+    }
+}
+
+/// Determines what happens when a compound contains the same key more than once.
+///
+/// This applies to every compound, regardless of the type that it is deserialized into.
+/// Compounds within ignored values, such as unknown fields of a struct, are skipped without
+/// being checked, except with [`Error`](Self::Error), which decodes ignored values so that they
+/// are checked as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DuplicateKeyPolicy {
+    /// Keeps the first entry with the key and skips any later entries.
+    First,
+    /// Passes every entry to the target type, which usually keeps the last one.
+    ///
+    /// Structs derived with serde reject duplicate fields regardless of this policy.
+    #[default]
+    Last,
+    /// Returns [`NbtError::DuplicateKey`] when a key occurs more than once.
+    Error,
+}
+
+/// Maximum amount of nested lists and compounds, matching the limit used by Minecraft.
+///
+/// This prevents malicious input from overflowing the stack.
+pub(crate) const MAX_DEPTH: usize = 512;
+
+/// Limits and policies of a [`Deserializer`], grouped so that they can be applied at once.
+///
+/// The default options accept any well-formed input, like a deserializer without any options
+/// set. Use [`hardened`](Self::hardened) for input from untrusted sources, and apply the options
+/// with [`Deserializer::with_options`] or [`from_bytes_with`].
+///
+/// # Example
+///
+/// ```rust
+/// use nbtx::{DeserializerOptions, DuplicateKeyPolicy, Value};
+///
+/// let options = DeserializerOptions::hardened()
+///     .with_max_bytes(1024)
+///     .with_duplicate_key_policy(DuplicateKeyPolicy::First);
+///
+/// let encoded = nbtx::to_be_bytes(&vec![1i8; 2048]).unwrap();
+/// assert!(nbtx::from_bytes_with::<nbtx::BigEndian, Value>(options, &encoded).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeserializerOptions {
+    max_depth: usize,
+    max_bytes: u64,
+    max_list_len: u32,
+    duplicate_key_policy: DuplicateKeyPolicy,
+    allowed_tags: TagMask,
+}
+
+impl Default for DeserializerOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeserializerOptions {
+    /// Creates the default options, which only limit the nesting depth to 512 levels.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            max_depth: MAX_DEPTH,
+            max_bytes: u64::MAX,
+            max_list_len: u32::MAX,
+            duplicate_key_policy: DuplicateKeyPolicy::Last,
+            allowed_tags: TagMask::ALL,
+        }
+    }
+
+    /// Creates options that are suitable for input from untrusted sources.
+    ///
+    /// These mirror the limits that Minecraft applies to NBT received over the network: input
+    /// is limited to 2 MiB and nesting to 512 levels. Lists and arrays may declare at most
+    /// 65536 elements, and compounds with repeated keys are rejected.
+    ///
+    /// Every check also applies to ignored values, such as unknown fields of a struct: they are
+    /// decoded rather than skipped, so their compounds are checked for repeated keys and their
+    /// strings for valid UTF-8. Whether input is rejected therefore does not depend on the
+    /// fields of the target type.
+    #[inline]
+    pub const fn hardened() -> Self {
+        Self {
+            max_depth: MAX_DEPTH,
+            max_bytes: 2 * 1024 * 1024,
+            max_list_len: 1 << 16,
+            duplicate_key_policy: DuplicateKeyPolicy::Error,
+            allowed_tags: TagMask::ALL,
+        }
+    }
+
+    /// Sets the maximum amount of nested lists and compounds.
+    ///
+    /// This cannot be raised above the default of 512, since deeper nesting could overflow the
+    /// stack.
+    #[inline]
+    pub const fn with_max_depth(mut self, max: usize) -> Self {
+        self.max_depth = if max < MAX_DEPTH { max } else { MAX_DEPTH };
+        self
+    }
+
+    /// Sets the maximum amount of bytes that are read from the input, including the root
+    /// header. Reading past it fails with [`NbtError::ByteLimitExceeded`].
+    #[inline]
+    pub const fn with_max_bytes(mut self, max: u64) -> Self {
+        self.max_bytes = max;
+        self
+    }
+
+    /// Sets the maximum amount of elements that a single list or array may declare.
+    ///
+    /// See [`Deserializer::with_max_list_len`].
+    #[inline]
+    pub const fn with_max_list_len(mut self, max: u32) -> Self {
+        self.max_list_len = max;
+        self
+    }
+
+    /// Sets how compounds with repeated keys are handled.
+    ///
+    /// See [`Deserializer::with_duplicate_key_policy`].
+    #[inline]
+    pub const fn with_duplicate_key_policy(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_key_policy = policy;
+        self
+    }
+
+    /// Sets the tag types that may appear below the root value.
+    ///
+    /// See [`Deserializer::with_allowed_tags`].
+    #[inline]
+    pub fn with_allowed_tags(mut self, tags: impl Into<TagMask>) -> Self {
+        self.allowed_tags = tags.into();
+        self
+    }
+}
+
 /// NBT deserializer.
+///
+/// Malformed input, such as invalid tag types, negative lengths or truncated data, results in
+/// an error rather than a panic. Lists and compounds can be nested at most 512 levels deep.
+/// Stricter limits can be set using [`DeserializerOptions`].
 #[derive(Debug)]
 pub struct Deserializer<'re, 'de, F, R>
 where
-    R: ReadBytesExt,
+    R: NbtRead<'de>,
     F: EndiannessImpl + 'de,
 {
-    input: &'re mut R,
+    input: PositionReader<'re, R>,
     next_ty: FieldType,
-    is_key: bool,
+    /// Path to the value that is currently being deserialized.
+    path: String,
+    /// Lengths of `path` before each of the currently open segments was pushed.
+    path_marks: Vec<usize>,
+    /// Amount of entries of the root compound that have been fully read.
+    entries: usize,
+    /// Name of the root value.
+    root_name: String,
+    /// Buffer that strings are copied into when they cannot be borrowed from the input.
+    scratch: Vec<u8>,
+    /// Amount of lists and compounds that are currently open.
+    depth: usize,
+    /// Maximum amount of nested lists and compounds.
+    max_depth: usize,
+    unknown_field_handler: Option<UnknownFieldHandler<'re>>,
+    /// Skipped entries of the root compound, if they should be collected.
+    extras: Option<Compound>,
+    /// Whether invalid UTF-8 in strings is replaced rather than rejected.
+    lenient_utf8: bool,
+    /// How compounds with repeated keys are handled.
+    duplicate_key_policy: DuplicateKeyPolicy,
+    /// Tag types that may appear in the input.
+    allowed_tags: TagMask,
+    /// Width of the length prefix of strings in the big and little endian variants.
+    string_len_width: StringLenWidth,
+    /// Width of the length prefix of byte arrays in the big and little endian variants.
+    byte_array_len_width: ByteArrayLenWidth,
+    /// Largest amount of elements that a single list or array may declare.
+    max_list_len: u32,
+    /// Tag types that have been read so far, apart from end tags.
+    observed_tags: TagMask,
+    /// Tag that booleans are read from.
+    bool_tag: BoolTag,
     _marker: PhantomData<&'de F>,
 }
 
+/// Callback that receives the path and value of fields that are not part of the target type.
+type UnknownFieldFn<'re> = dyn FnMut(&str, &Value) + 're;
+
+struct UnknownFieldHandler<'re>(Box<UnknownFieldFn<'re>>);
+
+impl fmt::Debug for UnknownFieldHandler<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UnknownFieldHandler")
+    }
+}
+
+/// Deserializer that reads directly from a byte slice.
+///
+/// See [`SliceReader`] and [`from_slice`].
+pub type SliceDeserializer<'re, 'de, F> = Deserializer<'re, 'de, F, SliceReader<'de>>;
+
 impl<'re, 'de, F, R> Deserializer<'re, 'de, F, R>
 where
-    R: ReadBytesExt,
+    R: NbtRead<'de>,
     F: EndiannessImpl + 'de,
 {
     /// Creates a new deserializer, consuming the reader.
     pub fn new(input: &'re mut R) -> Result<Self, NbtError> {
-        let next_ty = FieldType::try_from(input.read_u8()?)?;
-        if next_ty != FieldType::Compound {
-            return Err(NbtError::UnexpectedType {
-                actual: next_ty,
-                expected: FieldType::Compound,
-            });
-        }
+        let mut de = Self::with_type(input, FieldType::End);
+
+        de.read_root_header().map_err(|e| e.at(de.offset()))?;
+        Ok(de)
+    }
+
+    /// Creates a new deserializer for data whose strings use a nonstandard length prefix,
+    /// consuming the reader.
+    ///
+    /// Strings, compound keys and the root name are read with a prefix of the given width. This
+    /// is set when the deserializer is created, since [`new`](Self::new) already reads the root
+    /// name. It has no effect on the network variant, which always uses varints.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// use nbtx::{StringLenWidth, Value};
+    ///
+    /// // Compound containing the byte `a`, with strings prefixed by a single byte.
+    /// let encoded = [10, 0, 1, 1, b'a', 1, 0];
+    ///
+    /// let mut input = encoded.as_slice();
+    /// let mut de = nbtx::Deserializer::<nbtx::BigEndian, _>::new_with_string_len_width(
+    ///     &mut input,
+    ///     StringLenWidth::U8,
+    /// )
+    /// .unwrap();
+    /// let value = Value::deserialize(&mut de).unwrap();
+    ///
+    /// assert_eq!(value.as_compound().unwrap()["a"], Value::Byte(1));
+    /// ```
+    pub fn new_with_string_len_width(
+        input: &'re mut R,
+        width: StringLenWidth,
+    ) -> Result<Self, NbtError> {
+        let mut de = Self::with_type(input, FieldType::End);
+        de.string_len_width = width;
+
+        de.read_root_header().map_err(|e| e.at(de.offset()))?;
+        Ok(de)
+    }
 
-        let de = Deserializer {
-            input,
+    /// Creates a deserializer that expects a value of the given type, without a root header.
+    fn with_type(input: &'re mut R, next_ty: FieldType) -> Self {
+        Deserializer {
+            input: PositionReader::new(input),
             next_ty,
-            is_key: false,
+            path: String::new(),
+            path_marks: Vec::new(),
+            entries: 0,
+            root_name: String::new(),
+            scratch: Vec::new(),
+            depth: 0,
+            max_depth: MAX_DEPTH,
+            unknown_field_handler: None,
+            extras: None,
+            lenient_utf8: false,
+            duplicate_key_policy: DuplicateKeyPolicy::Last,
+            allowed_tags: TagMask::ALL,
+            string_len_width: StringLenWidth::U16,
+            byte_array_len_width: ByteArrayLenWidth::I32,
+            max_list_len: u32::MAX,
+            observed_tags: TagMask::NONE,
+            bool_tag: BoolTag::Byte,
             _marker: PhantomData,
+        }
+    }
+
+    /// Reads a tag type byte, checking that the tag is allowed.
+    #[inline]
+    fn read_tag(&mut self) -> Result<FieldType, NbtError> {
+        let ty = FieldType::try_from(self.input.read_u8()?)?;
+        if !self.allowed_tags.contains(ty) {
+            return Err(NbtError::ForbiddenTag(ty));
+        }
+        if ty != FieldType::End {
+            self.observed_tags.insert(ty);
+        }
+
+        Ok(ty)
+    }
+
+    /// Reads the type of the next compound entry, which is an end tag if the compound is
+    /// complete.
+    ///
+    /// Running out of input here means that the compound was never closed, so this returns
+    /// [`NbtError::MissingEndTag`] rather than an end of file error.
+    fn read_entry_tag(&mut self) -> Result<FieldType, NbtError> {
+        self.read_tag().map_err(|e| {
+            if is_eof(&e) {
+                NbtError::MissingEndTag
+            } else {
+                e
+            }
+        })
+    }
+
+    /// Reads the type and name of the root value.
+    ///
+    /// The root is usually a compound, but any other tag is accepted as well.
+    fn read_root_header(&mut self) -> Result<(), NbtError> {
+        let next_ty = self.read_tag().map_err(wrong_variant::<F>)?;
+        if next_ty == FieldType::End {
+            return Err(NbtError::Other(Cow::Borrowed(
+                "Encountered end tag in place of the root value",
+            )));
+        }
+        self.next_ty = next_ty;
+
+        self.root_name = self.read_string()?;
+        Ok(())
+    }
+
+    /// Calls `handler` for every field that is skipped because the target type does not have it,
+    /// such as unknown keys of a struct. The handler receives the path to the field and its
+    /// decoded value.
+    ///
+    /// This is useful to discover data that is not yet modelled by a schema.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::Compound;
+    /// # use serde::Deserialize;
+    /// #[derive(Deserialize)]
+    /// struct Player {
+    ///     name: String,
+    /// }
+    ///
+    /// let value = nbtx::Value::Compound(Compound::from_iter([
+    ///     ("name".to_owned(), nbtx::Value::String("Steve".to_owned())),
+    ///     ("health".to_owned(), nbtx::Value::Float(20.0)),
+    /// ]));
+    /// let encoded = nbtx::to_be_bytes(&value).unwrap();
+    ///
+    /// let mut unknown = Vec::new();
+    /// let mut input = encoded.as_slice();
+    /// let mut de = nbtx::Deserializer::<nbtx::BigEndian, _>::new(&mut input)
+    ///     .unwrap()
+    ///     .with_unknown_field_handler(|path, _| unknown.push(path.to_owned()));
+    /// Player::deserialize(&mut de).unwrap();
+    /// drop(de);
+    ///
+    /// assert_eq!(unknown, ["health"]);
+    /// ```
+    #[inline]
+    pub fn with_unknown_field_handler(mut self, handler: impl FnMut(&str, &Value) + 're) -> Self {
+        self.unknown_field_handler = Some(UnknownFieldHandler(Box::new(handler)));
+        self
+    }
+
+    /// Sets whether strings containing invalid UTF-8, such as lone surrogates, are accepted.
+    ///
+    /// When enabled, invalid sequences are replaced with `U+FFFD` instead of causing an error.
+    /// This applies to both string values and compound keys. Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// #[derive(Deserialize)]
+    /// struct Player {
+    ///     name: String,
+    /// }
+    ///
+    /// // Compound with a `name` containing a lone surrogate.
+    /// let encoded = [10, 0, 8, 4, b'n', b'a', b'm', b'e', 4, b'S', 0xed, 0xa0, 0x80, 0];
+    /// assert!(nbtx::from_net_slice::<Player>(&encoded).is_err());
+    ///
+    /// let mut input = encoded.as_slice();
+    /// let mut de = nbtx::Deserializer::<nbtx::NetworkLittleEndian, _>::new(&mut input)
+    ///     .unwrap()
+    ///     .with_lenient_utf8(true);
+    /// let player = Player::deserialize(&mut de).unwrap();
+    ///
+    /// assert_eq!(player.name, "S\u{fffd}\u{fffd}\u{fffd}");
+    /// ```
+    #[inline]
+    pub fn with_lenient_utf8(mut self, lenient: bool) -> Self {
+        self.lenient_utf8 = lenient;
+        self
+    }
+
+    /// Sets how compounds that contain the same key more than once are handled.
+    ///
+    /// Defaults to [`DuplicateKeyPolicy::Last`], which leaves the decision to the target type.
+    /// Maps and [`Value`] keep the last entry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// use nbtx::{DuplicateKeyPolicy, NbtError, Value};
+    ///
+    /// // Compound that contains the byte `a` twice.
+    /// let encoded = [10, 0, 0, 1, 0, 1, b'a', 1, 1, 0, 1, b'a', 2, 0];
+    ///
+    /// let mut input = encoded.as_slice();
+    /// let mut de = nbtx::Deserializer::<nbtx::BigEndian, _>::new(&mut input)
+    ///     .unwrap()
+    ///     .with_duplicate_key_policy(DuplicateKeyPolicy::Error);
+    /// let err = Value::deserialize(&mut de).unwrap_err();
+    ///
+    /// assert!(matches!(err.kind(), NbtError::DuplicateKey(key) if key == "a"));
+    /// ```
+    #[inline]
+    pub fn with_duplicate_key_policy(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_key_policy = policy;
+        self
+    }
+
+    /// Restricts the tag types that may appear below the root value, including list element
+    /// types and values that are skipped.
+    ///
+    /// Encountering any other tag returns [`NbtError::ForbiddenTag`]. The end tag that closes
+    /// compounds is always allowed. By default, all tags are allowed.
+    ///
+    /// The type of the root value is read by [`new`](Self::new), so it is not checked.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// # use std::collections::HashMap;
+    /// use nbtx::{FieldType, Int128, NbtError, TagMask, Value};
+    ///
+    /// // `Int128` is written as a long array.
+    /// let value = HashMap::from([("data", Int128(7))]);
+    /// let encoded = nbtx::to_be_bytes(&value).unwrap();
+    ///
+    /// let mut input = encoded.as_slice();
+    /// let mut de = nbtx::Deserializer::<nbtx::BigEndian, _>::new(&mut input)
+    ///     .unwrap()
+    ///     .with_allowed_tags(TagMask::ALL.without(FieldType::LongArray));
+    /// let err = Value::deserialize(&mut de).unwrap_err();
+    ///
+    /// assert!(matches!(err.kind(), NbtError::ForbiddenTag(FieldType::LongArray)));
+    /// ```
+    #[inline]
+    pub fn with_allowed_tags(mut self, tags: impl Into<TagMask>) -> Self {
+        self.allowed_tags = tags.into().with(FieldType::End);
+        self
+    }
+
+    /// Limits the amount of elements that a single list or array may declare.
+    ///
+    /// A list of empty compounds takes a single byte per element, so a small input can declare
+    /// billions of elements and exhaust memory while they are collected. Longer lists and arrays
+    /// fail with [`NbtError::ListTooLong`] as soon as their length is read, before any memory is
+    /// reserved for their elements. This applies to skipped values as well. Unlimited by
+    /// default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// use nbtx::{NbtError, Value};
+    ///
+    /// let encoded = nbtx::to_be_bytes(&vec![1i8; 100]).unwrap();
+    ///
+    /// let mut input = encoded.as_slice();
+    /// let mut de = nbtx::Deserializer::<nbtx::BigEndian, _>::new(&mut input)
+    ///     .unwrap()
+    ///     .with_max_list_len(64);
+    /// let err = Value::deserialize(&mut de).unwrap_err();
+    ///
+    /// assert!(matches!(err.kind(), NbtError::ListTooLong { declared: 100, max: 64 }));
+    /// ```
+    #[inline]
+    pub fn with_max_list_len(mut self, max: u32) -> Self {
+        self.max_list_len = max;
+        self
+    }
+
+    /// Sets the width of the length prefix of byte arrays.
+    ///
+    /// Defaults to [`ByteArrayLenWidth::I32`], as required by the NBT specification. This has no
+    /// effect on the network variant, which always reads lengths as varints.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// use nbtx::{ByteArrayLenWidth, Value};
+    ///
+    /// let mut input: &[u8] = &[7, 0, 0, 0, 2, 1, 2];
+    /// let mut de = nbtx::Deserializer::<nbtx::BigEndian, _>::new(&mut input)
+    ///     .unwrap()
+    ///     .with_byte_array_len_width(ByteArrayLenWidth::I16);
+    ///
+    /// assert_eq!(Value::deserialize(&mut de).unwrap(), Value::ByteArray(vec![1, 2]));
+    /// ```
+    #[inline]
+    pub fn with_byte_array_len_width(mut self, width: ByteArrayLenWidth) -> Self {
+        self.byte_array_len_width = width;
+        self
+    }
+
+    /// Sets the tag that booleans are read from.
+    ///
+    /// Defaults to [`BoolTag::Byte`]. This matches
+    /// [`Serializer::with_bool_tag`](crate::Serializer::with_bool_tag). Booleans stored as
+    /// any other tag fail with [`NbtError::UnexpectedType`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// use nbtx::BoolTag;
+    ///
+    /// let mut input: &[u8] = &[2, 0, 0, 0, 1];
+    /// let mut de = nbtx::Deserializer::<nbtx::BigEndian, _>::new(&mut input)
+    ///     .unwrap()
+    ///     .with_bool_tag(BoolTag::Short);
+    ///
+    /// assert!(bool::deserialize(&mut de).unwrap());
+    /// ```
+    #[inline]
+    pub fn with_bool_tag(mut self, tag: BoolTag) -> Self {
+        self.bool_tag = tag;
+        self
+    }
+
+    /// Applies all limits and policies of the given options, replacing any that were set
+    /// before.
+    ///
+    /// The byte limit includes the root header, which has already been read by
+    /// [`new`](Self::new).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// use nbtx::{DeserializerOptions, NbtError, Value};
+    ///
+    /// let encoded = nbtx::to_be_bytes(&vec![1i8; 100_000]).unwrap();
+    ///
+    /// let mut input = encoded.as_slice();
+    /// let mut de = nbtx::Deserializer::<nbtx::BigEndian, _>::new(&mut input)
+    ///     .unwrap()
+    ///     .with_options(DeserializerOptions::hardened());
+    /// let err = Value::deserialize(&mut de).unwrap_err();
+    ///
+    /// assert!(matches!(err.kind(), NbtError::ListTooLong { .. }));
+    /// ```
+    pub fn with_options(mut self, options: DeserializerOptions) -> Self {
+        self.max_depth = options.max_depth;
+        self.input.limit = options.max_bytes;
+        self.max_list_len = options.max_list_len;
+        self.duplicate_key_policy = options.duplicate_key_policy;
+        self.with_allowed_tags(options.allowed_tags)
+    }
+
+    /// Returns the tag types that have been read so far, including the root and the element
+    /// types of lists. End tags are not included.
+    ///
+    /// Tags of skipped values are included as well, but int and long arrays do not add the
+    /// type of their elements.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// use nbtx::{FieldType, TagMask, Value};
+    ///
+    /// let encoded = nbtx::to_be_bytes(&Value::List(vec![Value::Int(1)])).unwrap();
+    ///
+    /// let mut input = encoded.as_slice();
+    /// let mut de = nbtx::Deserializer::<nbtx::BigEndian, _>::new(&mut input).unwrap();
+    /// Value::deserialize(&mut de).unwrap();
+    ///
+    /// assert_eq!(de.observed_tags(), TagMask::from([FieldType::List, FieldType::Int]));
+    /// ```
+    #[inline]
+    pub fn observed_tags(&self) -> TagMask {
+        self.observed_tags
+    }
+
+    /// Returns the amount of bytes that have been read from the input so far.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.input.offset()
+    }
+
+    /// Returns the path to the value that is currently being deserialized,
+    /// such as `Level.Sections[2].Palette`.
+    ///
+    /// After a failed deserialization, this is the path of the value that caused the error.
+    #[inline]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the name of the root value, which is empty if no header was read.
+    ///
+    /// The name is not part of the deserialized value, so this is the only way to access it.
+    #[inline]
+    pub fn root_name(&self) -> &str {
+        &self.root_name
+    }
+
+    /// Returns the amount of entries of the root compound that have been fully read so far.
+    #[inline]
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// Attaches the current offset, path and progress to the given error.
+    #[inline]
+    pub fn locate(&self, error: NbtError) -> NbtError {
+        let mut error = error.at_path(self.offset(), self.path.as_str());
+        if let NbtError::At { entries, .. } = &mut error {
+            *entries = self.entries;
+        }
+
+        error
+    }
+
+    /// Skips the value the deserializer is positioned at, without decoding or allocating it.
+    ///
+    /// After [`new`](Self::new), this is the root value, so the reader is advanced to the
+    /// end of it. This allows skipping over values in a stream of consecutive NBT values, such
+    /// as the payload of a network packet.
+    ///
+    /// Strings are not checked for valid UTF-8.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::Compound;
+    /// let first = nbtx::Value::Compound(Compound::from_iter([
+    ///     ("data".to_owned(), nbtx::Value::List(vec![nbtx::Value::Int(1); 100])),
+    /// ]));
+    /// let second = nbtx::Value::Compound(Compound::from_iter([
+    ///     ("id".to_owned(), nbtx::Value::Int(7)),
+    /// ]));
+    ///
+    /// let mut encoded = nbtx::to_be_bytes(&first).unwrap();
+    /// encoded.extend(nbtx::to_be_bytes(&second).unwrap());
+    ///
+    /// let mut input = encoded.as_slice();
+    /// nbtx::Deserializer::<nbtx::BigEndian, _>::new(&mut input)
+    ///     .unwrap()
+    ///     .skip_value()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(nbtx::from_be_bytes::<nbtx::Value, _>(&mut input).unwrap(), second);
+    /// ```
+    pub fn skip_value(&mut self) -> Result<(), NbtError> {
+        let ty = std::mem::replace(&mut self.next_ty, FieldType::End);
+        self.skip_payload(ty).map_err(|e| self.locate(e))
+    }
+
+    /// Skips over the payload of a tag of the given type.
+    fn skip_payload(&mut self, ty: FieldType) -> Result<(), NbtError> {
+        match ty {
+            FieldType::End => Err(NbtError::Other(Cow::Borrowed(
+                "Encountered unmatched end tag",
+            ))),
+            FieldType::Byte => self.input.skip(1),
+            FieldType::Short => self.input.skip(2),
+            FieldType::Int => match F::AS_ENUM {
+                Variant::NetworkEndian => self.input.read_i32_varint().map(drop),
+                _ => self.input.skip(4),
+            },
+            FieldType::Long => match F::AS_ENUM {
+                Variant::NetworkEndian => self.input.read_i64_varint().map(drop),
+                _ => self.input.skip(8),
+            },
+            FieldType::Float => self.input.skip(4),
+            FieldType::Double => self.input.skip(8),
+            FieldType::ByteArray => {
+                let len = self.read_byte_array_len()?;
+                self.input.skip(len as usize)
+            }
+            FieldType::IntArray | FieldType::LongArray => {
+                let len = self.read_len()?;
+                let (ty, width) = if ty == FieldType::IntArray {
+                    (FieldType::Int, 4)
+                } else {
+                    (FieldType::Long, 8)
+                };
+                if !F::AS_ENUM.is_varint() {
+                    return self.input.skip(array_size(len, width)?);
+                }
+
+                // Varints have to be read one by one to find their end.
+                for _ in 0..len {
+                    self.skip_payload(ty)?;
+                }
+                Ok(())
+            }
+            FieldType::String => {
+                let len = self.read_str_len()?;
+                self.input.skip(len)
+            }
+            FieldType::List => {
+                self.enter()?;
+                let ty = self.read_tag()?;
+                let len = self.read_len()?;
+                if ty == FieldType::End && len > 0 {
+                    return Err(NbtError::InvalidListType { len });
+                }
+
+                for _ in 0..len {
+                    self.skip_payload(ty)?;
+                }
+
+                self.depth -= 1;
+                Ok(())
+            }
+            FieldType::Compound => {
+                self.enter()?;
+                loop {
+                    let ty = self.read_entry_tag()?;
+                    if ty == FieldType::End {
+                        self.depth -= 1;
+                        return Ok(());
+                    }
+
+                    self.skip_payload(FieldType::String)?;
+                    self.skip_payload(ty)?;
+                }
+            }
+        }
+    }
+
+    /// Reads a length-prefixed string, borrowing it from the input if possible.
+    fn read_str(&mut self) -> Result<Reference<'de, '_>, NbtError> {
+        let len = self.read_str_len()?;
+        self.input.read_slice(len, &mut self.scratch)
+    }
+
+    /// Reads the length prefix of a string.
+    #[inline]
+    fn read_str_len(&mut self) -> Result<usize, NbtError> {
+        let len = match (F::AS_ENUM, self.string_len_width) {
+            (Variant::NetworkEndian, _) => self.input.read_u32_varint()?,
+            (_, StringLenWidth::U8) => self.input.read_u8()? as u32,
+            (Variant::BigEndian, StringLenWidth::U16) => self.input.read_u16::<BigEndian>()? as u32,
+            (_, StringLenWidth::U16) => self.input.read_u16::<LittleEndian>()? as u32,
+            (Variant::BigEndian, StringLenWidth::U32) => self.input.read_u32::<BigEndian>()?,
+            (_, StringLenWidth::U32) => self.input.read_u32::<LittleEndian>()?,
         };
 
-        // Ignore name of root component
+        Ok(len as usize)
+    }
+
+    /// Reads a length-prefixed string.
+    fn read_string(&mut self) -> Result<String, NbtError> {
+        let lenient = self.lenient_utf8;
+        let data = self.read_str()?;
+        Ok(decode_utf8(data.as_slice(), lenient)?.into_owned())
+    }
+
+    /// Reads the length prefix of an array or list.
+    fn read_len(&mut self) -> Result<u32, NbtError> {
         let len = match F::AS_ENUM {
-            Variant::BigEndian => de.input.read_u16::<BigEndian>()? as u32,
-            Variant::LittleEndian => de.input.read_u16::<LittleEndian>()? as u32,
-            Variant::NetworkEndian => de.input.read_u32_varint()?,
+            Variant::BigEndian => self.input.read_i32::<BigEndian>()?,
+            Variant::LittleEndian => self.input.read_i32::<LittleEndian>()?,
+            Variant::NetworkEndian => self.input.read_i32_varint()?,
         };
 
-        let mut buf = vec![0; len as usize];
-        de.input.read_exact(&mut buf)?;
+        self.check_len(len)
+    }
 
-        let _name = String::from_utf8(buf)?;
+    /// Reads the length prefix of a byte array.
+    fn read_byte_array_len(&mut self) -> Result<u32, NbtError> {
+        let len = match (F::AS_ENUM, self.byte_array_len_width) {
+            (Variant::NetworkEndian, _) | (_, ByteArrayLenWidth::I32) => return self.read_len(),
+            (Variant::BigEndian, ByteArrayLenWidth::I16) => self.input.read_i16::<BigEndian>()?,
+            (_, ByteArrayLenWidth::I16) => self.input.read_i16::<LittleEndian>()?,
+        };
 
-        Ok(de)
+        self.check_len(len as i32)
+    }
+
+    /// Checks a declared sequence length against the configured limit.
+    fn check_len(&self, len: i32) -> Result<u32, NbtError> {
+        let len = u32::try_from(len).map_err(|_| NbtError::NegativeLength { len })?;
+        if len > self.max_list_len {
+            return Err(NbtError::ListTooLong {
+                declared: len,
+                max: self.max_list_len,
+            });
+        }
+
+        Ok(len)
+    }
+
+    /// Reads the elements of an array at once and passes them to the visitor.
+    fn deserialize_array<V>(
+        &mut self,
+        expected_len: Option<usize>,
+        visitor: V,
+    ) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        let (ty, width) = match self.next_ty {
+            FieldType::IntArray => (FieldType::Int, 4),
+            FieldType::LongArray => (FieldType::Long, 8),
+            _ => (FieldType::Byte, 1),
+        };
+
+        let len = if ty == FieldType::Byte {
+            self.read_byte_array_len()?
+        } else {
+            self.read_len()?
+        };
+        check_seq_len(expected_len, len)?;
+
+        let data = match self
+            .input
+            .read_slice(array_size(len, width)?, &mut self.scratch)?
+        {
+            Reference::Borrowed(data) => data,
+            Reference::Copied(data) => data,
+        };
+
+        visitor.visit_seq(ArrayDeserializer::<F> {
+            data,
+            ty,
+            width,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Deserializes a list or array, verifying its length if `expected_len` is set.
+    ///
+    /// Byte arrays, and int and long arrays in formats without varints, are read into memory at
+    /// once. Their elements can be deserialized into any integer type that can hold them, so
+    /// `TAG_Byte_Array` can be read into a `Vec<u8>`.
+    fn deserialize_list<V>(
+        &mut self,
+        expected_len: Option<usize>,
+        visitor: V,
+    ) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        let bulk = match self.next_ty {
+            FieldType::ByteArray => true,
+            FieldType::IntArray | FieldType::LongArray => !F::AS_ENUM.is_varint(),
+            _ => false,
+        };
+        if bulk {
+            return self.deserialize_array(expected_len, visitor);
+        }
+
+        let ty = match self.next_ty {
+            FieldType::ByteArray => FieldType::Byte,
+            FieldType::IntArray => FieldType::Int,
+            FieldType::LongArray => FieldType::Long,
+            _ => self.read_tag()?,
+        };
+
+        self.enter()?;
+        let de = SeqDeserializer::new(&mut *self, ty, expected_len)?;
+        let output = visitor.visit_seq(de)?;
+        self.depth -= 1;
+
+        Ok(output)
+    }
+
+    /// Enters a list or compound, failing if this exceeds the maximum depth.
+    #[inline]
+    fn enter(&mut self) -> Result<(), NbtError> {
+        if self.depth >= self.max_depth {
+            return Err(NbtError::Other(Cow::Borrowed(
+                "Exceeded maximum nesting depth of lists and compounds",
+            )));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Appends a list index to the current path.
+    fn push_index(&mut self, index: u32) {
+        self.path_marks.push(self.path.len());
+        // Writing to a string cannot fail.
+        let _ = write!(self.path, "[{index}]");
+    }
+
+    /// Removes the last segment from the current path.
+    fn pop_path(&mut self) {
+        if let Some(mark) = self.path_marks.pop() {
+            self.path.truncate(mark);
+        }
     }
 }
 
@@ -93,17 +1149,67 @@ where
 ///
 /// On success, the deserialized object and number of bytes read from the buffer are returned.
 #[inline]
-pub fn from_bytes<'de, 're, F, T>(reader: &'re mut impl ReadBytesExt) -> Result<T, NbtError>
+pub fn from_bytes<'de, 're, F, T>(reader: &'re mut impl Read) -> Result<T, NbtError>
 where
     T: Deserialize<'de>,
     F: EndiannessImpl + 'de,
 {
     let mut deserializer = Deserializer::<F, _>::new(reader)?;
-    let output = T::deserialize(&mut deserializer)?;
+    let output = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
 
     Ok(output)
 }
 
+/// Reads a single object of type `T` from the given buffer, failing if any data follows it.
+///
+/// Unlike [`from_bytes`], which leaves any bytes after the root value in the reader, this reads
+/// the reader to its end and returns [`NbtError::TrailingData`] if it was not empty. This helps
+/// to catch framing errors, such as a length that is off by one. Since the reader has to end,
+/// this is not suited for connections that stay open.
+#[inline]
+pub fn from_bytes_exact<'de, F, T>(reader: &mut impl Read) -> Result<T, NbtError>
+where
+    T: Deserialize<'de>,
+    F: EndiannessImpl + 'de,
+{
+    let output = from_bytes::<F, T>(reader)?;
+
+    let remaining = std::io::copy(reader, &mut std::io::sink())?;
+    if remaining != 0 {
+        return Err(NbtError::TrailingData { remaining });
+    }
+
+    Ok(output)
+}
+
+/// Reads a single object of type `T` from the given buffer, using the variant that is selected
+/// at runtime.
+///
+/// This is equivalent to calling [`from_bytes`] with the type matching `variant`.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::Compound;
+/// # use nbtx::{Value, Variant};
+///  let value = Value::Compound(Compound::from_iter([("x".to_owned(), Value::Int(5))]));
+///  let encoded = nbtx::to_bytes_dyn(Variant::BigEndian, &value).unwrap();
+///
+///  let decoded: Value = nbtx::from_bytes_dyn(Variant::BigEndian, &mut encoded.as_slice()).unwrap();
+///  assert_eq!(decoded, value);
+/// ```
+#[inline]
+pub fn from_bytes_dyn<'de, T>(variant: Variant, reader: &mut impl Read) -> Result<T, NbtError>
+where
+    T: Deserialize<'de>,
+{
+    match variant {
+        Variant::BigEndian => from_bytes::<BigEndian, T>(reader),
+        Variant::LittleEndian => from_bytes::<LittleEndian, T>(reader),
+        Variant::NetworkEndian => from_bytes::<NetworkLittleEndian, T>(reader),
+    }
+}
+
 /// Reads a single object of type `T` from the given buffer.
 ///
 /// This function uses the little endian format of NBT, which is used by disk formats
@@ -135,7 +1241,7 @@ where
 #[inline]
 pub fn from_le_bytes<'de, T, R>(reader: &mut R) -> Result<T, NbtError>
 where
-    R: ReadBytesExt,
+    R: Read,
     T: Deserialize<'de>,
 {
     from_bytes::<LittleEndian, T>(reader)
@@ -172,7 +1278,7 @@ where
 #[inline]
 pub fn from_be_bytes<'de, T, R>(reader: &mut R) -> Result<T, NbtError>
 where
-    R: ReadBytesExt,
+    R: Read,
     T: Deserialize<'de>,
 {
     from_bytes::<BigEndian, T>(reader)
@@ -209,45 +1315,340 @@ where
 #[inline]
 pub fn from_net_bytes<'data, T, R>(reader: &mut R) -> Result<T, NbtError>
 where
-    R: ReadBytesExt,
+    R: Read,
     T: Deserialize<'data>,
 {
     from_bytes::<NetworkLittleEndian, T>(reader)
 }
 
+/// Reads a single object of type `T` from the given buffer using the little endian format,
+/// failing if any data follows it.
+///
+/// See [`from_bytes_exact`] for more information.
+#[inline]
+pub fn from_le_bytes_exact<'de, T, R>(reader: &mut R) -> Result<T, NbtError>
+where
+    R: Read,
+    T: Deserialize<'de>,
+{
+    from_bytes_exact::<LittleEndian, T>(reader)
+}
+
+/// Reads a single object of type `T` from the given buffer using the big endian format,
+/// failing if any data follows it.
+///
+/// See [`from_bytes_exact`] for more information.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::{Compound, NbtError, Value};
+///  let value = Value::Compound(Compound::from_iter([("x".to_owned(), Value::Int(5))]));
+///  let mut encoded = nbtx::to_be_bytes(&value).unwrap();
+///  encoded.push(0);
+///
+///  let err = nbtx::from_be_bytes_exact::<Value, _>(&mut encoded.as_slice()).unwrap_err();
+///  assert!(matches!(err, NbtError::TrailingData { remaining: 1 }));
+///
+///  // The lenient variant ignores the trailing byte.
+///  let decoded: Value = nbtx::from_be_bytes(&mut encoded.as_slice()).unwrap();
+///  assert_eq!(decoded, value);
+/// ```
+#[inline]
+pub fn from_be_bytes_exact<'de, T, R>(reader: &mut R) -> Result<T, NbtError>
+where
+    R: Read,
+    T: Deserialize<'de>,
+{
+    from_bytes_exact::<BigEndian, T>(reader)
+}
+
+/// Reads a single object of type `T` from the given buffer using the network format, failing
+/// if any data follows it.
+///
+/// See [`from_bytes_exact`] for more information.
+#[inline]
+pub fn from_net_bytes_exact<'de, T, R>(reader: &mut R) -> Result<T, NbtError>
+where
+    R: Read,
+    T: Deserialize<'de>,
+{
+    from_bytes_exact::<NetworkLittleEndian, T>(reader)
+}
+
+/// Reads a single object of type `T` directly from the given slice.
+///
+/// This is faster than reading from a [`Read`] implementation and allows `T` to borrow
+/// strings and byte arrays from `data`. Any data after the root compound is ignored.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx as nbt;
+/// # fn main() {
+///  #[derive(serde::Serialize, serde::Deserialize, Debug)]
+///  struct Data<'a> {
+///     value: &'a str
+///  }
+///
+/// # let data = Data { value: "Hello, World!" };
+/// # let buffer = nbt::to_net_bytes(&data).unwrap();
+///  let data: Data = nbt::from_slice::<nbt::NetworkLittleEndian, _>(&buffer).unwrap();
+///
+///  println!("Got {data:?}!");
+/// # }
+/// ```
+pub fn from_slice<'de, F, T>(data: &'de [u8]) -> Result<T, NbtError>
+where
+    T: Deserialize<'de>,
+    F: EndiannessImpl + 'de,
+{
+    let mut reader = SliceReader::new(data);
+    let mut deserializer = SliceDeserializer::<F>::new(&mut reader)?;
+    let output = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+
+    Ok(output)
+}
+
+/// Reads a single object of type `T` from the given slice, applying the given limits and
+/// policies.
+///
+/// Like [`from_slice`], strings and byte arrays can be borrowed from `data`. See
+/// [`DeserializerOptions::hardened`] for options that are suitable for untrusted input.
+///
+/// # Example
+///
+/// ```rust
+/// use nbtx::{DeserializerOptions, Value};
+///
+/// let encoded = nbtx::to_net_bytes(&Value::Int(5)).unwrap();
+/// let value: Value =
+///     nbtx::from_bytes_with::<nbtx::NetworkLittleEndian, _>(DeserializerOptions::hardened(), &encoded)
+///         .unwrap();
+///
+/// assert_eq!(value, Value::Int(5));
+/// ```
+pub fn from_bytes_with<'de, F, T>(
+    options: DeserializerOptions,
+    data: &'de [u8],
+) -> Result<T, NbtError>
+where
+    T: Deserialize<'de>,
+    F: EndiannessImpl + 'de,
+{
+    let mut reader = SliceReader::new(data);
+    let mut deserializer = SliceDeserializer::<F>::new(&mut reader)?.with_options(options);
+    let output = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+
+    Ok(output)
+}
+
+/// Reads a single object of type `T` from the given slice, along with every entry of the root
+/// compound that `T` does not have a field for.
+///
+/// This allows data that is not modelled by `T` to be preserved, similar to a
+/// `#[serde(flatten)]` map, but without modifying `T`. Unknown fields of nested compounds are
+/// not collected.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::Compound;
+/// # use nbtx::Value;
+///  #[derive(serde::Deserialize)]
+///  struct Player {
+///     name: String,
+///  }
+///
+///  let value = Value::Compound(Compound::from_iter([
+///     ("name".to_owned(), Value::String("Steve".to_owned())),
+///     ("health".to_owned(), Value::Float(20.0)),
+///  ]));
+///  let encoded = nbtx::to_le_bytes(&value).unwrap();
+///
+///  let (player, extras) = nbtx::from_bytes_with_extras::<nbtx::LittleEndian, Player>(&encoded).unwrap();
+///  assert_eq!(player.name, "Steve");
+///  assert_eq!(extras["health"], Value::Float(20.0));
+/// ```
+pub fn from_bytes_with_extras<'de, F, T>(data: &'de [u8]) -> Result<(T, Compound), NbtError>
+where
+    T: Deserialize<'de>,
+    F: EndiannessImpl + 'de,
+{
+    let mut reader = SliceReader::new(data);
+    let mut deserializer = SliceDeserializer::<F>::new(&mut reader)?;
+    deserializer.extras = Some(Compound::default());
+
+    let output = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+    let extras = deserializer.extras.take().unwrap_or_default();
+
+    Ok((output, extras))
+}
+
+/// Reads a single object of type `T` directly from the given slice, using the little endian
+/// format of NBT.
+///
+/// See [`from_slice`] for more information.
+#[inline]
+pub fn from_le_slice<'de, T>(data: &'de [u8]) -> Result<T, NbtError>
+where
+    T: Deserialize<'de>,
+{
+    from_slice::<LittleEndian, T>(data)
+}
+
+/// Reads a single object of type `T` directly from the given slice, using the big endian
+/// format of NBT.
+///
+/// See [`from_slice`] for more information.
+#[inline]
+pub fn from_be_slice<'de, T>(data: &'de [u8]) -> Result<T, NbtError>
+where
+    T: Deserialize<'de>,
+{
+    from_slice::<BigEndian, T>(data)
+}
+
+/// Reads a single object of type `T` directly from the given slice, using the network
+/// format of NBT.
+///
+/// See [`from_slice`] for more information.
+#[inline]
+pub fn from_net_slice<'de, T>(data: &'de [u8]) -> Result<T, NbtError>
+where
+    T: Deserialize<'de>,
+{
+    from_slice::<NetworkLittleEndian, T>(data)
+}
+
+/// Reads a single object of type `T` from the start of the given slice, returning it along with
+/// the amount of bytes that it occupied.
+///
+/// This is useful when the NBT data is followed by other data, such as further NBT values or the
+/// remaining fields of a packet. The caller can advance past the returned length to continue
+/// reading.
+pub fn from_slice_prefix<'de, F, T>(data: &'de [u8]) -> Result<(T, usize), NbtError>
+where
+    T: Deserialize<'de>,
+    F: EndiannessImpl + 'de,
+{
+    let mut reader = SliceReader::new(data);
+    let mut deserializer = SliceDeserializer::<F>::new(&mut reader)?;
+    let output = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+    drop(deserializer);
+
+    Ok((output, reader.position()))
+}
+
+/// Reads a single object of type `T` from the start of the given slice using the network format
+/// of NBT, returning it along with the amount of bytes that it occupied.
+///
+/// See [`from_slice_prefix`] for more information.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::{Compound, Value};
+///  let value = Value::Compound(Compound::from_iter([("x".to_owned(), Value::Int(5))]));
+///  let mut packet = nbtx::to_net_bytes(&value).unwrap();
+///  packet.extend([0xff, 0xff]);
+///
+///  let (decoded, len) = nbtx::from_net_bytes_prefix::<Value>(&packet).unwrap();
+///  assert_eq!(decoded, value);
+///  assert_eq!(&packet[len..], [0xff, 0xff]);
+/// ```
+#[inline]
+pub fn from_net_bytes_prefix<'de, T>(data: &'de [u8]) -> Result<(T, usize), NbtError>
+where
+    T: Deserialize<'de>,
+{
+    from_slice_prefix::<NetworkLittleEndian, T>(data)
+}
+
+/// Reads a Bedrock `level.dat` file, returning its storage version and the deserialized data.
+///
+/// The file starts with an 8-byte header containing the storage version and the length of the
+/// NBT data, both as little endian ints, followed by the data in the little endian format.
+/// Fails with [`NbtError::FrameLengthMismatch`] if the declared length differs from the size of
+/// the data after the header or from the size of the NBT value that was read.
+///
+/// See [`to_bedrock_leveldat`](crate::to_bedrock_leveldat) for the reverse.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::Value;
+/// // Header of a `level.dat` with storage version 10, followed by an empty compound.
+/// let data = [10, 0, 0, 0, 4, 0, 0, 0, 10, 0, 0, 0];
+///
+/// let (version, level) = nbtx::from_bedrock_leveldat::<Value>(&data).unwrap();
+/// assert_eq!(version, 10);
+/// assert!(level.as_compound().unwrap().is_empty());
+/// ```
+pub fn from_bedrock_leveldat<'de, T>(data: &'de [u8]) -> Result<(i32, T), NbtError>
+where
+    T: Deserialize<'de>,
+{
+    let header = data
+        .get(..LEVELDAT_HEADER_LEN)
+        .ok_or(StreamError::UnexpectedEof {
+            expected: LEVELDAT_HEADER_LEN,
+            remaining: data.len(),
+        })?;
+    let version = LittleEndian::read_i32(&header[..4]);
+    let len = LittleEndian::read_i32(&header[4..]);
+    let declared = u32::try_from(len).map_err(|_| NbtError::NegativeLength { len })?;
+
+    let payload = &data[LEVELDAT_HEADER_LEN..];
+    if payload.len() != declared as usize {
+        return Err(NbtError::FrameLengthMismatch {
+            declared,
+            actual: payload.len(),
+        });
+    }
+
+    let (value, actual) = from_slice_prefix::<LittleEndian, T>(payload)?;
+    if actual != payload.len() {
+        return Err(NbtError::FrameLengthMismatch { declared, actual });
+    }
+
+    Ok((version, value))
+}
+
 impl<'de, 'a, F, R> de::Deserializer<'de> for &'a mut Deserializer<'_, 'de, F, R>
 where
-    R: ReadBytesExt,
+    R: NbtRead<'de>,
     F: EndiannessImpl + 'a,
 {
     type Error = NbtError;
 
     forward_unsupported!(char, u8, u16, u32, u64, i128, u128);
 
+    /// Deserializes the next value based on its tag type.
+    ///
+    /// This is also used by serde to buffer values for `#[serde(untagged)]` enums.
+    /// NBT has no boolean type, so `bool` fields are buffered as bytes and cannot be
+    /// deserialized from such a buffer. Use an integer field instead.
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, NbtError>
     where
         V: Visitor<'de>,
     {
-        if self.is_key {
-            self.deserialize_string(visitor)
-        } else {
-            match self.next_ty {
-                FieldType::End => Err(NbtError::Other(Cow::Borrowed(
-                    "Encountered unmatched end tag",
-                ))),
-                FieldType::Byte => self.deserialize_i8(visitor),
-                FieldType::Short => self.deserialize_i16(visitor),
-                FieldType::Int => self.deserialize_i32(visitor),
-                FieldType::Long => self.deserialize_i64(visitor),
-                FieldType::Float => self.deserialize_f32(visitor),
-                FieldType::Double => self.deserialize_f64(visitor),
-                FieldType::ByteArray => self.deserialize_byte_buf(visitor),
-                FieldType::String => self.deserialize_string(visitor),
-                FieldType::List => self.deserialize_seq(visitor),
-                FieldType::Compound => self.deserialize_map(visitor),
-                FieldType::IntArray => self.deserialize_seq(visitor),
-                FieldType::LongArray => self.deserialize_seq(visitor),
-            }
+        match self.next_ty {
+            FieldType::End => Err(NbtError::Other(Cow::Borrowed(
+                "Encountered unmatched end tag",
+            ))),
+            FieldType::Byte => self.deserialize_i8(visitor),
+            FieldType::Short => self.deserialize_i16(visitor),
+            FieldType::Int => self.deserialize_i32(visitor),
+            FieldType::Long => self.deserialize_i64(visitor),
+            FieldType::Float => self.deserialize_f32(visitor),
+            FieldType::Double => self.deserialize_f64(visitor),
+            FieldType::ByteArray => self.deserialize_byte_buf(visitor),
+            FieldType::String => self.deserialize_str(visitor),
+            FieldType::List => self.deserialize_seq(visitor),
+            FieldType::Compound => self.deserialize_map(visitor),
+            FieldType::IntArray => self.deserialize_seq(visitor),
+            FieldType::LongArray => self.deserialize_seq(visitor),
         }
     }
 
@@ -256,9 +1657,20 @@ where
     where
         V: Visitor<'de>,
     {
-        is_ty!(Byte, self.next_ty);
+        let expected = self.bool_tag.field_type();
+        if self.next_ty != expected {
+            return Err(NbtError::UnexpectedType {
+                expected,
+                actual: self.next_ty,
+            });
+        }
 
-        let n = self.input.read_u8()? != 0;
+        // Booleans are written as 0 and 1, but any other nonzero value is read as `true` as well.
+        let n = match (self.bool_tag, F::AS_ENUM) {
+            (BoolTag::Short, Variant::BigEndian) => self.input.read_i16::<BigEndian>()? != 0,
+            (BoolTag::Short, _) => self.input.read_i16::<LittleEndian>()? != 0,
+            (BoolTag::Byte, _) => self.input.read_u8()? != 0,
+        };
         visitor.visit_bool(n)
     }
 
@@ -350,14 +1762,27 @@ where
         visitor.visit_f64(n)
     }
 
+    /// Deserializes a string.
+    ///
+    /// When reading from a [`SliceReader`], the string is borrowed from the input.
     #[inline]
-    fn deserialize_str<V>(self, _visitor: V) -> Result<V::Value, NbtError>
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, NbtError>
     where
         V: Visitor<'de>,
     {
-        Err(NbtError::Unsupported(
-            "Deserializing string references is not supported",
-        ))
+        is_ty!(String, self.next_ty);
+
+        let lenient = self.lenient_utf8;
+        match self.read_str()? {
+            Reference::Borrowed(data) => match decode_utf8(data, lenient)? {
+                Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+            Reference::Copied(data) => match decode_utf8(data, lenient)? {
+                Cow::Borrowed(s) => visitor.visit_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+        }
     }
 
     #[inline]
@@ -367,41 +1792,25 @@ where
     {
         is_ty!(String, self.next_ty);
 
-        let len = match F::AS_ENUM {
-            Variant::BigEndian => self.input.read_u16::<BigEndian>()? as u32,
-            Variant::LittleEndian => self.input.read_u16::<LittleEndian>()? as u32,
-            Variant::NetworkEndian => self.input.read_u32_varint()?,
-        };
-
-        let mut buf = vec![0; len as usize];
-        self.input.read_exact(&mut buf)?;
-
-        let string = String::from_utf8(buf)?;
+        let string = self.read_string()?;
         visitor.visit_string(string)
     }
 
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value, NbtError>
+    /// Deserializes a byte array.
+    ///
+    /// When reading from a [`SliceReader`], the array is borrowed from the input.
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, NbtError>
     where
         V: Visitor<'de>,
     {
-        Err(NbtError::Unsupported(
-            "Deserializing byte slices is not supported",
-        ))
-
-        // is_ty!(ByteArray, self.next_ty);
-
-        // let len = match F::AS_ENUM {
-        //     Variant::BigEndian => self.input.read_i32::<BigEndian>()? as u32,
-        //     Variant::LittleEndian => self.input.read_i32::<LittleEndian>()? as u32,
-        //     Variant::NetworkEndian => self.input.read_i32_varint()? as u32,
-        // };
+        is_ty!(ByteArray, self.next_ty);
 
-        // // let mut buf = Vec::with_capacity(len as usize);
-        // // self.input.read_exact(&mut buf)?;
-        // //
-        // todo!("Obtain slice from cursor directly without copying to heap");
+        let len = self.read_byte_array_len()?;
 
-        // // visitor.visit_bytes(&buf)
+        match self.input.read_slice(len as usize, &mut self.scratch)? {
+            Reference::Borrowed(data) => visitor.visit_borrowed_bytes(data),
+            Reference::Copied(data) => visitor.visit_bytes(data),
+        }
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, NbtError>
@@ -410,14 +1819,12 @@ where
     {
         is_ty!(ByteArray, self.next_ty);
 
-        let len = match F::AS_ENUM {
-            Variant::BigEndian => self.input.read_i32::<BigEndian>()? as u32,
-            Variant::LittleEndian => self.input.read_i32::<LittleEndian>()? as u32,
-            Variant::NetworkEndian => self.input.read_i32_varint()? as u32,
-        };
+        let len = self.read_byte_array_len()?;
 
-        let mut buf = vec![0; len as usize];
-        self.input.read_exact(&mut buf)?;
+        let buf = match self.input.read_slice(len as usize, &mut self.scratch)? {
+            Reference::Borrowed(data) => data.to_vec(),
+            Reference::Copied(data) => data.to_vec(),
+        };
 
         visitor.visit_byte_buf(buf)
     }
@@ -455,17 +1862,44 @@ where
         ))
     }
 
+    /// Other newtype structs are deserialized from their inner value. The names used by
+    /// [`IntArray`](crate::IntArray) and [`LongArray`](crate::LongArray) are deserialized as
+    /// sequences.
+    ///
+    /// With the `bytemuck` feature, arrays whose byte order matches the host are instead passed
+    /// to the visitor as raw bytes.
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
-        _visitor: V,
+        name: &'static str,
+        visitor: V,
     ) -> Result<V::Value, NbtError>
     where
         V: Visitor<'de>,
     {
-        Err(NbtError::Unsupported(
-            "Deserializing newtype structs is not supported",
-        ))
+        let width = match (name, self.next_ty) {
+            (INT_ARRAY_TOKEN, FieldType::IntArray) => 4,
+            (LONG_ARRAY_TOKEN, FieldType::LongArray) => 8,
+            (INT_ARRAY_TOKEN | LONG_ARRAY_TOKEN, _) => return self.deserialize_seq(visitor),
+            _ => return visitor.visit_newtype_struct(self),
+        };
+
+        let native = match F::AS_ENUM {
+            Variant::LittleEndian => cfg!(target_endian = "little"),
+            Variant::BigEndian => cfg!(target_endian = "big"),
+            Variant::NetworkEndian => false,
+        };
+        if !cfg!(feature = "bytemuck") || !native {
+            return self.deserialize_seq(visitor);
+        }
+
+        let len = self.read_len()?;
+        match self
+            .input
+            .read_slice(array_size(len, width)?, &mut self.scratch)?
+        {
+            Reference::Borrowed(data) => visitor.visit_borrowed_bytes(data),
+            Reference::Copied(data) => visitor.visit_bytes(data),
+        }
     }
 
     #[inline]
@@ -473,34 +1907,50 @@ where
     where
         V: Visitor<'de>,
     {
-        self.deserialize_tuple(0, visitor)
+        self.deserialize_list(None, visitor)
     }
 
+    /// Deserializes a list or array of exactly `len` elements, such as a `[T; N]`.
+    ///
+    /// Returns [`NbtError::ArrayLengthMismatch`] if the list has a different length.
     #[inline]
     fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, NbtError>
     where
         V: Visitor<'de>,
     {
-        let ty = match self.next_ty {
-            FieldType::ByteArray => FieldType::Byte,
-            FieldType::IntArray => FieldType::Int,
-            FieldType::LongArray => FieldType::Long,
-            _ => FieldType::try_from(self.input.read_u8()?)?,
-        };
-
-        let de = SeqDeserializer::new(self, ty, len as u32)?;
-        visitor.visit_seq(de)
+        self.deserialize_list(Some(len), visitor)
     }
 
+    /// Tuple structs are not supported, apart from the name used by
+    /// [`heapless_string`](crate::serde_helpers::heapless_string), which reads a string of at
+    /// most `len` bytes.
+    #[cfg_attr(not(feature = "heapless"), allow(unused_variables))]
     fn deserialize_tuple_struct<V>(
         self,
-        _name: &'static str,
-        _len: usize,
-        _visitor: V,
+        name: &'static str,
+        len: usize,
+        visitor: V,
     ) -> Result<V::Value, NbtError>
     where
         V: Visitor<'de>,
     {
+        #[cfg(feature = "heapless")]
+        if name == HEAPLESS_STRING_TOKEN {
+            is_ty!(String, self.next_ty);
+
+            let lenient = self.lenient_utf8;
+            let (Reference::Borrowed(data) | Reference::Copied(data)) = self.read_str()?;
+            let s = decode_utf8(data, lenient)?;
+            if s.len() > len {
+                return Err(NbtError::CapacityExceeded {
+                    len: s.len(),
+                    capacity: len,
+                });
+            }
+
+            return visitor.visit_str(&s);
+        }
+
         Err(NbtError::Unsupported(
             "Deserializing tuple structs is not supported",
         ))
@@ -513,8 +1963,13 @@ where
     {
         is_ty!(Compound, self.next_ty);
 
-        let de = MapDeserializer::from(self);
-        visitor.visit_map(de)
+        self.enter()?;
+        let mut de = MapDeserializer::from(&mut *self);
+        let output = visitor.visit_map(&mut de)?;
+        de.end()?;
+        self.depth -= 1;
+
+        Ok(output)
     }
 
     #[inline]
@@ -552,12 +2007,35 @@ where
         self.deserialize_string(visitor)
     }
 
-    #[inline]
+    /// Skips the next value.
+    ///
+    /// Like [`skip_value`](Deserializer::skip_value), this does not allocate or check strings
+    /// for valid UTF-8. If an unknown field handler is set, the value is collected as an extra
+    /// entry or duplicate keys are rejected with [`DuplicateKeyPolicy::Error`], it is decoded
+    /// instead.
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, NbtError>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        // Only the key of an entry of the root compound has been pushed onto the path.
+        let is_extra = self.extras.is_some() && self.path_marks.len() == 1;
+        let checked = self.duplicate_key_policy == DuplicateKeyPolicy::Error;
+        if self.unknown_field_handler.is_none() && !is_extra && !checked {
+            // Nothing observes the value, so it is skipped without decoding or allocating it.
+            let ty = std::mem::replace(&mut self.next_ty, FieldType::End);
+            self.skip_payload(ty)?;
+            return visitor.visit_unit();
+        }
+
+        let value = Value::deserialize(&mut *self)?;
+        if let Some(UnknownFieldHandler(handler)) = &mut self.unknown_field_handler {
+            handler(&self.path, &value);
+        }
+        if let (true, Some(extras)) = (is_extra, &mut self.extras) {
+            extras.insert(self.path.clone(), value);
+        }
+
+        visitor.visit_unit()
     }
 
     #[inline]
@@ -566,56 +2044,193 @@ where
     }
 }
 
+/// Lazily deserializes the elements of a list.
+///
+/// The reader must be positioned at the start of a list payload, i.e. the element type
+/// followed by the length. Each call to [`next`](Iterator::next) deserializes a single
+/// element, so that lists can be processed without holding every element in memory.
+///
+/// Iteration stops after the first error.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx as nbt;
+/// # fn main() {
+///  #[derive(serde::Serialize, serde::Deserialize, Debug)]
+///  struct Entity {
+///     id: i32
+///  }
+///
+/// # #[derive(serde::Serialize)]
+/// # struct Root { list: Vec<Entity> }
+/// # let list: Vec<_> = (0..3).map(|id| Entity { id }).collect();
+/// # let buffer = nbt::to_be_bytes(&Root { list }).unwrap();
+///  // Skip the root header and the header of the `list` entry.
+///  let mut reader = &buffer[1 + 2 + 4 + 1 + 2 + 4..];
+///  let list = nbt::ListDeserializer::<nbt::BigEndian, _, Entity>::new(&mut reader).unwrap();
+///  for entity in list {
+///     println!("Got {:?}!", entity.unwrap());
+///  }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ListDeserializer<'re, 'de, F, R, T>
+where
+    R: NbtRead<'de>,
+    F: EndiannessImpl + 'de,
+{
+    de: Deserializer<'re, 'de, F, R>,
+    ty: FieldType,
+    remaining: u32,
+    index: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'re, 'de, F, R, T> ListDeserializer<'re, 'de, F, R, T>
+where
+    R: NbtRead<'de>,
+    F: EndiannessImpl + 'de,
+    T: Deserialize<'de>,
+{
+    /// Reads the header of the list.
+    pub fn new(input: &'re mut R) -> Result<Self, NbtError> {
+        let mut de = Deserializer::with_type(input, FieldType::List);
+        let header = Self::read_header(&mut de);
+        let (ty, remaining) = header.map_err(|e| de.locate(e))?;
+
+        de.next_ty = ty;
+        Ok(Self {
+            de,
+            ty,
+            remaining,
+            index: 0,
+            _marker: PhantomData,
+        })
+    }
+
+    fn read_header(de: &mut Deserializer<'re, 'de, F, R>) -> Result<(FieldType, u32), NbtError> {
+        let ty = de.read_tag()?;
+        let len = de.read_len()?;
+        if ty == FieldType::End && len > 0 {
+            return Err(NbtError::InvalidListType { len });
+        }
+
+        Ok((ty, len))
+    }
+
+    /// Returns the type of the elements in the list.
+    #[inline]
+    pub fn element_type(&self) -> FieldType {
+        self.ty
+    }
+
+    /// Returns the amount of elements that have not been read yet.
+    #[inline]
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+}
+
+impl<'de, F, R, T> Iterator for ListDeserializer<'_, 'de, F, R, T>
+where
+    R: NbtRead<'de>,
+    F: EndiannessImpl + 'de,
+    T: Deserialize<'de>,
+{
+    type Item = Result<T, NbtError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        self.de.push_index(self.index);
+        let output = T::deserialize(&mut self.de);
+        match output {
+            Ok(output) => {
+                self.de.pop_path();
+                self.de.next_ty = self.ty;
+                self.index += 1;
+
+                Some(Ok(output))
+            }
+            Err(e) => {
+                // The position of the reader is unknown after an error.
+                self.remaining = 0;
+                Some(Err(self.de.locate(e)))
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl<'de, F, R, T> ExactSizeIterator for ListDeserializer<'_, 'de, F, R, T>
+where
+    R: NbtRead<'de>,
+    F: EndiannessImpl + 'de,
+    T: Deserialize<'de>,
+{
+}
+
 /// Deserializes NBT sequences.
 ///
-/// Sequences are in this case: [`ByteArray`](FieldType::ByteArray), [`IntArray`](FieldType::IntArray)
-/// [`LongArray`](FieldType::LongArray) and [`List`](FieldType::List).
+/// Sequences are in this case: [`List`](FieldType::List), as well as
+/// [`IntArray`](FieldType::IntArray) and [`LongArray`](FieldType::LongArray) in the network format,
+/// where their elements are varints. Other arrays use [`ArrayDeserializer`].
 #[derive(Debug)]
 struct SeqDeserializer<'a, 're, 'de: 'a, F, R>
 where
-    R: ReadBytesExt,
+    R: NbtRead<'de>,
     F: EndiannessImpl,
 {
     de: &'a mut Deserializer<'re, 'de, F, R>,
     ty: FieldType,
     remaining: u32,
+    /// Index of the next element, used to track the current path.
+    index: u32,
 }
 
 impl<'de, 're, 'a, F, R> SeqDeserializer<'a, 're, 'de, F, R>
 where
-    R: ReadBytesExt,
+    R: NbtRead<'de>,
     F: EndiannessImpl,
 {
     #[inline]
     pub fn new(
         de: &'a mut Deserializer<'re, 'de, F, R>,
         ty: FieldType,
-        expected_len: u32,
+        expected_len: Option<usize>,
     ) -> Result<Self, NbtError> {
         // debug_assert_ne!(ty, FieldType::End, "Cannot serialize sequence of end tags");
 
         // ty is not read in here because the x_array types don't have a type prefix.
 
         de.next_ty = ty;
-        let remaining = match F::AS_ENUM {
-            Variant::BigEndian => de.input.read_i32::<BigEndian>()? as u32,
-            Variant::LittleEndian => de.input.read_i32::<LittleEndian>()? as u32,
-            Variant::NetworkEndian => de.input.read_i32_varint()? as u32,
-        };
-
-        if expected_len != 0 && expected_len != remaining {
-            return Err(NbtError::Other(Cow::Owned(format!(
-                "Sequence of {expected_len} {ty:?} expected, found only {remaining} items"
-            ))));
+        let remaining = de.read_len()?;
+        if ty == FieldType::End && remaining > 0 {
+            return Err(NbtError::InvalidListType { len: remaining });
         }
 
-        Ok(Self { de, ty, remaining })
+        check_seq_len(expected_len, remaining)?;
+
+        Ok(Self {
+            de,
+            ty,
+            remaining,
+            index: 0,
+        })
     }
 }
 
 impl<'de, F, R> SeqAccess<'de> for SeqDeserializer<'_, '_, 'de, F, R>
 where
-    R: ReadBytesExt,
+    R: NbtRead<'de>,
     F: EndiannessImpl,
 {
     type Error = NbtError;
@@ -628,40 +2243,317 @@ where
         if self.remaining > 0 {
             self.remaining -= 1;
 
-            let output = seed.deserialize(&mut *self.de).map(Some);
+            self.de.push_index(self.index);
+            let output = seed.deserialize(&mut *self.de)?;
+            self.de.pop_path();
+
+            self.index += 1;
             self.de.next_ty = self.ty;
-            output
+            Ok(Some(output))
         } else {
             Ok(None)
         }
     }
 }
 
+/// Verifies that a sequence has the length of the array or tuple it is deserialized into, if
+/// any.
+#[inline]
+fn check_seq_len(expected_len: Option<usize>, len: u32) -> Result<(), NbtError> {
+    match expected_len {
+        Some(expected) if expected != len as usize => Err(NbtError::ArrayLengthMismatch {
+            expected,
+            actual: len as usize,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the size in bytes of an array with `len` elements of `width` bytes each.
+#[inline]
+fn array_size(len: u32, width: usize) -> Result<usize, NbtError> {
+    (len as usize)
+        .checked_mul(width)
+        .ok_or(NbtError::Other(Cow::Borrowed(
+            "Array is too large to fit in memory",
+        )))
+}
+
+/// Deserializes the elements of an array that has been read into memory.
+struct ArrayDeserializer<'s, F> {
+    data: &'s [u8],
+    /// Type of the elements.
+    ty: FieldType,
+    /// Size of a single element in bytes.
+    width: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<'de, F> SeqAccess<'de> for ArrayDeserializer<'_, F>
+where
+    F: EndiannessImpl,
+{
+    type Error = NbtError;
+
+    #[inline]
+    fn next_element_seed<E>(&mut self, seed: E) -> Result<Option<E::Value>, NbtError>
+    where
+        E: DeserializeSeed<'de>,
+    {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+
+        let (element, rest) = self.data.split_at(self.width);
+        self.data = rest;
+
+        seed.deserialize(ArrayElementDeserializer::<F> {
+            data: element,
+            ty: self.ty,
+            _marker: PhantomData,
+        })
+        .map(Some)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.data.len() / self.width)
+    }
+}
+
+/// Deserializes a single element of an array.
+///
+/// Elements of byte arrays are unsigned when deserialized as `u8` and signed otherwise.
+struct ArrayElementDeserializer<'s, F> {
+    data: &'s [u8],
+    ty: FieldType,
+    _marker: PhantomData<F>,
+}
+
+impl<'de, F> de::Deserializer<'de> for ArrayElementDeserializer<'_, F>
+where
+    F: EndiannessImpl,
+{
+    type Error = NbtError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        match (self.ty, F::AS_ENUM) {
+            (FieldType::Int, Variant::BigEndian) => {
+                visitor.visit_i32(BigEndian::read_i32(self.data))
+            }
+            (FieldType::Int, _) => visitor.visit_i32(LittleEndian::read_i32(self.data)),
+            (FieldType::Long, Variant::BigEndian) => {
+                visitor.visit_i64(BigEndian::read_i64(self.data))
+            }
+            (FieldType::Long, _) => visitor.visit_i64(LittleEndian::read_i64(self.data)),
+            _ => visitor.visit_i8(self.data[0] as i8),
+        }
+    }
+
+    #[inline]
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        if self.ty == FieldType::Byte {
+            visitor.visit_u8(self.data[0])
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Returns whether the error was caused by the input ending.
+pub(crate) fn is_eof(e: &NbtError) -> bool {
+    match e {
+        NbtError::ByteError(StreamError::UnexpectedEof { .. }) => true,
+        NbtError::ByteError(StreamError::IoError(e)) => e.kind() == io::ErrorKind::UnexpectedEof,
+        _ => false,
+    }
+}
+
+/// Generates methods that parse an integer from a compound key.
+macro_rules! parse_key {
+    ($($ty: ident),+) => {
+        paste! {$(
+            #[inline]
+            fn [<deserialize_ $ty>]<V>(self, visitor: V) -> Result<V::Value, NbtError>
+            where
+                V: Visitor<'de>,
+            {
+                match self.key.parse::<$ty>() {
+                    Ok(v) => visitor.[<visit_ $ty>](v),
+                    // Let the visitor report the string as an invalid type.
+                    Err(_) => self.inner.deserialize_any(visitor),
+                }
+            }
+        )+}
+    }
+}
+
+/// Deserializer for compound keys.
+///
+/// Keys are always strings, but integers are parsed from them when requested, so that maps with
+/// integer keys such as `HashMap<i32, T>` can be read. Anything else is passed on to `inner`.
+pub(crate) struct KeyDeserializer<'k, D> {
+    key: &'k str,
+    inner: D,
+}
+
+impl<'k, D> KeyDeserializer<'k, D> {
+    #[inline]
+    pub(crate) fn new(key: &'k str, inner: D) -> Self {
+        Self { key, inner }
+    }
+}
+
+impl<'de, D> de::Deserializer<'de> for KeyDeserializer<'_, D>
+where
+    D: de::Deserializer<'de, Error = NbtError>,
+{
+    type Error = NbtError;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        self.inner.deserialize_any(visitor)
+    }
+
+    parse_key!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    #[inline]
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        self.inner.deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf option unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de, D> IntoDeserializer<'de, NbtError> for KeyDeserializer<'_, D>
+where
+    D: de::Deserializer<'de, Error = NbtError>,
+{
+    type Deserializer = Self;
+
+    #[inline]
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
 /// Deserialises NBT compounds.
 #[derive(Debug)]
 struct MapDeserializer<'a, 're, 'de: 'a, F, R>
 where
-    R: ReadBytesExt,
+    R: NbtRead<'de>,
     F: EndiannessImpl,
 {
     de: &'a mut Deserializer<'re, 'de, F, R>,
+    /// Keys that have been read so far, unless duplicates are passed through.
+    seen: HashSet<String>,
+    /// Whether the end tag of the compound has been read.
+    finished: bool,
+}
+
+impl<'de, F, R> MapDeserializer<'_, '_, 'de, F, R>
+where
+    R: NbtRead<'de>,
+    F: EndiannessImpl,
+{
+    /// Verifies that the compound is closed by an end tag, reading it if the visitor stopped
+    /// before the end of the compound.
+    ///
+    /// Any other tag means that entries were left unread, which would leave the input out of
+    /// sync with the deserializer.
+    fn end(self) -> Result<(), NbtError> {
+        if !self.finished && self.de.read_entry_tag()? != FieldType::End {
+            return Err(NbtError::MissingEndTag);
+        }
+
+        Ok(())
+    }
 }
 
 impl<'de, 're, 'a, F, R> From<&'a mut Deserializer<'re, 'de, F, R>>
     for MapDeserializer<'a, 're, 'de, F, R>
 where
-    R: ReadBytesExt,
+    R: NbtRead<'de>,
     F: EndiannessImpl,
 {
     #[inline]
     fn from(v: &'a mut Deserializer<'re, 'de, F, R>) -> Self {
-        Self { de: v }
+        Self {
+            de: v,
+            seen: HashSet::new(),
+            finished: false,
+        }
+    }
+}
+
+/// Records the key of a compound entry, returning whether the entry should be skipped.
+fn check_duplicate(
+    seen: &mut HashSet<String>,
+    policy: DuplicateKeyPolicy,
+    key: &str,
+) -> Result<bool, NbtError> {
+    if policy == DuplicateKeyPolicy::Last || seen.insert(key.to_owned()) {
+        return Ok(false);
+    }
+
+    match policy {
+        DuplicateKeyPolicy::Error => Err(NbtError::DuplicateKey(key.to_owned())),
+        _ => Ok(true),
     }
 }
 
 impl<'de, F, R> MapAccess<'de> for MapDeserializer<'_, '_, 'de, F, R>
 where
-    R: ReadBytesExt,
+    R: NbtRead<'de>,
     F: EndiannessImpl,
 {
     type Error = NbtError;
@@ -671,21 +2563,53 @@ where
     where
         K: DeserializeSeed<'de>,
     {
-        self.de.is_key = true;
-        self.de.next_ty = FieldType::String;
-
-        let next_ty = FieldType::try_from(self.de.input.read_u8()?)?;
-        // dbg!(&next_ty);
+        loop {
+            let next_ty = self.de.read_entry_tag()?;
+            if next_ty == FieldType::End {
+                self.finished = true;
+                return Ok(None);
+            }
 
-        let r = if next_ty == FieldType::End {
-            Ok(None)
-        } else {
-            seed.deserialize(&mut *self.de).map(Some)
-        };
+            // The key is only copied if it cannot be borrowed from the input and the seed needs
+            // an owned string.
+            let len = self.de.read_str_len()?;
+            let lenient = self.de.lenient_utf8;
+            let policy = self.de.duplicate_key_policy;
+            let de = &mut *self.de;
+            de.next_ty = next_ty;
+
+            match de.input.read_slice(len, &mut de.scratch)? {
+                Reference::Borrowed(data) => {
+                    let key = decode_utf8(data, lenient)?;
+                    if !check_duplicate(&mut self.seen, policy, &key)? {
+                        push_key(&mut de.path, &mut de.path_marks, &key);
+                        return match key {
+                            Cow::Borrowed(key) => seed.deserialize(KeyDeserializer::new(
+                                key,
+                                BorrowedStrDeserializer::new(key),
+                            )),
+                            Cow::Owned(key) => seed.deserialize(KeyDeserializer::new(
+                                &key,
+                                StrDeserializer::new(&key),
+                            )),
+                        }
+                        .map(Some);
+                    }
+                }
+                Reference::Copied(data) => {
+                    let key = decode_utf8(data, lenient)?;
+                    if !check_duplicate(&mut self.seen, policy, &key)? {
+                        push_key(&mut de.path, &mut de.path_marks, &key);
+                        return seed
+                            .deserialize(KeyDeserializer::new(&key, StrDeserializer::new(&key)))
+                            .map(Some);
+                    }
+                }
+            }
 
-        self.de.is_key = false;
-        self.de.next_ty = next_ty;
-        r
+            // A repeated key with the `First` policy.
+            de.skip_payload(next_ty)?;
+        }
     }
 
     #[inline]
@@ -698,6 +2622,14 @@ where
             FieldType::End,
             "Cannot serialize end as a map field"
         );
-        seed.deserialize(&mut *self.de)
+        let output = seed.deserialize(&mut *self.de)?;
+        self.de.pop_path();
+
+        if self.de.path_marks.is_empty() {
+            // This was an entry of the root compound.
+            self.de.entries += 1;
+        }
+
+        Ok(output)
     }
 }
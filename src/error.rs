@@ -1,15 +1,38 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+use core::fmt::{self, Write};
 
 use thiserror::Error;
 
-use crate::FieldType;
+use crate::{FieldType, Variant};
 
 /// Errors that can occur while serializing or deserializing NBT data.
 #[derive(Error, Debug, Clone)]
+#[non_exhaustive]
 pub enum NbtError {
-    /// The encountered NBT tag type is invalid.
-    #[error("An unknown tag type was encountered ({actual}), it should be in the range 0-12")]
-    TypeOutOfRange { actual: u8 },
+    /// The encountered tag type byte does not correspond to any [`FieldType`].
+    #[error("Unknown tag {0:#04x}, it should be in the range 0x00-0x0c")]
+    UnknownTag(u8),
+    /// The type of the root value is not a valid tag type.
+    ///
+    /// The root tag is the first byte of the input, so this usually means that the data is
+    /// compressed, like the files that Java Edition writes to disk, or was not written as NBT of
+    /// the expected [`Variant`]. Only the first byte is checked, so data of another variant that
+    /// starts with a valid tag is not detected.
+    #[error(
+        "Unknown root tag {byte:#04x} for the {variant:?} variant, the data is likely compressed \
+         or in a different format"
+    )]
+    LikelyWrongVariant {
+        /// The first byte of the input.
+        byte: u8,
+        /// Variant that the data was read as.
+        variant: Variant,
+    },
     /// Found a type different from the type that was expected.
     #[error("Expected tag of type {expected:?}, received {actual:?}")]
     UnexpectedType {
@@ -18,59 +41,442 @@ pub enum NbtError {
         /// Type that was found in the NBT stream.
         actual: FieldType,
     },
+    /// A list declared elements of type [`End`](FieldType::End) with a nonzero length.
+    ///
+    /// Only empty lists may use `End` as their element type.
+    #[error("List of end tags declared a nonzero length of {len}")]
+    InvalidListType {
+        /// Length that was declared by the list.
+        len: u32,
+    },
+    /// The input ended before the end tag of a compound, or a compound was not closed where
+    /// its end tag was expected.
+    #[error("Compound is missing its end tag")]
+    MissingEndTag,
+    /// A compound did not contain the entry for a field of the struct that it is deserialized
+    /// into.
+    ///
+    /// Fields marked with `#[serde(default)]` or of type `Option` are filled in instead.
+    #[error("Compound is missing the field {0:?}")]
+    MissingField(&'static str),
+    /// A compound contained the same key more than once.
+    ///
+    /// Only returned with [`DuplicateKeyPolicy::Error`](crate::DuplicateKeyPolicy::Error).
+    #[error("Compound contains the key {0:?} more than once")]
+    DuplicateKey(String),
+    /// The first byte of the input does not start a gzip stream, a zlib stream or a compound.
+    ///
+    /// See [`from_reader_auto`](crate::from_reader_auto).
+    #[error("Unrecognized compression, the input starts with {0:#04x}")]
+    UnknownCompression(u8),
+    /// A tag type that is not in the allowed set was encountered.
+    ///
+    /// Only returned when allowed tags are configured using
+    /// [`Deserializer::with_allowed_tags`](crate::Deserializer::with_allowed_tags).
+    #[error("Encountered forbidden tag {0}")]
+    ForbiddenTag(FieldType),
     /// The requested operation is not supported.
     #[error("{0}")]
     Unsupported(&'static str),
+    /// A list element was `None`.
+    ///
+    /// `None` compound entries and struct fields are skipped, but every element of a list has
+    /// to be written.
+    #[error("List elements cannot be skipped, found `None` in a list")]
+    CannotSkipInList,
+    /// A sequence yielded a different amount of elements than the length it declared.
+    #[error("Sequence declared a length of {declared}, but {actual} elements were serialized")]
+    LengthMismatch {
+        /// Length that was passed to `serialize_seq`.
+        declared: usize,
+        /// Amount of elements that were actually serialized.
+        actual: usize,
+    },
+    /// The writer was taken out of the serializer, or flushed, while a list was still open.
+    ///
+    /// This happens when a [`ListWriter`](crate::ListWriter) is dropped without calling
+    /// [`end_list`](crate::ListWriter::end_list), or after serializing a list failed.
+    #[error("Serialization ended with {open} unfinished lists")]
+    UnfinishedList {
+        /// Amount of lists that are still open.
+        open: usize,
+    },
+    /// A list or array has a different length than the fixed-size array or tuple that it is
+    /// deserialized into.
+    #[error("Expected a sequence of {expected} elements, found {actual}")]
+    ArrayLengthMismatch {
+        /// Length of the array or tuple.
+        expected: usize,
+        /// Length of the sequence in the input.
+        actual: usize,
+    },
+    /// A list or array has more elements than fit in its signed 32-bit length prefix.
+    #[error("Length {len} exceeds the maximum sequence length of {}", i32::MAX)]
+    LengthTooLarge {
+        /// Amount of elements of the sequence.
+        len: usize,
+    },
+    /// A list or array declared more elements than allowed.
+    ///
+    /// Only returned when a limit is set using
+    /// [`Deserializer::with_max_list_len`](crate::Deserializer::with_max_list_len).
+    #[error("Sequence declared a length of {declared}, which exceeds the maximum of {max}")]
+    ListTooLong {
+        /// Length that was declared in the input.
+        declared: u32,
+        /// Largest length that is allowed.
+        max: u32,
+    },
+    /// The input is longer than allowed.
+    ///
+    /// Only returned when a limit is set using
+    /// [`DeserializerOptions::with_max_bytes`](crate::DeserializerOptions::with_max_bytes).
+    #[error("Input exceeds the maximum size of {max} bytes")]
+    ByteLimitExceeded {
+        /// Largest amount of bytes that may be read.
+        max: u64,
+    },
+    /// Data remained in the input after the root value.
+    ///
+    /// Only returned by the `_exact` functions, such as
+    /// [`from_be_bytes_exact`](crate::from_be_bytes_exact).
+    #[error("Found {remaining} bytes of trailing data after the root value")]
+    TrailingData {
+        /// Amount of bytes that followed the root value.
+        remaining: u64,
+    },
+    /// The length declared in the header of a file differs from the size of its NBT data.
+    ///
+    /// See [`from_bedrock_leveldat`](crate::from_bedrock_leveldat).
+    #[error("Header declared {declared} bytes of NBT data, found {actual}")]
+    FrameLengthMismatch {
+        /// Length that was declared in the header.
+        declared: u32,
+        /// Size of the data in bytes.
+        actual: usize,
+    },
+    /// A string is longer than its length prefix can hold.
+    ///
+    /// See [`StringLenWidth`](crate::StringLenWidth).
+    #[error("String of {len} bytes exceeds the maximum length of {max}")]
+    StringTooLong {
+        /// Length of the string in bytes.
+        len: usize,
+        /// Largest length that fits in the prefix.
+        max: usize,
+    },
+    /// A map key could not be converted to a string to be used as the name of a compound entry.
+    ///
+    /// Strings, integers, chars and unit variants can be used as keys. Contains a description
+    /// of the key.
+    #[error("Compound keys must be strings or integers, found {0}")]
+    NonStringKey(&'static str),
+    /// An element of a list converted by [`to_value`](crate::to_value) has a different type
+    /// than the first element.
+    ///
+    /// NBT lists are homogeneous, and elements are not converted to a common type.
+    #[error("List element {index} is of type {actual:?}, but the list contains {expected:?}")]
+    HeterogeneousList {
+        /// Index of the element in the list.
+        index: usize,
+        /// Type of the first element of the list.
+        expected: FieldType,
+        /// Type of the element.
+        actual: FieldType,
+    },
+    /// A byte array is longer than its length prefix can hold.
+    ///
+    /// See [`ByteArrayLenWidth`](crate::ByteArrayLenWidth).
+    #[error("Byte array of {len} bytes exceeds the maximum length of {max}")]
+    ByteArrayTooLong {
+        /// Length of the array in bytes.
+        len: usize,
+        /// Largest length that fits in the prefix.
+        max: usize,
+    },
+    /// A list or array declared a negative length.
+    #[error("Encountered negative sequence length {len}")]
+    NegativeLength {
+        /// Length that was declared in the input.
+        len: i32,
+    },
+    /// A varint in the network format has more bytes or bits than its type can hold.
+    #[error("Varint does not fit in {bits} bits")]
+    VarintTooLong {
+        /// Amount of bits of the type that was read.
+        bits: u32,
+    },
+    /// A string is longer than the fixed capacity of the type it is read into.
+    ///
+    /// See [`serde_helpers::heapless_string`](crate::serde_helpers::heapless_string).
+    #[error("String of {len} bytes exceeds the capacity of {capacity}")]
+    CapacityExceeded {
+        /// Length of the string in bytes.
+        len: usize,
+        /// Capacity of the type in bytes.
+        capacity: usize,
+    },
+    /// A NaN or infinite float was serialized with [`FloatPolicy::Reject`](crate::FloatPolicy::Reject).
+    #[error("Cannot serialize non-finite float {value}")]
+    NonFiniteFloat {
+        /// The value that was rejected.
+        value: f64,
+    },
     /// Any errors related to reading and writing from the stream.
-    #[error("{0}")]
+    #[error(transparent)]
     ByteError(#[from] StreamError),
     /// Other errors that do not fit in any of the previous categories.
     #[error("{0}")]
     Other(Cow<'static, str>),
+    /// Wraps another error with the position in the stream at which it occurred.
+    #[error("{error} at offset {offset}{}", path_suffix(.path))]
+    At {
+        /// Amount of bytes that had been consumed from the stream when the error occurred.
+        offset: u64,
+        /// Path to the value that was being deserialized, such as `Level.Sections[2].Palette`.
+        path: String,
+        /// Amount of entries of the root compound that were fully read before the error occurred.
+        entries: usize,
+        /// The error that occurred.
+        #[source]
+        error: Box<NbtError>,
+    },
 }
 
+fn path_suffix(path: &str) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!(" ({path})")
+    }
+}
+
+impl NbtError {
+    /// Attaches the given stream offset to this error.
+    ///
+    /// Errors that already carry a position are returned unchanged, so that the
+    /// innermost location is preserved.
+    pub fn at(self, offset: u64) -> Self {
+        match self {
+            Self::At { .. } => self,
+            error => Self::At {
+                offset,
+                path: String::new(),
+                entries: 0,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Attaches the given stream offset and value path to this error.
+    ///
+    /// Errors that already carry a position are returned unchanged.
+    pub fn at_path(self, offset: u64, path: impl Into<String>) -> Self {
+        match self {
+            Self::At { .. } => self,
+            error => Self::At {
+                offset,
+                path: path.into(),
+                entries: 0,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Returns the stream offset at which this error occurred, if known.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Self::At { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the path to the value that failed to deserialize, if known.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::At { path, .. } if !path.is_empty() => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Returns how far deserialization got before this error occurred, if known.
+    pub fn progress(&self) -> Option<Progress> {
+        match self {
+            Self::At {
+                offset, entries, ..
+            } => Some(Progress {
+                bytes: *offset,
+                entries: *entries,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying error, stripping any positional context.
+    pub fn kind(&self) -> &NbtError {
+        match self {
+            Self::At { error, .. } => error,
+            error => error,
+        }
+    }
+
+    /// Renders the bytes of `data` around the offset of this error, see [`debug_dump`].
+    ///
+    /// `data` must be the input that was being deserialized. Returns `None` if the offset is
+    /// not known.
+    pub fn dump(&self, data: &[u8]) -> Option<String> {
+        self.offset().map(|offset| debug_dump(data, offset))
+    }
+}
+
+/// Amount of bytes that are shown per row by [`debug_dump`].
+const DUMP_ROW_LEN: usize = 16;
+
+/// Renders the bytes of `data` around `offset` in hex, with a caret under the byte at `offset`.
+///
+/// The row containing `offset` is shown together with the rows before and after it. Offsets
+/// at or past the end of `data` are marked right after the last byte.
+///
+/// The offset of an error is the amount of bytes that had been consumed, so the byte that
+/// caused it usually directly precedes the marked one.
+///
+/// # Example
+///
+/// ```rust
+/// // Compound with an entry whose tag type is 99.
+/// let data = [10, 0, 0, 99, 0, 1, b'a', 0];
+///
+/// let dump = nbtx::debug_dump(&data, 3);
+/// let lines: Vec<_> = dump.lines().collect();
+///
+/// assert_eq!(lines[0], "offset 3 (0x3):");
+/// assert_eq!(lines[1], "00000000  0a 00 00 63 00 01 61 00");
+/// assert_eq!(lines[2], "                   ^^");
+/// ```
+pub fn debug_dump(data: &[u8], offset: u64) -> String {
+    let marked = usize::try_from(offset).map_or(data.len(), |o| o.min(data.len()));
+    let row = marked / DUMP_ROW_LEN;
+
+    let mut out = format!("offset {offset} ({offset:#x}):\n");
+    for r in row.saturating_sub(1)..=row + 1 {
+        let start = r * DUMP_ROW_LEN;
+        if r > row && start >= data.len() {
+            break;
+        }
+
+        let _ = write!(out, "{start:08x}");
+        let bytes = &data[start..(start + DUMP_ROW_LEN).min(data.len())];
+        if !bytes.is_empty() {
+            out.push(' ');
+        }
+        for byte in bytes {
+            let _ = write!(out, " {byte:02x}");
+        }
+        out.push('\n');
+
+        if r == row {
+            let _ = writeln!(out, "{:width$}^^", "", width = 10 + 3 * (marked - start));
+        }
+    }
+
+    out
+}
+
+/// How much of the input was successfully consumed before an error occurred.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Progress {
+    /// Amount of bytes that had been consumed from the stream.
+    pub bytes: u64,
+    /// Amount of entries of the root compound that had been fully read.
+    pub entries: usize,
+}
+
+#[cfg(feature = "std")]
 impl From<std::io::Error> for NbtError {
     fn from(value: std::io::Error) -> Self {
-        Self::ByteError(StreamError::IoError(value.to_string()))
+        Self::ByteError(StreamError::from(value))
     }
 }
 
-impl From<std::str::Utf8Error> for NbtError {
-    fn from(value: std::str::Utf8Error) -> Self {
+impl From<core::str::Utf8Error> for NbtError {
+    fn from(value: core::str::Utf8Error) -> Self {
         Self::ByteError(StreamError::Utf8Error(value))
     }
 }
 
-impl From<std::string::FromUtf8Error> for NbtError {
-    fn from(value: std::string::FromUtf8Error) -> Self {
+impl From<alloc::string::FromUtf8Error> for NbtError {
+    fn from(value: alloc::string::FromUtf8Error) -> Self {
         Self::ByteError(StreamError::FromUtf8Error(value))
     }
 }
 
 /// Errors related to binary reading and writing.
-#[derive(Debug, Clone, Error)]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum StreamError {
-    // TODO: std::io::Error does not implement Clone while the ProtoCodec error type requires it.
-    // This is why I convert the error to a string rather than storing it directly like the others.
+    // std::io::Error does not implement Clone while the ProtoCodec error type requires it.
+    // This is why the error is stored behind an `Arc` rather than directly like the others.
     /// An IO [`Error`](std::io::Error).
-    #[error("{0}")]
-    IoError(String),
+    #[cfg(feature = "std")]
+    IoError(Arc<std::io::Error>),
     /// A byte slice could not be converted into a `String` because it is invalid UTF-8.
-    #[error("{0}")]
-    FromUtf8Error(#[from] std::string::FromUtf8Error),
+    FromUtf8Error(alloc::string::FromUtf8Error),
     /// A byte slice could not be converted into a `str` because it is invalid UTF-8.
-    #[error("{0}")]
-    Utf8Error(#[from] std::str::Utf8Error),
+    Utf8Error(core::str::Utf8Error),
     /// The deserializer tried to read past the end of the buffer.
-    #[error("Expected {expected} remaining bytes, found only {remaining}")]
     UnexpectedEof { expected: usize, remaining: usize },
     /// Any errors that do not fit the previous categories.
-    #[error("{0}")]
     Other(Cow<'static, str>),
 }
 
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Self::IoError(e) => e.fmt(f),
+            Self::FromUtf8Error(e) => e.fmt(f),
+            Self::Utf8Error(e) => e.fmt(f),
+            Self::UnexpectedEof {
+                expected,
+                remaining,
+            } => write!(
+                f,
+                "Expected {expected} remaining bytes, found only {remaining}"
+            ),
+            Self::Other(msg) => f.write_str(msg),
+        }
+    }
+}
+
+// Implemented by hand rather than derived, so that `source` returns the `io::Error` itself
+// instead of the `Arc` that wraps it. This allows callers to downcast the source.
+impl core::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Self::IoError(e) => Some(e.as_ref()),
+            Self::FromUtf8Error(e) => Some(e),
+            Self::Utf8Error(e) => Some(e),
+            Self::UnexpectedEof { .. } | Self::Other(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 impl From<std::io::Error> for StreamError {
     fn from(value: std::io::Error) -> Self {
-        Self::IoError(value.to_string())
+        Self::IoError(Arc::new(value))
+    }
+}
+
+impl From<alloc::string::FromUtf8Error> for StreamError {
+    fn from(value: alloc::string::FromUtf8Error) -> Self {
+        Self::FromUtf8Error(value)
+    }
+}
+
+impl From<core::str::Utf8Error> for StreamError {
+    fn from(value: core::str::Utf8Error) -> Self {
+        Self::Utf8Error(value)
     }
 }
@@ -0,0 +1,176 @@
+use core::hash::Hasher;
+
+use serde::Serialize;
+
+use crate::io::Write;
+use crate::{EndiannessImpl, NbtError, Serializer};
+
+/// Computes a stable 64-bit hash of the given data.
+///
+/// The hash is computed over a canonical NBT encoding of the data, in which compound
+/// entries are sorted by key and all NaN floats share the same bit pattern. Two values
+/// with the same contents therefore produce the same hash, regardless of the iteration
+/// order of their maps. The hash function is 64-bit FNV-1a, which is stable across runs,
+/// platforms and versions of this crate.
+///
+/// This is intended for deduplication and change detection, not for cryptographic purposes.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::collections::HashMap;
+/// # fn main() {
+/// # #[cfg(feature = "std")] {
+///  let a = HashMap::from([("x", 1), ("y", 2)]);
+///  let b = HashMap::from([("y", 2), ("x", 1)]);
+///
+///  assert_eq!(
+///     nbtx::content_hash::<nbtx::LittleEndian>(&a).unwrap(),
+///     nbtx::content_hash::<nbtx::LittleEndian>(&b).unwrap(),
+///  );
+/// # }
+/// # }
+/// ```
+pub fn content_hash<E>(v: &(impl Serialize + ?Sized)) -> Result<u64, NbtError>
+where
+    E: EndiannessImpl,
+{
+    let mut ser = Serializer::<_, E>::new(Fnv1a::new()).canonical();
+    v.serialize(&mut ser)?;
+
+    Ok(ser.into_inner()?.finish())
+}
+
+/// Writer that computes the 64-bit FNV-1a hash of all data written into it.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    #[inline]
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Write for Fnv1a {
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), NbtError> {
+        for byte in buf {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+
+        Ok(())
+    }
+}
+
+/// Writer adapter that feeds all data written through it into a [`Hasher`].
+///
+/// This allows serializing into a buffer or file while computing a checksum of the output in
+/// the same pass.
+///
+/// With the `std` feature enabled, this implements [`std::io::Write`] if `W` does.
+/// Otherwise, it implements [`Write`] if `W` does.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::collections::hash_map::DefaultHasher;
+/// # use std::collections::BTreeMap;
+/// # use std::hash::Hasher;
+/// let value = BTreeMap::from([("x", 1)]);
+///
+/// let mut writer = nbtx::HashingWriter::new(DefaultHasher::new(), Vec::new());
+/// nbtx::to_le_bytes_in(&mut writer, &value).unwrap();
+/// let (hasher, bytes) = writer.into_parts();
+///
+/// let mut expected = DefaultHasher::new();
+/// expected.write(&bytes);
+/// assert_eq!(hasher.finish(), expected.finish());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HashingWriter<H, W> {
+    hasher: H,
+    inner: W,
+}
+
+impl<H, W> HashingWriter<H, W>
+where
+    H: Hasher,
+{
+    /// Creates a new writer that hashes all data with `hasher` before forwarding it to `inner`.
+    #[inline]
+    pub fn new(hasher: H, inner: W) -> Self {
+        Self { hasher, inner }
+    }
+
+    /// Returns the hash of all data written so far.
+    #[inline]
+    pub fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    /// Returns a reference to the hasher.
+    #[inline]
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Returns a reference to the wrapped writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the hasher and the wrapped writer.
+    #[inline]
+    pub fn into_parts(self) -> (H, W) {
+        (self.hasher, self.inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H, W> std::io::Write for HashingWriter<H, W>
+where
+    H: Hasher,
+    W: std::io::Write,
+{
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // Only hash the part of the buffer that was actually written.
+        let n = self.inner.write(buf)?;
+        self.hasher.write(&buf[..n]);
+        Ok(n)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<H, W> Write for HashingWriter<H, W>
+where
+    H: Hasher,
+    W: Write,
+{
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), NbtError> {
+        self.inner.write_all(buf)?;
+        self.hasher.write(buf);
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), NbtError> {
+        self.inner.flush()
+    }
+}
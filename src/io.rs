@@ -0,0 +1,193 @@
+//! Minimal IO abstraction used by the serializer.
+//!
+//! This allows serialization to work in `no_std` environments, where `std::io` is not available.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use byteorder::ByteOrder;
+
+use crate::NbtError;
+
+/// A byte sink that NBT data can be written into.
+///
+/// With the `std` feature enabled, this is implemented for every [`std::io::Write`].
+/// Otherwise, it is implemented for `alloc::vec::Vec<u8>`.
+pub trait Write {
+    /// Writes the entire buffer into this writer.
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), NbtError>;
+
+    /// Writes any data that is buffered by this writer to its destination.
+    ///
+    /// Does nothing by default.
+    #[inline]
+    fn flush(&mut self) -> Result<(), NbtError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W> Write for W
+where
+    W: std::io::Write + ?Sized,
+{
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), NbtError> {
+        std::io::Write::write_all(self, buf)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), NbtError> {
+        std::io::Write::flush(self)?;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Write for Vec<u8> {
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), NbtError> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<W> Write for &mut W
+where
+    W: Write + ?Sized,
+{
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), NbtError> {
+        (**self).write_all(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), NbtError> {
+        (**self).flush()
+    }
+}
+
+/// Forwards writes to a mutable reference of a writer.
+///
+/// With the `std` feature enabled, `&mut W` only implements [`Write`] if `W` implements
+/// [`std::io::Write`]. This wrapper allows passing a reference to any [`Write`] instead.
+pub(crate) struct ByRef<'a, W: ?Sized>(pub(crate) &'a mut W);
+
+impl<W> Write for ByRef<'_, W>
+where
+    W: Write + ?Sized,
+{
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), NbtError> {
+        self.0.write_all(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), NbtError> {
+        self.0.flush()
+    }
+}
+
+/// Helper methods to write primitives in a given byte order.
+pub(crate) trait WriteExt: Write {
+    #[inline]
+    fn write_u8(&mut self, n: u8) -> Result<(), NbtError> {
+        self.write_all(&[n])
+    }
+
+    #[inline]
+    fn write_i8(&mut self, n: i8) -> Result<(), NbtError> {
+        self.write_all(&[n as u8])
+    }
+
+    #[inline]
+    fn write_u16<B: ByteOrder>(&mut self, n: u16) -> Result<(), NbtError> {
+        let mut buf = [0; 2];
+        B::write_u16(&mut buf, n);
+        self.write_all(&buf)
+    }
+
+    #[inline]
+    fn write_i16<B: ByteOrder>(&mut self, n: i16) -> Result<(), NbtError> {
+        let mut buf = [0; 2];
+        B::write_i16(&mut buf, n);
+        self.write_all(&buf)
+    }
+
+    #[inline]
+    fn write_u32<B: ByteOrder>(&mut self, n: u32) -> Result<(), NbtError> {
+        let mut buf = [0; 4];
+        B::write_u32(&mut buf, n);
+        self.write_all(&buf)
+    }
+
+    #[inline]
+    fn write_i32<B: ByteOrder>(&mut self, n: i32) -> Result<(), NbtError> {
+        let mut buf = [0; 4];
+        B::write_i32(&mut buf, n);
+        self.write_all(&buf)
+    }
+
+    #[inline]
+    fn write_i64<B: ByteOrder>(&mut self, n: i64) -> Result<(), NbtError> {
+        let mut buf = [0; 8];
+        B::write_i64(&mut buf, n);
+        self.write_all(&buf)
+    }
+
+    #[inline]
+    fn write_f32<B: ByteOrder>(&mut self, n: f32) -> Result<(), NbtError> {
+        let mut buf = [0; 4];
+        B::write_f32(&mut buf, n);
+        self.write_all(&buf)
+    }
+
+    #[inline]
+    fn write_f64<B: ByteOrder>(&mut self, n: f64) -> Result<(), NbtError> {
+        let mut buf = [0; 8];
+        B::write_f64(&mut buf, n);
+        self.write_all(&buf)
+    }
+
+    /// Writes an unsigned LEB128 varint.
+    #[inline]
+    fn write_u64_varint(&mut self, mut n: u64) -> Result<(), NbtError> {
+        let mut buf = [0; 10];
+        let mut len = 0;
+        loop {
+            let byte = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                buf[len] = byte;
+                len += 1;
+                break;
+            }
+
+            buf[len] = byte | 0x80;
+            len += 1;
+        }
+
+        self.write_all(&buf[..len])
+    }
+
+    #[inline]
+    fn write_u32_varint(&mut self, n: u32) -> Result<(), NbtError> {
+        self.write_u64_varint(n as u64)
+    }
+
+    /// Writes a zigzag encoded signed varint.
+    #[inline]
+    fn write_i32_varint(&mut self, n: i32) -> Result<(), NbtError> {
+        self.write_u32_varint(((n << 1) ^ (n >> 31)) as u32)
+    }
+
+    /// Writes a zigzag encoded signed varint.
+    #[inline]
+    fn write_i64_varint(&mut self, n: i64) -> Result<(), NbtError> {
+        self.write_u64_varint(((n << 1) ^ (n >> 63)) as u64)
+    }
+}
+
+impl<W> WriteExt for W where W: Write + ?Sized {}
@@ -0,0 +1,196 @@
+//! Conversion between [`Value`] and [`serde_json::Value`].
+//!
+//! # NBT to JSON
+//!
+//! | NBT                                          | JSON                          |
+//! |----------------------------------------------|-------------------------------|
+//! | `Byte`, `Short`, `Int`, `Long`               | Number                        |
+//! | `Float`, `Double`                            | Number, or `null` if not finite |
+//! | `String`                                     | String                        |
+//! | `List`                                       | Array                         |
+//! | `Compound`                                   | Object                        |
+//! | `ByteArray`, `IntArray`, `LongArray`         | Array of numbers              |
+//!
+//! This conversion is lossy: the original tag types cannot be recovered from the JSON.
+//!
+//! # JSON to NBT
+//!
+//! JSON cannot distinguish between integer widths, so the tag used for an integer is chosen by
+//! an [`IntegerWidth`] hint. [`TryFrom`] uses [`IntegerWidth::Narrowest`]. Numbers with a
+//! fractional part become a `Double` and booleans become a `Byte`.
+//!
+//! Arrays always become a `List`, never one of the array tags. Since every element of a list must
+//! have the same type, integer elements are widened to the widest tag needed by any element of the
+//! array, and become `Double` if the array also contains fractional numbers. Arrays with elements of
+//! otherwise differing types and `null` values cannot be converted.
+
+use std::borrow::Cow;
+
+use serde_json::{Map, Number};
+
+use crate::{Compound, FieldType, NbtError, Value};
+
+/// Determines which tag JSON integers are converted to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum IntegerWidth {
+    /// Uses the narrowest of `Byte`, `Short`, `Int` and `Long` that can hold the value.
+    #[default]
+    Narrowest,
+    /// Uses `Int`, or `Long` for values that do not fit in an `Int`.
+    Int,
+    /// Always uses `Long`.
+    Long,
+}
+
+impl IntegerWidth {
+    fn convert(self, n: i64) -> Value {
+        match self {
+            Self::Narrowest if i8::try_from(n).is_ok() => Value::Byte(n as i8),
+            Self::Narrowest if i16::try_from(n).is_ok() => Value::Short(n as i16),
+            Self::Narrowest | Self::Int if i32::try_from(n).is_ok() => Value::Int(n as i32),
+            _ => Value::Long(n),
+        }
+    }
+}
+
+impl Value {
+    /// Converts a JSON value into NBT, using the given hint to select the tags used for integers.
+    ///
+    /// See the [`json`](crate::json) module for a description of the mapping.
+    pub fn from_json(json: serde_json::Value, width: IntegerWidth) -> Result<Value, NbtError> {
+        Ok(match json {
+            serde_json::Value::Null => {
+                return Err(NbtError::Unsupported(
+                    "JSON null cannot be converted to NBT",
+                ))
+            }
+            serde_json::Value::Bool(b) => Value::Byte(b as i8),
+            serde_json::Value::Number(n) => number_to_nbt(&n, width)?,
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Array(array) => {
+                let list = array
+                    .into_iter()
+                    .map(|v| Value::from_json(v, width))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Value::List(unify_list(list)?)
+            }
+            serde_json::Value::Object(object) => Value::Compound(
+                object
+                    .into_iter()
+                    .map(|(k, v)| Ok((k, Value::from_json(v, width)?)))
+                    .collect::<Result<Compound, NbtError>>()?,
+            ),
+        })
+    }
+}
+
+fn number_to_nbt(n: &Number, width: IntegerWidth) -> Result<Value, NbtError> {
+    if let Some(n) = n.as_i64() {
+        Ok(width.convert(n))
+    } else if n.is_u64() {
+        Err(NbtError::Other(Cow::Owned(format!(
+            "JSON integer {n} does not fit in a long"
+        ))))
+    } else {
+        // Numbers that are not integers are always representable as f64.
+        Ok(Value::Double(n.as_f64().unwrap_or_default()))
+    }
+}
+
+/// Widens the numeric elements of a list so that they all share the same tag.
+fn unify_list(list: Vec<Value>) -> Result<Vec<Value>, NbtError> {
+    let Some(first) = list.first() else {
+        return Ok(list);
+    };
+
+    let is_integer = |v: &Value| v.discriminant() <= FieldType::Long as u8;
+    let is_numeric = |v: &Value| v.discriminant() <= FieldType::Double as u8;
+
+    if list
+        .iter()
+        .all(|v| v.discriminant() == first.discriminant())
+    {
+        return Ok(list);
+    }
+
+    if !list.iter().all(is_numeric) {
+        return Err(NbtError::Other(Cow::Borrowed(
+            "JSON array contains elements of different types, which NBT lists cannot represent",
+        )));
+    }
+
+    if list.iter().all(is_integer) {
+        let widest = list.iter().map(Value::discriminant).max().unwrap_or(0);
+        Ok(list
+            .into_iter()
+            .map(|v| {
+                let n = integer_value(&v);
+                match widest {
+                    d if d == FieldType::Short as u8 => Value::Short(n as i16),
+                    d if d == FieldType::Int as u8 => Value::Int(n as i32),
+                    d if d == FieldType::Long as u8 => Value::Long(n),
+                    _ => v,
+                }
+            })
+            .collect())
+    } else {
+        Ok(list
+            .into_iter()
+            .map(|v| match v {
+                Value::Double(_) => v,
+                _ => Value::Double(integer_value(&v) as f64),
+            })
+            .collect())
+    }
+}
+
+fn integer_value(v: &Value) -> i64 {
+    match v {
+        Value::Byte(n) => *n as i64,
+        Value::Short(n) => *n as i64,
+        Value::Int(n) => *n as i64,
+        Value::Long(n) => *n,
+        _ => 0,
+    }
+}
+
+impl From<&Value> for serde_json::Value {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Byte(v) => (*v).into(),
+            Value::Short(v) => (*v).into(),
+            Value::Int(v) => (*v).into(),
+            Value::Long(v) => (*v).into(),
+            Value::Float(v) => (*v).into(),
+            Value::Double(v) => (*v).into(),
+            Value::ByteArray(v) => v.as_slice().into(),
+            Value::String(v) => v.as_str().into(),
+            Value::List(v) => v.iter().map(serde_json::Value::from).collect(),
+            Value::Compound(v) => serde_json::Value::Object(
+                v.iter()
+                    .map(|(k, v)| (k.clone(), serde_json::Value::from(v)))
+                    .collect::<Map<_, _>>(),
+            ),
+            Value::IntArray(v) => v.as_slice().into(),
+            Value::LongArray(v) => v.as_slice().into(),
+        }
+    }
+}
+
+impl From<Value> for serde_json::Value {
+    #[inline]
+    fn from(value: Value) -> Self {
+        serde_json::Value::from(&value)
+    }
+}
+
+impl TryFrom<serde_json::Value> for Value {
+    type Error = NbtError;
+
+    /// Converts a JSON value into NBT, using the narrowest tag for integers.
+    #[inline]
+    fn try_from(json: serde_json::Value) -> Result<Self, Self::Error> {
+        Value::from_json(json, IntegerWidth::Narrowest)
+    }
+}
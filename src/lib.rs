@@ -1,25 +1,106 @@
 //! Implements NBT serialisation and deserialization for three different integer encodings.
+//!
+//! # Features
+//!
+//! - `std` (enabled by default): enables the deserializer, [`Value`] and support for
+//!   [`std::io`] readers and writers. Without it, the crate is `no_std` and only requires `alloc`.
+//!   In that case only serialization into an `alloc::vec::Vec` is available.
+//! - `json`: enables conversion between [`Value`] and `serde_json::Value`, see the `json` module.
+//! - `indexmap`: stores the entries of compounds in an `IndexMap`, preserving their order.
+//!   See [`Compound`].
+//! - `arena`: enables deserializing into `ValueRef`, whose data is allocated in a `bumpalo` arena.
+//!   See the `arena` module.
+//! - `bytemuck`: copies int and long arrays whose byte order matches the host at once when
+//!   deserializing them into [`IntArray`] and [`LongArray`].
+//! - `async`: enables [`to_net_bytes_async`] and [`from_net_bytes_async`], which read and write
+//!   network NBT through `tokio::io`.
+//! - `fxhash`: hashes the keys of compounds using `rustc_hash::FxBuildHasher`, which is faster
+//!   than the default hasher. See [`CompoundHasher`].
+//! - `compression`: enables [`from_reader_auto`], which reads gzip, zlib and uncompressed data
+//!   using `flate2`.
+//! - `heapless`: enables reading and writing fields of type `heapless::String` without
+//!   allocating, see the `serde_helpers` module.
 
-pub use crate::de::{from_be_bytes, from_bytes, from_le_bytes, from_net_bytes, Deserializer};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "async")]
+pub use crate::async_io::{from_net_bytes_async, to_net_bytes_async};
+#[cfg(feature = "compression")]
+pub use crate::compression::from_reader_auto;
+#[cfg(feature = "std")]
+pub use crate::de::{
+    from_be_bytes, from_be_bytes_exact, from_be_slice, from_bedrock_leveldat, from_bytes,
+    from_bytes_dyn, from_bytes_exact, from_bytes_with, from_bytes_with_extras, from_le_bytes,
+    from_le_bytes_exact, from_le_slice, from_net_bytes, from_net_bytes_exact,
+    from_net_bytes_prefix, from_net_slice, from_slice, from_slice_prefix, Deserializer,
+    DeserializerOptions, DuplicateKeyPolicy, ListDeserializer, SliceDeserializer,
+};
+pub use crate::hash::{content_hash, HashingWriter};
+#[cfg(feature = "std")]
+pub use crate::read::{NbtRead, SliceReader};
+pub use crate::scalar::Scalar;
+#[cfg(feature = "std")]
+pub use crate::ser::to_bytes_buffered_in;
 pub use crate::ser::{
-    to_be_bytes, to_be_bytes_in, to_bytes, to_bytes_in, to_le_bytes, to_le_bytes_in, to_net_bytes,
-    to_net_bytes_in, Serializer,
+    encoded_len, to_be_bytes, to_be_bytes_in, to_bedrock_leveldat, to_bytes, to_bytes_dyn,
+    to_bytes_in, to_bytes_with_capacity, to_le_bytes, to_le_bytes_in, to_net_bytes,
+    to_net_bytes_in, to_net_bytes_then_send, FloatPolicy, ListWriter, Serializer,
 };
-pub use crate::value::Value;
+pub use crate::tag_mask::TagMask;
+#[cfg(feature = "std")]
+pub use crate::validate::{max_depth, validate};
+#[cfg(feature = "std")]
+pub use crate::value::{
+    Compound, CompoundEntry, CompoundHasher, OrderedCompound, TreeDisplay, Value,
+};
+#[cfg(feature = "std")]
+pub use crate::value_ser::to_value;
+pub use crate::wrapper::{AsFloat, ByteArrayList, ByteList, Int128, IntArray, LongArray, Uuid};
 pub use byteorder::{BigEndian, LittleEndian};
 
-use std::borrow::Cow;
-use std::fmt::{Debug, Display};
+use alloc::borrow::Cow;
+use alloc::string::ToString;
+use core::fmt::{Debug, Display};
 
-pub use error::NbtError;
+pub use error::{debug_dump, NbtError, Progress, StreamError};
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod test;
 
+#[cfg(feature = "arena")]
+pub mod arena;
+#[cfg(feature = "async")]
+mod async_io;
+#[cfg(feature = "compression")]
+mod compression;
+#[cfg(feature = "std")]
 mod de;
 mod error;
+mod hash;
+pub mod io;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "std")]
+mod read;
+mod scalar;
 mod ser;
+#[cfg(any(feature = "std", feature = "heapless"))]
+pub mod serde_helpers;
+#[cfg(feature = "std")]
+pub mod snbt;
+pub mod tag;
+mod tag_mask;
+#[cfg(feature = "std")]
+pub mod uuid;
+#[cfg(feature = "std")]
+mod validate;
+#[cfg(feature = "std")]
 mod value;
+#[cfg(feature = "std")]
+mod value_ser;
+mod wrapper;
 
 mod private {
     use byteorder::{BigEndian, LittleEndian};
@@ -52,6 +133,21 @@ pub trait EndiannessImpl: private::Sealed {
     /// This is used to match generic types in order to prevent
     /// having to duplicate all deserialisation code three times.
     const AS_ENUM: Variant;
+
+    /// Returns the variant at runtime, allowing generic code to branch on the format.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nbtx::{EndiannessImpl, NetworkLittleEndian, Variant};
+    ///
+    /// assert_eq!(NetworkLittleEndian::variant(), Variant::NetworkEndian);
+    /// assert_eq!(nbtx::BigEndian::variant(), Variant::BigEndian);
+    /// ```
+    #[inline]
+    fn variant() -> Variant {
+        Self::AS_ENUM
+    }
 }
 
 /// NBT format variant.
@@ -70,6 +166,136 @@ pub enum Variant {
     NetworkEndian,
 }
 
+impl Variant {
+    /// Returns whether lengths, ints and longs are written as varints,
+    /// which is only the case for [`NetworkEndian`](Self::NetworkEndian).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nbtx::{EndiannessImpl, Variant};
+    ///
+    /// assert!(nbtx::NetworkLittleEndian::variant().is_varint());
+    /// assert!(!Variant::LittleEndian.is_varint());
+    /// assert!(!Variant::BigEndian.is_varint());
+    /// ```
+    #[inline]
+    pub const fn is_varint(self) -> bool {
+        matches!(self, Self::NetworkEndian)
+    }
+}
+
+/// Width of the length prefix of strings, compound keys and the root name.
+///
+/// The NBT specification uses an unsigned short, but some third-party tools write a single byte
+/// or an unsigned int instead. The prefix uses the byte order of the variant. This only applies
+/// to the big and little endian variants, since [`NetworkEndian`](Variant::NetworkEndian)
+/// always writes string lengths as varints.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum StringLenWidth {
+    /// A single byte, allowing strings of up to 255 bytes.
+    U8,
+    /// Two bytes, allowing strings of up to 65535 bytes. This is the standard width.
+    #[default]
+    U16,
+    /// Four bytes, allowing strings of up to `u32::MAX` bytes.
+    U32,
+}
+
+impl StringLenWidth {
+    /// Returns the length in bytes of the longest string whose length fits in the prefix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nbtx::StringLenWidth;
+    ///
+    /// assert_eq!(StringLenWidth::U8.max_len(), 255);
+    /// assert_eq!(StringLenWidth::default().max_len(), 65535);
+    /// ```
+    #[inline]
+    pub const fn max_len(self) -> usize {
+        match self {
+            Self::U8 => u8::MAX as usize,
+            Self::U16 => u16::MAX as usize,
+            Self::U32 => u32::MAX as usize,
+        }
+    }
+}
+
+/// Width of the length prefix of byte arrays.
+///
+/// The NBT specification uses a signed int, which remains the default. Some third-party tools
+/// write a signed short instead. Like [`StringLenWidth`], this only applies to the big and
+/// little endian variants, since [`NetworkEndian`](Variant::NetworkEndian) always writes
+/// lengths as varints. Lists, int arrays and long arrays always use a signed int.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ByteArrayLenWidth {
+    /// Two bytes, allowing arrays of up to 32767 bytes.
+    I16,
+    /// Four bytes, allowing arrays of up to `i32::MAX` bytes. This is the standard width.
+    #[default]
+    I32,
+}
+
+impl ByteArrayLenWidth {
+    /// Returns the length of the longest byte array whose length fits in the prefix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nbtx::ByteArrayLenWidth;
+    ///
+    /// assert_eq!(ByteArrayLenWidth::I16.max_len(), 32767);
+    /// assert_eq!(ByteArrayLenWidth::default().max_len(), i32::MAX as usize);
+    /// ```
+    #[inline]
+    pub const fn max_len(self) -> usize {
+        match self {
+            Self::I16 => i16::MAX as usize,
+            Self::I32 => i32::MAX as usize,
+        }
+    }
+}
+
+/// Tag that booleans are stored as.
+///
+/// NBT has no boolean type, so booleans are stored as bytes by default. Some legacy formats
+/// store them as shorts instead.
+///
+/// The `with_bool_tag` options take this type rather than a [`FieldType`], so that tags which
+/// cannot hold a boolean, such as [`FieldType::String`], are ruled out at compile time instead
+/// of failing when a value is serialized. [`BoolTag::field_type`] returns the corresponding
+/// [`FieldType`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum BoolTag {
+    /// A [`FieldType::Byte`]. This is the standard tag.
+    #[default]
+    Byte,
+    /// A [`FieldType::Short`].
+    Short,
+}
+
+impl BoolTag {
+    /// Returns the field type of the tag.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nbtx::{BoolTag, FieldType};
+    ///
+    /// assert_eq!(BoolTag::Short.field_type(), FieldType::Short);
+    /// assert_eq!(BoolTag::default().field_type(), FieldType::Byte);
+    /// ```
+    #[inline]
+    pub const fn field_type(self) -> FieldType {
+        match self {
+            Self::Byte => FieldType::Byte,
+            Self::Short => FieldType::Short,
+        }
+    }
+}
+
 /// Used by Bedrock for NBT transferred over the network.
 /// This format is the same as [`LittleEndian`], except that type lengths
 /// (such as for strings or lists), are varints instead of shorts.
@@ -77,8 +303,9 @@ pub enum Variant {
 pub enum NetworkLittleEndian {}
 
 /// NBT field type
-// Compiler complains about unused enum variants even though they're constructed using a transmute.
-#[allow(dead_code)]
+///
+/// The discriminant of each variant is the tag type byte that precedes the value in the
+/// encoded data. These are also available as constants in the [`tag`] module.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub enum FieldType {
@@ -111,19 +338,81 @@ pub enum FieldType {
     LongArray = 12,
 }
 
+impl FieldType {
+    /// Returns the field type with the given tag type byte, or `None` if the byte does not
+    /// correspond to any type.
+    ///
+    /// Use [`TryFrom`] instead to get an [`NbtError::UnknownTag`] for unknown bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nbtx::FieldType;
+    ///
+    /// assert_eq!(FieldType::from_u8(10), Some(FieldType::Compound));
+    /// assert_eq!(FieldType::from_u8(13), None);
+    /// ```
+    pub const fn from_u8(v: u8) -> Option<FieldType> {
+        Some(match v {
+            0 => Self::End,
+            1 => Self::Byte,
+            2 => Self::Short,
+            3 => Self::Int,
+            4 => Self::Long,
+            5 => Self::Float,
+            6 => Self::Double,
+            7 => Self::ByteArray,
+            8 => Self::String,
+            9 => Self::List,
+            10 => Self::Compound,
+            11 => Self::IntArray,
+            12 => Self::LongArray,
+            _ => return None,
+        })
+    }
+
+    /// Returns the name of this type as used in the NBT specification, such as `TAG_Compound`.
+    ///
+    /// This is also how the type is formatted by [`Display`].
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::End => "TAG_End",
+            Self::Byte => "TAG_Byte",
+            Self::Short => "TAG_Short",
+            Self::Int => "TAG_Int",
+            Self::Long => "TAG_Long",
+            Self::Float => "TAG_Float",
+            Self::Double => "TAG_Double",
+            Self::ByteArray => "TAG_Byte_Array",
+            Self::String => "TAG_String",
+            Self::List => "TAG_List",
+            Self::Compound => "TAG_Compound",
+            Self::IntArray => "TAG_Int_Array",
+            Self::LongArray => "TAG_Long_Array",
+        }
+    }
+}
+
+impl Display for FieldType {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<FieldType> for u8 {
+    #[inline]
+    fn from(v: FieldType) -> Self {
+        v as u8
+    }
+}
+
 impl TryFrom<u8> for FieldType {
     type Error = NbtError;
 
+    #[inline]
     fn try_from(v: u8) -> Result<Self, Self::Error> {
-        const LAST_DISC: u8 = FieldType::LongArray as u8;
-        if v > LAST_DISC {
-            return Err(NbtError::TypeOutOfRange { actual: v });
-        }
-
-        // SAFETY: Because `Self` is marked as `repr(u8)`, its layout is guaranteed to start
-        // with a `u8` discriminant as its first field. Additionally, the raw discriminant is verified
-        // to be in the enum's range.
-        Ok(unsafe { std::mem::transmute::<u8, FieldType>(v) })
+        Self::from_u8(v).ok_or(NbtError::UnknownTag(v))
     }
 }
 
@@ -134,6 +423,11 @@ impl serde::de::Error for NbtError {
     {
         NbtError::Other(Cow::Owned(msg.to_string()))
     }
+
+    #[inline]
+    fn missing_field(field: &'static str) -> Self {
+        NbtError::MissingField(field)
+    }
 }
 
 impl serde::ser::Error for NbtError {
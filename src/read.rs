@@ -0,0 +1,176 @@
+use std::io::{self, Read};
+
+use crate::error::StreamError;
+use crate::NbtError;
+
+/// Source of NBT data that can be deserialized.
+///
+/// This is implemented for every [`Read`] and for [`SliceReader`]. Reading from a
+/// [`SliceReader`] avoids the overhead of [`Read`] and allows strings and byte arrays
+/// to be borrowed directly from the input.
+///
+/// This trait is sealed and cannot be implemented outside of this crate.
+pub trait NbtRead<'de>: sealed::Input<'de> {}
+
+impl<'de, R> NbtRead<'de> for R where R: sealed::Input<'de> {}
+
+pub(crate) mod sealed {
+    use crate::NbtError;
+
+    /// Data that was read from an [`NbtRead`](super::NbtRead).
+    pub enum Reference<'de, 's> {
+        /// Data that is borrowed directly from the input.
+        Borrowed(&'de [u8]),
+        /// Data that was copied into a scratch buffer.
+        Copied(&'s [u8]),
+    }
+
+    impl Reference<'_, '_> {
+        #[inline]
+        pub fn as_slice(&self) -> &[u8] {
+            match self {
+                Self::Borrowed(b) => b,
+                Self::Copied(b) => b,
+            }
+        }
+    }
+
+    /// Low-level operations used by the deserializer.
+    pub trait Input<'de> {
+        /// Fills the entire buffer with data from the input.
+        fn read_into(&mut self, buf: &mut [u8]) -> Result<(), NbtError>;
+
+        /// Reads `len` bytes, either by borrowing them from the input or by copying them into
+        /// `scratch`.
+        fn read_slice<'s>(
+            &'s mut self,
+            len: usize,
+            scratch: &'s mut Vec<u8>,
+        ) -> Result<Reference<'de, 's>, NbtError>;
+
+        /// Discards the next `len` bytes.
+        fn skip(&mut self, len: usize) -> Result<(), NbtError>;
+    }
+}
+
+use sealed::{Input, Reference};
+
+/// Largest buffer that is allocated up front when copying data out of a [`Read`].
+const MAX_PREALLOCATION: usize = 64 * 1024;
+
+impl<'de, R> Input<'de> for R
+where
+    R: Read,
+{
+    #[inline]
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<(), NbtError> {
+        self.read_exact(buf)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn read_slice<'s>(
+        &'s mut self,
+        len: usize,
+        scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's>, NbtError> {
+        scratch.clear();
+        if len <= MAX_PREALLOCATION {
+            scratch.resize(len, 0);
+            self.read_exact(scratch)?;
+        } else {
+            // The length comes from the input and cannot be trusted, so the buffer only grows as
+            // data is actually read.
+            let read = self.take(len as u64).read_to_end(scratch)?;
+            if read != len {
+                return Err(StreamError::UnexpectedEof {
+                    expected: len,
+                    remaining: read,
+                }
+                .into());
+            }
+        }
+
+        Ok(Reference::Copied(scratch))
+    }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> Result<(), NbtError> {
+        let skipped = io::copy(&mut self.take(len as u64), &mut io::sink())?;
+        if skipped != len as u64 {
+            return Err(StreamError::UnexpectedEof {
+                expected: len,
+                remaining: skipped as usize,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads NBT data directly from a byte slice.
+///
+/// See [`from_slice`](crate::from_slice) for the most common use.
+#[derive(Debug, Clone)]
+pub struct SliceReader<'de> {
+    data: &'de [u8],
+    position: usize,
+}
+
+impl<'de> SliceReader<'de> {
+    /// Creates a reader that starts at the beginning of the given slice.
+    #[inline]
+    pub const fn new(data: &'de [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// Returns the amount of bytes that have been read so far.
+    #[inline]
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the data that has not been read yet.
+    #[inline]
+    pub fn remaining(&self) -> &'de [u8] {
+        &self.data[self.position..]
+    }
+
+    #[inline]
+    fn take(&mut self, len: usize) -> Result<&'de [u8], NbtError> {
+        let remaining = self.remaining();
+        if remaining.len() < len {
+            return Err(StreamError::UnexpectedEof {
+                expected: len,
+                remaining: remaining.len(),
+            }
+            .into());
+        }
+
+        self.position += len;
+        Ok(&remaining[..len])
+    }
+}
+
+impl<'de> Input<'de> for SliceReader<'de> {
+    #[inline]
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<(), NbtError> {
+        buf.copy_from_slice(self.take(buf.len())?);
+        Ok(())
+    }
+
+    #[inline]
+    fn read_slice<'s>(
+        &'s mut self,
+        len: usize,
+        _scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's>, NbtError> {
+        self.take(len).map(Reference::Borrowed)
+    }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> Result<(), NbtError> {
+        self.take(len).map(|_| ())
+    }
+}
@@ -0,0 +1,156 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use core::fmt;
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Any NBT value that is not a list, compound or array.
+///
+/// This is a lightweight alternative to [`Value`](crate::Value) for fields that can hold
+/// "some number or string". Deserializing a list, compound or array into it results in an error.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scalar {
+    /// A signed byte.
+    Byte(i8),
+    /// A signed short.
+    Short(i16),
+    /// A signed int.
+    Int(i32),
+    /// A signed long.
+    Long(i64),
+    /// A float.
+    Float(f32),
+    /// A double.
+    Double(f64),
+    /// A UTF-8 string.
+    String(String),
+}
+
+macro_rules! impl_from {
+    ($($tag: ident = $ty: ty),+) => {
+        $(
+            impl From<$ty> for Scalar {
+                #[inline]
+                fn from(value: $ty) -> Self {
+                    Self::$tag(value)
+                }
+            }
+        )+
+    }
+}
+
+impl_from!(
+    Byte = i8,
+    Short = i16,
+    Int = i32,
+    Long = i64,
+    Float = f32,
+    Double = f64,
+    String = String
+);
+
+impl From<&str> for Scalar {
+    #[inline]
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Scalar> for crate::Value {
+    fn from(value: Scalar) -> Self {
+        match value {
+            Scalar::Byte(v) => Self::Byte(v),
+            Scalar::Short(v) => Self::Short(v),
+            Scalar::Int(v) => Self::Int(v),
+            Scalar::Long(v) => Self::Long(v),
+            Scalar::Float(v) => Self::Float(v),
+            Scalar::Double(v) => Self::Double(v),
+            Scalar::String(v) => Self::String(v),
+        }
+    }
+}
+
+impl Serialize for Scalar {
+    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Byte(v) => ser.serialize_i8(*v),
+            Self::Short(v) => ser.serialize_i16(*v),
+            Self::Int(v) => ser.serialize_i32(*v),
+            Self::Long(v) => ser.serialize_i64(*v),
+            Self::Float(v) => ser.serialize_f32(*v),
+            Self::Double(v) => ser.serialize_f64(*v),
+            Self::String(v) => ser.serialize_str(v),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Scalar {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ScalarVisitor)
+    }
+}
+
+struct ScalarVisitor;
+
+impl<'de> Visitor<'de> for ScalarVisitor {
+    type Value = Scalar;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an NBT number or string")
+    }
+
+    #[inline]
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Scalar, E> {
+        Ok(Scalar::Byte(v as i8))
+    }
+
+    #[inline]
+    fn visit_i8<E: de::Error>(self, v: i8) -> Result<Scalar, E> {
+        Ok(Scalar::Byte(v))
+    }
+
+    #[inline]
+    fn visit_i16<E: de::Error>(self, v: i16) -> Result<Scalar, E> {
+        Ok(Scalar::Short(v))
+    }
+
+    #[inline]
+    fn visit_i32<E: de::Error>(self, v: i32) -> Result<Scalar, E> {
+        Ok(Scalar::Int(v))
+    }
+
+    #[inline]
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Scalar, E> {
+        Ok(Scalar::Long(v))
+    }
+
+    #[inline]
+    fn visit_f32<E: de::Error>(self, v: f32) -> Result<Scalar, E> {
+        Ok(Scalar::Float(v))
+    }
+
+    #[inline]
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Scalar, E> {
+        Ok(Scalar::Double(v))
+    }
+
+    #[inline]
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Scalar, E> {
+        Ok(Scalar::String(v.to_owned()))
+    }
+
+    #[inline]
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Scalar, E> {
+        Ok(Scalar::String(v))
+    }
+}
@@ -1,13 +1,19 @@
-use std::marker::PhantomData;
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
 
-use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+use byteorder::{BigEndian, LittleEndian};
 use paste::paste;
 use serde::ser::{Impossible, SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple};
 use serde::{ser, Serialize};
 
-use varint_rs::VarintWriter;
-
-use crate::{EndiannessImpl, FieldType, NbtError, NetworkLittleEndian, Variant};
+use crate::io::{ByRef, Write, WriteExt};
+use crate::wrapper::{INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN};
+use crate::{
+    BoolTag, ByteArrayLenWidth, EndiannessImpl, FieldType, NbtError, NetworkLittleEndian,
+    StringLenWidth, Variant,
+};
 
 /// Returns a `not supported` error.
 macro_rules! forward_unsupported {
@@ -37,6 +43,129 @@ macro_rules! forward_unsupported_field {
     }
 }
 
+/// Writes a fixed-size value using the given closure.
+///
+/// Values inside of lists are collected in the pending buffer of the serializer,
+/// so that they can be written to the underlying writer in bulk.
+macro_rules! write_scalar {
+    ($ser: expr, |$w: ident| $body: expr) => {{
+        if $ser.in_list {
+            let $w = &mut $ser.pending;
+            $body?;
+
+            if $ser.pending.len() >= PENDING_CAPACITY {
+                $ser.flush_pending()?;
+            }
+        } else {
+            let $w = &mut $ser.writer;
+            $body?;
+        }
+    }};
+}
+
+/// Determines how the serializer handles non-finite floats.
+///
+/// Some NBT parsers cannot handle NaN or infinite values in float and double tags.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FloatPolicy {
+    /// Writes floats as they are, including the exact bit pattern of NaN values.
+    ///
+    /// Floats are written and read through their raw bits, so signaling NaNs, NaN payloads,
+    /// subnormals and negative zero are deserialized with the same bits in every variant.
+    #[default]
+    Passthrough,
+    /// Returns [`NbtError::NonFiniteFloat`] when a NaN or infinite value is serialized.
+    Reject,
+    /// Writes every NaN using the same bit pattern. Infinite values are written as they are.
+    Canonicalize,
+}
+
+/// Declared and actual amount of elements of a list that is being serialized.
+#[derive(Debug, Clone, Copy, Default)]
+struct ListLen {
+    declared: usize,
+    written: usize,
+    /// Whether everything that precedes the elements has been written when the sequence was
+    /// started. This is the case for int and long arrays, which have no element type, and for
+    /// lists written with a [`ListWriter`].
+    prefixed: bool,
+}
+
+/// Writes the elements of a list one at a time.
+///
+/// Created by [`Serializer::begin_list`]. The list must be finished with
+/// [`end_list`](Self::end_list). If the writer is dropped before that, the list stays
+/// incomplete and [`Serializer::flush`] and [`Serializer::into_inner`] return
+/// [`NbtError::UnfinishedList`].
+#[derive(Debug)]
+#[must_use = "the list is incomplete until `end_list` is called"]
+pub struct ListWriter<'a, W, E>
+where
+    W: Write,
+    E: EndiannessImpl,
+{
+    ser: &'a mut Serializer<W, E>,
+    /// Type of the elements.
+    ty: FieldType,
+}
+
+impl<W, E> ListWriter<'_, W, E>
+where
+    W: Write,
+    E: EndiannessImpl,
+{
+    /// Writes the next element of the list.
+    ///
+    /// Returns [`NbtError::UnexpectedType`] if the first element is not of the type that was
+    /// passed to [`Serializer::begin_list`], and [`NbtError::LengthMismatch`] if the list is
+    /// already complete.
+    pub fn write_element<T>(&mut self, element: &T) -> Result<(), NbtError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let list = self.ser.lists.last().copied().unwrap_or_default();
+        if list.written == list.declared {
+            return Err(NbtError::LengthMismatch {
+                declared: list.declared,
+                actual: list.written + 1,
+            });
+        }
+
+        if list.written == 0 {
+            let mut entry = self.ser.entry_serializer();
+            entry.in_list = true;
+            if element.serialize(FieldTypeSerializer::new(&mut entry))? {
+                return Err(NbtError::CannotSkipInList);
+            }
+
+            let actual = entry.into_inner()?.first().copied().unwrap_or_default();
+            if actual != self.ty as u8 {
+                return Err(NbtError::UnexpectedType {
+                    expected: self.ty,
+                    actual: FieldType::try_from(actual)?,
+                });
+            }
+        }
+
+        self.ser.serialize_list_element(element)
+    }
+
+    /// Finishes the list, verifying that the declared amount of elements was written.
+    #[inline]
+    pub fn end_list(self) -> Result<(), NbtError> {
+        self.ser.close_list()
+    }
+}
+
+/// Converts the length of a list or array to its signed length prefix.
+#[inline]
+fn len_prefix(len: usize) -> Result<i32, NbtError> {
+    i32::try_from(len).map_err(|_| NbtError::LengthTooLarge { len })
+}
+
+/// Amount of bytes of list elements that are collected before they are written.
+const PENDING_CAPACITY: usize = 8 * 1024;
+
 /// Serializes the given data in any endian format.
 ///
 /// See [`to_bytes_in`] for an alternative that serializes into the given writer, instead
@@ -62,17 +191,123 @@ where
     let mut ser = Serializer::<_, E>::new(Vec::new());
     v.serialize(&mut ser)?;
 
-    Ok(ser.into_inner())
+    ser.into_inner()
+}
+
+/// Returns the amount of bytes that the given data occupies when it is serialized in any endian
+/// format.
+///
+/// The data is serialized into a writer that only counts the bytes, so no output buffer is
+/// allocated. The result is equal to the length of the output of [`to_bytes`], which makes it
+/// possible to allocate a buffer of the exact size up front.
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() {
+///  #[derive(serde::Serialize)]
+///  struct Data {
+///     value: i32
+///  }
+///
+///  let data = Data { value: 300 };
+///  let len = nbtx::encoded_len::<nbtx::NetworkLittleEndian>(&data).unwrap();
+///
+///  assert_eq!(len, nbtx::to_net_bytes(&data).unwrap().len());
+/// # }
+/// ```
+pub fn encoded_len<E>(v: &(impl Serialize + ?Sized)) -> Result<usize, NbtError>
+where
+    E: EndiannessImpl,
+{
+    let mut ser = Serializer::<_, E>::new(Counter(0));
+    v.serialize(&mut ser)?;
+
+    Ok(ser.into_inner()?.0)
+}
+
+/// Writer that discards all data written into it, only counting its length.
+struct Counter(usize);
+
+impl Write for Counter {
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), NbtError> {
+        self.0 += buf.len();
+        Ok(())
+    }
+}
+
+/// Serializes the given data in the variant that is selected at runtime.
+///
+/// This is equivalent to calling [`to_bytes`] with the type matching `variant`.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::collections::BTreeMap;
+/// # use nbtx::Variant;
+///  let value = BTreeMap::from([("x", 5)]);
+///
+///  let encoded = nbtx::to_bytes_dyn(Variant::NetworkEndian, &value).unwrap();
+///  assert_eq!(encoded, nbtx::to_net_bytes(&value).unwrap());
+/// ```
+pub fn to_bytes_dyn(variant: Variant, v: &(impl Serialize + ?Sized)) -> Result<Vec<u8>, NbtError> {
+    match variant {
+        Variant::BigEndian => to_bytes::<BigEndian>(v),
+        Variant::LittleEndian => to_bytes::<LittleEndian>(v),
+        Variant::NetworkEndian => to_bytes::<NetworkLittleEndian>(v),
+    }
+}
+
+/// Serializes the given data in any endian format into a buffer that has room for at least
+/// `capacity` bytes.
+///
+/// This is the same as [`to_bytes`], but avoids repeatedly growing the buffer when the
+/// approximate size of the output is known in advance.
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() {
+///  #[derive(serde::Serialize, serde::Deserialize)]
+///  struct Data {
+///     value: String
+///  }
+///
+///  let data = Data { value: "Hello, World!".to_owned() };
+///  let encoded = nbtx::to_bytes_with_capacity::<nbtx::BigEndian>(&data, 64).unwrap();
+/// # }
+/// ```
+pub fn to_bytes_with_capacity<E>(
+    v: &(impl Serialize + ?Sized),
+    capacity: usize,
+) -> Result<Vec<u8>, NbtError>
+where
+    E: EndiannessImpl,
+{
+    let mut ser = Serializer::<_, E>::new(Vec::with_capacity(capacity));
+    v.serialize(&mut ser)?;
+
+    ser.into_inner()
 }
 
 /// Serializes the given data in any endian format.
 ///
 /// See [`to_bytes`] for an alternative just returns a new buffer, instead of using an existing writer.
 ///
+/// The writer is flushed before returning, so buffered writers can be dropped afterwards.
+///
+/// # Errors
+///
+/// The data is written while it is being serialized. If serialization fails, or the writer
+/// returns an error (such as [`WouldBlock`](std::io::ErrorKind::WouldBlock) from a
+/// non-blocking socket), whatever was written up to that point is left in the writer and the
+/// serializer cannot be resumed. Use [`to_bytes`] and write the result yourself when a partially
+/// written stream cannot be recovered from.
+///
 /// # Example
 ///
 /// ```rust
-/// # use std::io::Cursor;
 /// # fn main() {
 /// #[derive(serde::Serialize, serde::Deserialize)]
 ///  struct Data {
@@ -80,22 +315,65 @@ where
 ///  }
 ///
 ///  let data = Data { value: "Hello, World!".to_owned() };
-///  let mut writer = Cursor::new(Vec::new());
+///  let mut writer = Vec::new();
 ///
 ///  nbtx::to_bytes_in::<nbtx::BigEndian>(&mut writer, &data).unwrap();
 /// # }
 /// ```
+///
+/// Any [`std::io::Write`] can be used as the writer, without importing `byteorder`:
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// fn save<W: std::io::Write>(writer: &mut W, value: &nbtx::Value) -> Result<(), nbtx::NbtError> {
+///     nbtx::to_bytes_in::<nbtx::LittleEndian>(writer, value)?;
+///     nbtx::to_net_bytes_in(writer, value)
+/// }
+/// ```
 pub fn to_bytes_in<E>(
-    writer: &mut impl WriteBytesExt,
+    writer: &mut impl Write,
     v: &(impl Serialize + ?Sized),
 ) -> Result<(), NbtError>
 where
     E: EndiannessImpl,
 {
-    let mut ser = Serializer::<_, E>::new(writer);
+    let mut ser = Serializer::<_, E>::new(ByRef(writer));
     v.serialize(&mut ser)?;
 
-    Ok(())
+    ser.flush()
+}
+
+/// Serializes the given data in any endian format, buffering the output before it is
+/// written to `writer`.
+///
+/// This is useful when writing to an unbuffered writer, such as a [`File`](std::fs::File),
+/// where every write results in a system call. The buffer is flushed before returning.
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() {
+/// #[derive(serde::Serialize, serde::Deserialize)]
+///  struct Data {
+///     value: String
+///  }
+///
+///  let data = Data { value: "Hello, World!".to_owned() };
+///  let mut writer = Vec::new();
+///
+///  nbtx::to_bytes_buffered_in::<nbtx::BigEndian>(&mut writer, &data).unwrap();
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn to_bytes_buffered_in<E>(
+    writer: impl std::io::Write,
+    v: &(impl Serialize + ?Sized),
+) -> Result<(), NbtError>
+where
+    E: EndiannessImpl,
+{
+    // This flushes the buffer as well.
+    to_bytes_in::<E>(&mut std::io::BufWriter::new(writer), v)
 }
 
 /// Serializes the given data in network little endian format.
@@ -135,7 +413,6 @@ where
 /// # Example
 ///
 /// ```rust
-/// # use std::io::Cursor;
 /// # fn main() {
 ///  #[derive(serde::Serialize, serde::Deserialize)]
 ///  struct Data {
@@ -143,7 +420,7 @@ where
 ///  }
 ///
 ///  let data = Data { value: "Hello, World!".to_owned() };
-///  let mut writer = Cursor::new(Vec::new());
+///  let mut writer = Vec::new();
 ///
 ///  let encoded = nbtx::to_net_bytes_in(&mut writer, &data).unwrap();
 /// # }
@@ -151,12 +428,51 @@ where
 #[inline]
 pub fn to_net_bytes_in<T, W>(writer: &mut W, v: &T) -> Result<(), NbtError>
 where
-    W: WriteBytesExt,
+    W: Write,
     T: ?Sized + Serialize,
 {
     to_bytes_in::<NetworkLittleEndian>(writer, v)
 }
 
+/// Serializes the given data in network little endian format into memory, and then writes it
+/// to `writer` all at once.
+///
+/// Unlike [`to_net_bytes_in`], nothing is written if serialization fails, so an unsupported
+/// value cannot leave half of a packet in a connection. The encoded data is returned on success,
+/// so that it can be sent again without serializing it a second time.
+///
+/// The data is written with a single call to [`Write::write_all`]. If the writer itself fails,
+/// the stream may still contain part of the data, and the caller has to resend the returned
+/// buffer from the start or drop the connection.
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() {
+///  #[derive(serde::Serialize, serde::Deserialize)]
+///  struct Data {
+///     value: String
+///  }
+///
+///  let data = Data { value: "Hello, World!".to_owned() };
+///  let mut socket = Vec::new();
+///
+///  nbtx::to_net_bytes_then_send(&mut socket, &data).unwrap();
+///  assert_eq!(socket, nbtx::to_net_bytes(&data).unwrap());
+/// # }
+/// ```
+pub fn to_net_bytes_then_send<T, W>(writer: &mut W, v: &T) -> Result<Vec<u8>, NbtError>
+where
+    W: Write,
+    T: ?Sized + Serialize,
+{
+    let encoded = to_net_bytes(v)?;
+    writer.write_all(&encoded)?;
+    writer.flush()?;
+
+    Ok(encoded)
+}
+
 /// Serializes the given data in big endian format.
 ///
 /// This is the format used by Minecraft: Java Edition.
@@ -194,7 +510,6 @@ where
 /// # Example
 ///
 /// ```rust
-/// # use std::io::Cursor;
 /// # fn main() {
 ///  #[derive(serde::Serialize, serde::Deserialize)]
 ///  struct Data {
@@ -202,7 +517,7 @@ where
 ///  }
 ///
 ///  let data = Data { value: "Hello, World!".to_owned() };
-///  let mut writer = Cursor::new(Vec::new());
+///  let mut writer = Vec::new();
 ///
 ///  let encoded = nbtx::to_be_bytes_in(&mut writer, &data).unwrap();
 /// # }
@@ -210,7 +525,7 @@ where
 #[inline]
 pub fn to_be_bytes_in<T, W>(writer: &mut W, v: &T) -> Result<(), NbtError>
 where
-    W: WriteBytesExt,
+    W: Write,
     T: ?Sized + Serialize,
 {
     to_bytes_in::<BigEndian>(writer, v)
@@ -253,7 +568,6 @@ where
 /// # Example
 ///
 /// ```rust
-/// # use std::io::Cursor;
 /// # fn main() {
 ///  #[derive(serde::Serialize, serde::Deserialize)]
 ///  struct Data {
@@ -261,7 +575,7 @@ where
 ///  }
 ///
 ///  let data = Data { value: "Hello, World!".to_owned() };
-///  let mut writer = Cursor::new(Vec::new());
+///  let mut writer = Vec::new();
 ///
 ///  let encoded = nbtx::to_le_bytes_in(&mut writer, &data).unwrap();
 /// # }
@@ -269,31 +583,122 @@ where
 #[inline]
 pub fn to_le_bytes_in<T, W>(writer: &mut W, v: &T) -> Result<(), NbtError>
 where
-    W: WriteBytesExt,
+    W: Write,
     T: ?Sized + Serialize,
 {
     to_bytes_in::<LittleEndian>(writer, v)
 }
 
+/// Serializes the given data as a Bedrock `level.dat` file.
+///
+/// The file starts with an 8-byte header containing the storage `version` and the length of the
+/// NBT data, both as little endian ints. The data itself uses the little endian format, with an
+/// empty root name like the files written by the game.
+///
+/// See [`from_bedrock_leveldat`](crate::from_bedrock_leveldat) for the reverse.
+///
+/// # Example
+///
+/// ```rust
+/// #[derive(serde::Serialize)]
+/// struct LevelDat {
+///     #[serde(rename = "LevelName")]
+///     level_name: String,
+/// }
+///
+/// let level = LevelDat { level_name: "My World".to_owned() };
+/// let encoded = nbtx::to_bedrock_leveldat(10, &level).unwrap();
+///
+/// assert_eq!(encoded[..4], [10, 0, 0, 0]);
+/// assert_eq!(encoded[4..8], (encoded.len() as i32 - 8).to_le_bytes());
+/// ```
+pub fn to_bedrock_leveldat<T>(version: i32, v: &T) -> Result<Vec<u8>, NbtError>
+where
+    T: ?Sized + Serialize,
+{
+    let mut out = Vec::new();
+    out.write_i32::<LittleEndian>(version)?;
+    // The length is filled in once the data has been written.
+    out.write_i32::<LittleEndian>(0)?;
+
+    let mut ser = Serializer::<_, LittleEndian>::new(out).with_root_name(Some(""));
+    v.serialize(&mut ser)?;
+    let mut out = ser.into_inner()?;
+
+    let len = len_prefix(out.len() - LEVELDAT_HEADER_LEN)?;
+    out[4..LEVELDAT_HEADER_LEN].copy_from_slice(&len.to_le_bytes());
+
+    Ok(out)
+}
+
+/// Size of the header that precedes the NBT data of a Bedrock `level.dat` file.
+pub(crate) const LEVELDAT_HEADER_LEN: usize = 8;
+
 /// NBT data serializer.
+///
+/// The root value is usually a struct or map, which is written as a named compound.
+/// Any other root value is written as a tag with an empty name, as Bedrock does for standalone
+/// values. For example, serializing `42i32` in the big endian format produces
+/// `[3, 0, 0, 0, 0, 0, 42]`: the int tag type, the length of the empty name and the payload.
+///
+/// Struct fields and map entries that are `None` are skipped, and `Some` values are written as
+/// the value they contain. Every element of a list has to be written, so a `None` list element
+/// results in [`NbtError::CannotSkipInList`].
+///
+/// Fields marked with `#[serde(flatten)]` are written directly into the parent compound.
+/// Serde serializes structs with flattened fields as maps, so such a struct has an empty
+/// name when it is the root value.
+///
+/// Every value is visited twice, once to determine its tag type and once to write its payload.
+/// Wrappers such as `Box`, `RefCell` and `Mutex` are transparent and produce the tag of the
+/// value they contain. A `RefCell` that is mutably borrowed, or a poisoned `Mutex`, results in
+/// an error.
 #[derive(Debug)]
 pub struct Serializer<W, E>
 where
-    W: WriteBytesExt,
+    W: Write,
     E: EndiannessImpl,
 {
     writer: W,
     /// Whether this is the first data to be written.
-    /// This makes sure that the name and type of the root compound are written.
+    /// This makes sure that the name and type of the root value are written.
     is_initial: bool,
-    /// Stores the length of the list that is currently being serialised.
-    len: usize,
+    /// Lengths of the lists that are currently being serialised, innermost last.
+    lists: Vec<ListLen>,
+    /// Whether compound entries are written in lexicographic key order.
+    sorted_keys: bool,
+    /// How non-finite floats are written.
+    float_policy: FloatPolicy,
+    /// Entries of the compound that is currently being serialised, when keys are sorted.
+    entries: Vec<(String, Vec<u8>)>,
+    /// Whether integers are written using the smallest tag that can hold their value.
+    minimize_integers: bool,
+    /// Whether the value that is currently being serialised is a list element.
+    in_list: bool,
+    /// Encoded list elements that have not been written yet.
+    pending: Vec<u8>,
+    /// Amount of compounds that are currently being serialised.
+    compounds: usize,
+    /// Whether the end tag of the root compound is written.
+    root_end: bool,
+    /// Name that is written for the root value instead of the name of the root struct.
+    root_name: Option<String>,
+    /// Array tag that the next sequence is written as, instead of a list.
+    next_array: Option<FieldType>,
+    /// Buffer that values passed to `collect_str` are formatted into, reused between calls.
+    scratch: String,
+    /// Width of the length prefix of strings in the big and little endian variants.
+    string_len_width: StringLenWidth,
+    /// Width of the length prefix of byte arrays in the big and little endian variants.
+    byte_array_len_width: ByteArrayLenWidth,
+    /// Tag that booleans are written as.
+    bool_tag: BoolTag,
     _marker: PhantomData<E>,
 }
 
 impl<W, E> Serializer<W, E>
 where
-    W: WriteBytesExt,
+    W: Write,
     E: EndiannessImpl,
 {
     /// Creates a new and empty serializer.
@@ -302,22 +707,600 @@ where
         Serializer {
             writer: w,
             is_initial: true,
-            len: 0,
+            lists: Vec::new(),
+            sorted_keys: false,
+            float_policy: FloatPolicy::Passthrough,
+            entries: Vec::new(),
+            minimize_integers: false,
+            in_list: false,
+            pending: Vec::new(),
+            compounds: 0,
+            root_end: true,
+            root_name: None,
+            next_array: None,
+            scratch: String::new(),
+            string_len_width: StringLenWidth::U16,
+            byte_array_len_width: ByteArrayLenWidth::I32,
+            bool_tag: BoolTag::Byte,
             _marker: PhantomData,
         }
     }
 
+    /// Writes any list elements that are still collected by the serializer, and then flushes the
+    /// inner writer.
+    ///
+    /// Call this before dropping a serializer that writes into a buffered writer, such as a
+    /// [`BufWriter`](std::io::BufWriter), or the end of the data may be lost. The `to_*_in`
+    /// functions do this before returning.
+    ///
+    /// Returns [`NbtError::UnfinishedList`] if a list is still open, after writing and flushing
+    /// everything that was serialized so far.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Serialize;
+    /// # use std::io::BufWriter;
+    /// # #[cfg(feature = "std")] {
+    /// let mut ser = nbtx::Serializer::<_, nbtx::BigEndian>::new(BufWriter::new(Vec::new()));
+    /// 5i32.serialize(&mut ser).unwrap();
+    /// ser.flush().unwrap();
+    ///
+    /// assert_eq!(ser.into_inner().unwrap().buffer().len(), 0);
+    /// # }
+    /// ```
+    pub fn flush(&mut self) -> Result<(), NbtError> {
+        self.flush_pending()?;
+        self.writer.flush()?;
+        self.check_lists_closed()
+    }
+
     /// Consumes the serialiser and returns the inner writer.
+    ///
+    /// List elements that are still collected by the serializer are written first, so that the
+    /// writer holds everything that was serialized. The inner writer itself is not flushed.
+    ///
+    /// Returns [`NbtError::UnfinishedList`] if a list is still open, since the data in the
+    /// writer is incomplete.
+    pub fn into_inner(mut self) -> Result<W, NbtError> {
+        self.flush_pending()?;
+        self.check_lists_closed()?;
+        Ok(self.writer)
+    }
+
+    /// Prepares the serializer for writing another root value to the same writer.
+    ///
+    /// Without this, a second value is written as if it were part of the first one and lacks
+    /// the tag type and name of its root. Options set through the `with_*` methods are kept.
+    ///
+    /// List elements that are still collected by the serializer are written before the state
+    /// is cleared, rather than being discarded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use serde::Serialize;
+    /// # use nbtx::{BigEndian, Serializer};
+    /// # #[derive(serde::Serialize)]
+    /// # struct Data {
+    /// #     value: i32,
+    /// # }
+    /// let mut ser = Serializer::<_, BigEndian>::new(Vec::new());
+    /// Data { value: 1 }.serialize(&mut ser).unwrap();
+    /// ser.reset().unwrap();
+    /// Data { value: 2 }.serialize(&mut ser).unwrap();
+    ///
+    /// let first = nbtx::to_be_bytes(&Data { value: 1 }).unwrap();
+    /// let second = nbtx::to_be_bytes(&Data { value: 2 }).unwrap();
+    /// assert_eq!(ser.into_inner().unwrap(), [first, second].concat());
+    /// ```
+    pub fn reset(&mut self) -> Result<(), NbtError> {
+        self.flush_pending()?;
+
+        self.is_initial = true;
+        self.lists.clear();
+        self.entries.clear();
+        self.in_list = false;
+        self.compounds = 0;
+        self.next_array = None;
+
+        Ok(())
+    }
+
+    /// Sets how non-finite floats (NaN and infinity) are written.
+    ///
+    /// Defaults to [`FloatPolicy::Passthrough`].
+    #[inline]
+    pub fn with_float_policy(mut self, policy: FloatPolicy) -> Self {
+        self.float_policy = policy;
+        self
+    }
+
+    /// Stores every integer in the smallest tag that can hold its value, rather than the tag
+    /// matching the width of its Rust type. For example, an `i64` with a value of `5` is written
+    /// as a `Byte`.
+    ///
+    /// This preserves the value of every integer, but not its type. Reading the data back into
+    /// [`Value`](crate::Value) works as usual, but typed fields must be able to accept the
+    /// narrower tags.
+    ///
+    /// Elements of lists are not affected, since every element of a list must have the same type.
+    #[inline]
+    pub fn with_minimized_integer_width(mut self, minimize: bool) -> Self {
+        self.minimize_integers = minimize;
+        self
+    }
+
+    /// Writes the entries of every compound in lexicographic key order, so that maps with a
+    /// nondeterministic iteration order, such as `HashMap`, always produce the same bytes.
+    ///
+    /// This also applies to the fields of structs. Since entries have to be collected before
+    /// they can be sorted, this is slower than writing them as they are produced and is therefore
+    /// disabled by default.
+    #[inline]
+    pub fn with_sorted_keys(mut self, sorted: bool) -> Self {
+        self.sorted_keys = sorted;
+        self
+    }
+
+    /// Sets the width of the length prefix of strings, compound keys and the root name.
+    ///
+    /// Defaults to [`StringLenWidth::U16`], as required by the NBT specification. Other widths
+    /// are only useful to exchange data with tools that use them. Strings that are too long for
+    /// the prefix fail with [`NbtError::StringTooLong`]. This has no effect on the network
+    /// variant, which always writes string lengths as varints.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::collections::BTreeMap;
+    /// # use nbtx::{Serializer, StringLenWidth};
+    /// # use serde::Serialize;
+    /// let mut ser = Serializer::<_, nbtx::BigEndian>::new(Vec::new())
+    ///     .with_string_len_width(StringLenWidth::U8);
+    /// BTreeMap::from([("a", 1i8)]).serialize(&mut ser).unwrap();
+    ///
+    /// assert_eq!(ser.into_inner().unwrap(), [10, 0, 1, 1, b'a', 1, 0]);
+    /// ```
+    #[inline]
+    pub fn with_string_len_width(mut self, width: StringLenWidth) -> Self {
+        self.string_len_width = width;
+        self
+    }
+
+    /// Sets the width of the length prefix of byte arrays.
+    ///
+    /// Defaults to [`ByteArrayLenWidth::I32`], as required by the NBT specification. Arrays that
+    /// are too long for a shorter prefix fail with [`NbtError::ByteArrayTooLong`]. This has no
+    /// effect on the network variant, which always writes lengths as varints.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::{ByteArrayLenWidth, Serializer};
+    /// # use serde::Serialize;
+    /// # #[cfg(feature = "std")] {
+    /// let mut ser = Serializer::<_, nbtx::BigEndian>::new(Vec::new())
+    ///     .with_byte_array_len_width(ByteArrayLenWidth::I16);
+    /// nbtx::Value::ByteArray(vec![1, 2]).serialize(&mut ser).unwrap();
+    ///
+    /// assert_eq!(ser.into_inner().unwrap(), [7, 0, 0, 0, 2, 1, 2]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn with_byte_array_len_width(mut self, width: ByteArrayLenWidth) -> Self {
+        self.byte_array_len_width = width;
+        self
+    }
+
+    /// Sets the tag that booleans are written as.
+    ///
+    /// Defaults to [`BoolTag::Byte`]. Some legacy formats store booleans as shorts instead.
+    /// Such data has to be read by a deserializer with the same
+    /// [`with_bool_tag`](crate::Deserializer::with_bool_tag) option.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::{BoolTag, Serializer};
+    /// # use serde::Serialize;
+    /// let mut ser = Serializer::<_, nbtx::BigEndian>::new(Vec::new())
+    ///     .with_bool_tag(BoolTag::Short);
+    /// true.serialize(&mut ser).unwrap();
+    ///
+    /// assert_eq!(ser.into_inner().unwrap(), [2, 0, 0, 0, 1]);
+    /// ```
+    #[inline]
+    pub fn with_bool_tag(mut self, tag: BoolTag) -> Self {
+        self.bool_tag = tag;
+        self
+    }
+
+    /// Sets whether the end tag of the root compound is written. Defaults to `true`.
+    ///
+    /// When disabled, the output is left open after the last entry of the root compound, so that
+    /// further entries can be appended by serializing more values with the same serializer.
+    /// Only the first value writes the header of the root compound.
+    ///
+    /// **The output is not valid NBT until the caller writes the end tag (a single `0` byte)
+    /// themselves.** Any reader will fail or read past the end of the data otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::collections::BTreeMap;
+    /// # use nbtx::Serializer;
+    /// # use serde::Serialize;
+    ///  let mut ser = Serializer::<_, nbtx::BigEndian>::new(Vec::new()).with_root_end(false);
+    ///  BTreeMap::from([("a", 1)]).serialize(&mut ser).unwrap();
+    ///  BTreeMap::from([("b", 2)]).serialize(&mut ser).unwrap();
+    ///
+    ///  let mut encoded = ser.into_inner().unwrap();
+    ///  encoded.push(0);
+    ///
+    ///  let both = BTreeMap::from([("a", 1), ("b", 2)]);
+    ///  assert_eq!(encoded, nbtx::to_be_bytes(&both).unwrap());
+    /// ```
+    #[inline]
+    pub fn with_root_end(mut self, write: bool) -> Self {
+        self.root_end = write;
+        self
+    }
+
+    /// Sets the name that is written for the root value.
+    ///
+    /// By default, the root of a struct is named after the Rust type and any other root has an
+    /// empty name. Passing `Some` overrides this for every root value, so `Some("")` produces
+    /// the empty name that Bedrock expects. Passing `None` restores the default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::Serializer;
+    /// # use serde::Serialize;
+    /// #[derive(Serialize)]
+    /// struct LevelData {
+    ///     seed: i64,
+    /// }
+    ///
+    /// let mut ser = Serializer::<_, nbtx::LittleEndian>::new(Vec::new()).with_root_name(Some(""));
+    /// LevelData { seed: 0 }.serialize(&mut ser).unwrap();
+    ///
+    /// let encoded = ser.into_inner().unwrap();
+    /// assert_eq!(encoded[..3], [10, 0, 0]);
+    /// ```
     #[inline]
-    pub fn into_inner(self) -> W {
-        self.writer
+    pub fn with_root_name(mut self, name: Option<&str>) -> Self {
+        self.root_name = name.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Starts writing a list of `len` elements of type `ty`, without collecting the elements
+    /// first.
+    ///
+    /// This complements [`serialize_seq`](ser::Serializer::serialize_seq) for producers that know
+    /// the amount of elements up front, but generate them one at a time. The element type and
+    /// length are written immediately, after which each element is written by
+    /// [`ListWriter::write_element`]. Scalar elements are buffered in chunks, so memory usage does
+    /// not depend on the length of the list.
+    ///
+    /// If nothing has been written yet, the list is written as the root value, named after the
+    /// [root name](Self::with_root_name) or empty. Otherwise only the payload of the list is
+    /// written.
+    ///
+    /// The list must be finished with [`ListWriter::end_list`], which verifies that exactly
+    /// `len` elements were written. Until then, [`flush`](Self::flush) and
+    /// [`into_inner`](Self::into_inner) return [`NbtError::UnfinishedList`]. Only the type of the
+    /// first element is verified against `ty`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::{FieldType, Serializer};
+    /// let mut ser = Serializer::<_, nbtx::BigEndian>::new(Vec::new());
+    ///
+    /// let mut list = ser.begin_list(FieldType::Int, 1000).unwrap();
+    /// for i in 0..1000 {
+    ///     list.write_element(&i).unwrap();
+    /// }
+    /// list.end_list().unwrap();
+    ///
+    /// let encoded = ser.into_inner().unwrap();
+    /// assert_eq!(encoded, nbtx::to_be_bytes(&(0..1000).collect::<Vec<i32>>()).unwrap());
+    /// ```
+    pub fn begin_list(
+        &mut self,
+        ty: FieldType,
+        len: usize,
+    ) -> Result<ListWriter<'_, W, E>, NbtError> {
+        let prefix = len_prefix(len)?;
+        if ty == FieldType::End && len > 0 {
+            return Err(NbtError::InvalidListType { len: prefix as u32 });
+        }
+
+        self.flush_pending()?;
+        self.open_list(len)?;
+        self.writer.write_u8(ty as u8)?;
+        self.write_list_len(len)?;
+
+        if let Some(list) = self.lists.last_mut() {
+            list.prefixed = true;
+        }
+
+        Ok(ListWriter { ser: self, ty })
+    }
+
+    /// Starts a compound.
+    #[inline]
+    fn begin_compound(&mut self) {
+        self.compounds += 1;
+    }
+
+    /// Finishes the current compound by writing its end tag, unless it is an unterminated root.
+    fn end_compound(&mut self) -> Result<(), NbtError> {
+        if self.sorted_keys {
+            self.write_sorted_entries()?;
+        }
+
+        self.compounds -= 1;
+        let is_root = self.compounds == 0 && self.lists.is_empty();
+        if self.root_end || !is_root {
+            self.writer.write_u8(FieldType::End as u8)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the tag that the given integer should be narrowed to, if integers are minimized.
+    #[inline]
+    fn narrowed_tag(&self, v: i64) -> Option<FieldType> {
+        if !self.minimize_integers || self.in_list {
+            return None;
+        }
+
+        Some(if i8::try_from(v).is_ok() {
+            FieldType::Byte
+        } else if i16::try_from(v).is_ok() {
+            FieldType::Short
+        } else if i32::try_from(v).is_ok() {
+            FieldType::Int
+        } else {
+            FieldType::Long
+        })
+    }
+
+    /// Writes an integer using the narrowed tag, if any.
+    ///
+    /// Returns whether the integer was written.
+    fn write_narrowed(&mut self, v: i64) -> Result<bool, NbtError> {
+        match self.narrowed_tag(v) {
+            Some(FieldType::Byte) => self.writer.write_i8(v as i8)?,
+            Some(FieldType::Short) => match E::AS_ENUM {
+                Variant::BigEndian => self.writer.write_i16::<BigEndian>(v as i16)?,
+                Variant::LittleEndian | Variant::NetworkEndian => {
+                    self.writer.write_i16::<LittleEndian>(v as i16)?
+                }
+            },
+            Some(FieldType::Int) => match E::AS_ENUM {
+                Variant::BigEndian => self.writer.write_i32::<BigEndian>(v as i32)?,
+                Variant::LittleEndian => self.writer.write_i32::<LittleEndian>(v as i32)?,
+                Variant::NetworkEndian => self.writer.write_i32_varint(v as i32)?,
+            },
+            // Longs are written by the caller.
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Configures the serializer to produce a canonical encoding, where compound keys are
+    /// sorted and all NaN floats share the same bit pattern.
+    #[inline]
+    pub(crate) fn canonical(mut self) -> Self {
+        self.sorted_keys = true;
+        self.float_policy = FloatPolicy::Canonicalize;
+        self
+    }
+
+    /// Creates a serializer that writes a single compound entry into a separate buffer,
+    /// using the same options as this serializer.
+    fn entry_serializer(&self) -> Serializer<Vec<u8>, E> {
+        Serializer {
+            writer: Vec::new(),
+            is_initial: false,
+            lists: Vec::new(),
+            sorted_keys: self.sorted_keys,
+            float_policy: self.float_policy,
+            entries: Vec::new(),
+            minimize_integers: self.minimize_integers,
+            in_list: false,
+            pending: Vec::new(),
+            compounds: 0,
+            root_end: true,
+            root_name: None,
+            next_array: None,
+            scratch: String::new(),
+            string_len_width: self.string_len_width,
+            byte_array_len_width: self.byte_array_len_width,
+            bool_tag: self.bool_tag,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Starts a list with the given declared length.
+    ///
+    /// If the sequence is written as an int or long array, its length is written right away,
+    /// since arrays do not store an element type.
+    fn open_list(&mut self, declared: usize) -> Result<(), NbtError> {
+        len_prefix(declared)?;
+
+        let array = self.next_array.take();
+        self.write_root_header(array.unwrap_or(FieldType::List), "")?;
+        if array.is_some() {
+            self.write_list_len(declared)?;
+        }
+
+        self.lists.push(ListLen {
+            declared,
+            written: 0,
+            prefixed: array.is_some(),
+        });
+        Ok(())
+    }
+
+    /// Writes the length prefix of a string, which is also used for compound keys and the name of
+    /// the root value.
+    fn write_str_len(&mut self, len: usize) -> Result<(), NbtError> {
+        if E::AS_ENUM.is_varint() {
+            return self.writer.write_u32_varint(len as u32);
+        }
+
+        let max = self.string_len_width.max_len();
+        if len > max {
+            return Err(NbtError::StringTooLong { len, max });
+        }
+
+        match (self.string_len_width, E::AS_ENUM) {
+            (StringLenWidth::U8, _) => self.writer.write_u8(len as u8),
+            (StringLenWidth::U16, Variant::BigEndian) => {
+                self.writer.write_u16::<BigEndian>(len as u16)
+            }
+            (StringLenWidth::U16, _) => self.writer.write_u16::<LittleEndian>(len as u16),
+            (StringLenWidth::U32, Variant::BigEndian) => {
+                self.writer.write_u32::<BigEndian>(len as u32)
+            }
+            (StringLenWidth::U32, _) => self.writer.write_u32::<LittleEndian>(len as u32),
+        }
+    }
+
+    /// Writes the length prefix of a byte array.
+    fn write_byte_array_len(&mut self, len: usize) -> Result<(), NbtError> {
+        if E::AS_ENUM.is_varint() || self.byte_array_len_width == ByteArrayLenWidth::I32 {
+            return self.write_list_len(len);
+        }
+
+        let max = ByteArrayLenWidth::I16.max_len();
+        if len > max {
+            return Err(NbtError::ByteArrayTooLong { len, max });
+        }
+
+        match E::AS_ENUM {
+            Variant::BigEndian => self.writer.write_i16::<BigEndian>(len as i16),
+            _ => self.writer.write_i16::<LittleEndian>(len as i16),
+        }
+    }
+
+    /// Writes the length of the list that is currently being serialised.
+    fn write_list_len(&mut self, len: usize) -> Result<(), NbtError> {
+        let len = len_prefix(len)?;
+        match E::AS_ENUM {
+            Variant::BigEndian => self.writer.write_i32::<BigEndian>(len),
+            Variant::LittleEndian => self.writer.write_i32::<LittleEndian>(len),
+            Variant::NetworkEndian => self.writer.write_i32_varint(len),
+        }
+    }
+
+    /// Serialises a single element of the current list.
+    ///
+    /// The element type and length of the list are written before the first element.
+    fn serialize_list_element<T>(&mut self, element: &T) -> Result<(), NbtError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let in_list = core::mem::replace(&mut self.in_list, true);
+
+        let list = self.lists.last().copied().unwrap_or_default();
+        if list.written == 0 && !list.prefixed {
+            let ty_serializer = FieldTypeSerializer::new(self);
+            if element.serialize(ty_serializer)? {
+                return Err(NbtError::CannotSkipInList);
+            }
+            self.write_list_len(list.declared)?;
+        }
+
+        element.serialize(&mut *self)?;
+        self.in_list = in_list;
+
+        if let Some(list) = self.lists.last_mut() {
+            list.written += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Finishes the current list, verifying that its declared length was correct.
+    fn close_list(&mut self) -> Result<(), NbtError> {
+        self.flush_pending()?;
+
+        let list = self.lists.pop().unwrap_or_default();
+        if list.written != list.declared {
+            return Err(NbtError::LengthMismatch {
+                declared: list.declared,
+                actual: list.written,
+            });
+        }
+
+        if list.written == 0 && !list.prefixed {
+            // Empty lists have no elements to derive the type from.
+            self.writer.write_u8(FieldType::End as u8)?;
+            self.write_list_len(0)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if a list has been started but not finished.
+    fn check_lists_closed(&self) -> Result<(), NbtError> {
+        if self.lists.is_empty() {
+            Ok(())
+        } else {
+            Err(NbtError::UnfinishedList {
+                open: self.lists.len(),
+            })
+        }
+    }
+
+    /// Writes the list elements that have been collected so far.
+    fn flush_pending(&mut self) -> Result<(), NbtError> {
+        if !self.pending.is_empty() {
+            self.writer.write_all(&self.pending)?;
+            self.pending.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Writes the tag type and name of the root value, if nothing has been written yet.
+    fn write_root_header(&mut self, ty: FieldType, name: &str) -> Result<(), NbtError> {
+        if self.is_initial {
+            self.is_initial = false;
+            self.writer.write_u8(ty as u8)?;
+
+            let root_name = self.root_name.take();
+            ser::Serializer::serialize_str(&mut *self, root_name.as_deref().unwrap_or(name))?;
+            self.root_name = root_name;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the buffered compound entries in lexicographic key order.
+    fn write_sorted_entries(&mut self) -> Result<(), NbtError> {
+        let mut entries = core::mem::take(&mut self.entries);
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (_, entry) in entries {
+            self.writer.write_all(&entry)?;
+        }
+
+        Ok(())
     }
 }
 
 impl<W, E> ser::Serializer for &mut Serializer<W, E>
 where
     E: EndiannessImpl,
-    W: WriteBytesExt,
+    W: Write,
 {
     type Ok = ();
     type Error = NbtError;
@@ -334,108 +1317,173 @@ where
 
     #[inline]
     fn serialize_bool(self, v: bool) -> Result<(), NbtError> {
-        self.writer.write_u8(v as u8)?;
+        self.write_root_header(self.bool_tag.field_type(), "")?;
+        if self.bool_tag == BoolTag::Short {
+            write_scalar!(self, |w| match E::AS_ENUM {
+                Variant::BigEndian => w.write_i16::<BigEndian>(v as i16),
+                Variant::LittleEndian | Variant::NetworkEndian => {
+                    w.write_i16::<LittleEndian>(v as i16)
+                }
+            });
+        } else {
+            write_scalar!(self, |w| w.write_u8(v as u8));
+        }
         Ok(())
     }
 
     #[inline]
     fn serialize_i8(self, v: i8) -> Result<(), NbtError> {
-        self.writer.write_i8(v)?;
+        self.write_root_header(FieldType::Byte, "")?;
+        write_scalar!(self, |w| w.write_i8(v));
         Ok(())
     }
 
     #[inline]
     fn serialize_i16(self, v: i16) -> Result<(), NbtError> {
-        match E::AS_ENUM {
-            Variant::BigEndian => self.writer.write_i16::<BigEndian>(v)?,
-            Variant::LittleEndian | Variant::NetworkEndian => {
-                self.writer.write_i16::<LittleEndian>(v)?
-            }
-        };
+        let ty = self.narrowed_tag(v as i64).unwrap_or(FieldType::Short);
+        self.write_root_header(ty, "")?;
+        if self.write_narrowed(v as i64)? {
+            return Ok(());
+        }
+
+        write_scalar!(self, |w| match E::AS_ENUM {
+            Variant::BigEndian => w.write_i16::<BigEndian>(v),
+            Variant::LittleEndian | Variant::NetworkEndian => w.write_i16::<LittleEndian>(v),
+        });
 
         Ok(())
     }
 
     #[inline]
     fn serialize_i32(self, v: i32) -> Result<(), NbtError> {
-        match E::AS_ENUM {
-            Variant::BigEndian => self.writer.write_i32::<BigEndian>(v)?,
-            Variant::LittleEndian => self.writer.write_i32::<LittleEndian>(v)?,
-            Variant::NetworkEndian => self.writer.write_i32_varint(v)?,
-        };
+        let ty = self.narrowed_tag(v as i64).unwrap_or(FieldType::Int);
+        self.write_root_header(ty, "")?;
+        if self.write_narrowed(v as i64)? {
+            return Ok(());
+        }
+
+        write_scalar!(self, |w| match E::AS_ENUM {
+            Variant::BigEndian => w.write_i32::<BigEndian>(v),
+            Variant::LittleEndian => w.write_i32::<LittleEndian>(v),
+            Variant::NetworkEndian => w.write_i32_varint(v),
+        });
 
         Ok(())
     }
 
     #[inline]
     fn serialize_i64(self, v: i64) -> Result<(), NbtError> {
-        match E::AS_ENUM {
-            Variant::BigEndian => self.writer.write_i64::<BigEndian>(v)?,
-            Variant::LittleEndian => self.writer.write_i64::<LittleEndian>(v)?,
-            Variant::NetworkEndian => self.writer.write_i64_varint(v)?,
-        };
+        let ty = self.narrowed_tag(v).unwrap_or(FieldType::Long);
+        self.write_root_header(ty, "")?;
+        if self.write_narrowed(v)? {
+            return Ok(());
+        }
+
+        write_scalar!(self, |w| match E::AS_ENUM {
+            Variant::BigEndian => w.write_i64::<BigEndian>(v),
+            Variant::LittleEndian => w.write_i64::<LittleEndian>(v),
+            Variant::NetworkEndian => w.write_i64_varint(v),
+        });
 
         Ok(())
     }
 
     #[inline]
     fn serialize_f32(self, v: f32) -> Result<(), NbtError> {
-        match E::AS_ENUM {
-            Variant::BigEndian => self.writer.write_f32::<BigEndian>(v)?,
-            Variant::LittleEndian | Variant::NetworkEndian => {
-                self.writer.write_f32::<LittleEndian>(v)?
+        self.write_root_header(FieldType::Float, "")?;
+        let v = match self.float_policy {
+            FloatPolicy::Reject if !v.is_finite() => {
+                return Err(NbtError::NonFiniteFloat { value: v as f64 })
             }
+            FloatPolicy::Canonicalize if v.is_nan() => f32::NAN,
+            _ => v,
         };
 
+        write_scalar!(self, |w| match E::AS_ENUM {
+            Variant::BigEndian => w.write_f32::<BigEndian>(v),
+            Variant::LittleEndian | Variant::NetworkEndian => w.write_f32::<LittleEndian>(v),
+        });
+
         Ok(())
     }
 
     #[inline]
     fn serialize_f64(self, v: f64) -> Result<(), NbtError> {
-        match E::AS_ENUM {
-            Variant::BigEndian => self.writer.write_f64::<BigEndian>(v)?,
-            Variant::LittleEndian | Variant::NetworkEndian => {
-                self.writer.write_f64::<LittleEndian>(v)?
+        self.write_root_header(FieldType::Double, "")?;
+        let v = match self.float_policy {
+            FloatPolicy::Reject if !v.is_finite() => {
+                return Err(NbtError::NonFiniteFloat { value: v })
             }
+            FloatPolicy::Canonicalize if v.is_nan() => f64::NAN,
+            _ => v,
         };
 
+        write_scalar!(self, |w| match E::AS_ENUM {
+            Variant::BigEndian => w.write_f64::<BigEndian>(v),
+            Variant::LittleEndian | Variant::NetworkEndian => w.write_f64::<LittleEndian>(v),
+        });
+
         Ok(())
     }
 
     #[inline]
     fn serialize_str(self, v: &str) -> Result<(), NbtError> {
-        match E::AS_ENUM {
-            Variant::BigEndian => self.writer.write_u16::<BigEndian>(v.len() as u16),
-            Variant::LittleEndian => self.writer.write_u16::<LittleEndian>(v.len() as u16),
-            Variant::NetworkEndian => self.writer.write_u32_varint(v.len() as u32),
-        }?;
+        self.flush_pending()?;
+        self.write_root_header(FieldType::String, "")?;
 
+        self.write_str_len(v.len())?;
         self.writer.write_all(v.as_bytes())?;
         Ok(())
     }
 
+    /// Writes the value as a string.
+    ///
+    /// The length has to be written before the string, so the value is first formatted into a
+    /// buffer that is kept by the serializer. Unlike the default implementation, this does not
+    /// allocate a new string for every value.
+    fn collect_str<T>(self, value: &T) -> Result<(), NbtError>
+    where
+        T: ?Sized + core::fmt::Display,
+    {
+        use core::fmt::Write as _;
+
+        let mut scratch = core::mem::take(&mut self.scratch);
+        scratch.clear();
+        if write!(scratch, "{value}").is_err() {
+            return Err(NbtError::Other(
+                "Display implementation returned an error".into(),
+            ));
+        }
+
+        let result = ser::Serializer::serialize_str(&mut *self, &scratch);
+        self.scratch = scratch;
+        result
+    }
+
     #[inline]
     fn serialize_bytes(self, v: &[u8]) -> Result<(), NbtError> {
-        match E::AS_ENUM {
-            Variant::BigEndian => self.writer.write_i32::<BigEndian>(v.len() as i32),
-            Variant::LittleEndian => self.writer.write_i32::<LittleEndian>(v.len() as i32),
-            Variant::NetworkEndian => self.writer.write_i32_varint(v.len() as i32),
-        }?;
+        self.flush_pending()?;
+        len_prefix(v.len())?;
+        self.write_root_header(FieldType::ByteArray, "")?;
 
+        self.write_byte_array_len(v.len())?;
         self.writer.write_all(v)?;
         Ok(())
     }
 
     fn serialize_none(self) -> Result<(), NbtError> {
+        if self.in_list {
+            return Err(NbtError::CannotSkipInList);
+        }
+
         Err(NbtError::Unsupported(
-            "Serializing Options is not supported",
+            "`None` can only be serialized as a compound entry",
         ))
     }
 
-    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<(), NbtError> {
-        Err(NbtError::Unsupported(
-            "Serializing Options is not supported",
-        ))
+    #[inline]
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), NbtError> {
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<(), NbtError> {
@@ -459,14 +1507,22 @@ where
         ))
     }
 
+    /// Newtype structs are serialized as their inner value, apart from the wrappers that produce
+    /// int and long arrays.
     fn serialize_newtype_struct<T: Serialize + ?Sized>(
         self,
-        _name: &'static str,
-        _value: &T,
+        name: &'static str,
+        value: &T,
     ) -> Result<(), NbtError> {
-        Err(NbtError::Unsupported(
-            "Serializing newtype structs is not supported",
-        ))
+        self.next_array = Some(match name {
+            INT_ARRAY_TOKEN => FieldType::IntArray,
+            LONG_ARRAY_TOKEN => FieldType::LongArray,
+            _ => return value.serialize(self),
+        });
+
+        let result = value.serialize(&mut *self);
+        self.next_array = None;
+        result
     }
 
     fn serialize_newtype_variant<T: Serialize + ?Sized>(
@@ -483,8 +1539,10 @@ where
 
     #[inline]
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.flush_pending()?;
+
         if let Some(len) = len {
-            self.len = len;
+            self.open_list(len)?;
             Ok(self)
         } else {
             Err(NbtError::Unsupported("Dynamically sized sequences is not supported. If you are trying to serialize an iterator, call `Iterator::collect` to create a sequence with known size."))
@@ -493,7 +1551,9 @@ where
 
     #[inline]
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        self.len = len;
+        self.flush_pending()?;
+
+        self.open_list(len)?;
         Ok(self)
     }
 
@@ -522,12 +1582,10 @@ where
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
         // nbt::Value does not distinguish between maps and structs.
         // Therefore, this is also necessary here
-        if self.is_initial {
-            self.writer.write_u8(FieldType::Compound as u8)?;
-            self.serialize_str("")?;
-            self.is_initial = false;
-        }
+        self.flush_pending()?;
+        self.write_root_header(FieldType::Compound, "")?;
 
+        self.begin_compound();
         Ok(self)
     }
 
@@ -536,12 +1594,10 @@ where
         name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        if self.is_initial {
-            self.writer.write_u8(FieldType::Compound as u8)?;
-            self.serialize_str(name)?;
-            self.is_initial = false;
-        }
+        self.flush_pending()?;
+        self.write_root_header(FieldType::Compound, name)?;
 
+        self.begin_compound();
         Ok(self)
     }
 
@@ -560,7 +1616,7 @@ where
 
 impl<W, F> SerializeSeq for &mut Serializer<W, F>
 where
-    W: WriteBytesExt,
+    W: Write,
     F: EndiannessImpl,
 {
     type Ok = ();
@@ -571,30 +1627,18 @@ where
     where
         T: ?Sized + Serialize,
     {
-        if self.len != 0 {
-            let ty_serializer = FieldTypeSerializer::new(self);
-            element.serialize(ty_serializer)?;
-
-            match F::AS_ENUM {
-                Variant::BigEndian => self.writer.write_i32::<BigEndian>(self.len as i32),
-                Variant::LittleEndian => self.writer.write_i32::<LittleEndian>(self.len as i32),
-                Variant::NetworkEndian => self.writer.write_i32_varint(self.len as i32),
-            }?;
-            self.len = 0;
-        }
-
-        element.serialize(&mut **self)
+        self.serialize_list_element(element)
     }
 
     #[inline]
     fn end(self) -> Result<(), NbtError> {
-        Ok(())
+        self.close_list()
     }
 }
 
 impl<W, M> SerializeTuple for &mut Serializer<W, M>
 where
-    W: WriteBytesExt,
+    W: Write,
     M: EndiannessImpl,
 {
     type Ok = ();
@@ -605,30 +1649,18 @@ where
     where
         T: ?Sized + Serialize,
     {
-        if self.len != 0 {
-            let ty_serializer = FieldTypeSerializer::new(self);
-            element.serialize(ty_serializer)?;
-
-            match M::AS_ENUM {
-                Variant::BigEndian => self.writer.write_i32::<BigEndian>(self.len as i32),
-                Variant::LittleEndian => self.writer.write_i32::<LittleEndian>(self.len as i32),
-                Variant::NetworkEndian => self.writer.write_i32_varint(self.len as i32),
-            }?;
-            self.len = 0;
-        }
-
-        element.serialize(&mut **self)
+        self.serialize_list_element(element)
     }
 
     #[inline]
     fn end(self) -> Result<(), NbtError> {
-        Ok(())
+        self.close_list()
     }
 }
 
 impl<W, M> SerializeMap for &mut Serializer<W, M>
 where
-    W: WriteBytesExt,
+    W: Write,
     M: EndiannessImpl,
 {
     type Ok = ();
@@ -659,23 +1691,53 @@ where
         K: ?Sized + Serialize,
         V: ?Sized + Serialize,
     {
+        if self.sorted_keys {
+            let mut name = String::new();
+            key.serialize(KeySerializer(&mut name))?;
+
+            let mut entry = self.entry_serializer();
+            if !value.serialize(FieldTypeSerializer::new(&mut entry))? {
+                ser::Serializer::serialize_str(&mut entry, &name)?;
+                value.serialize(&mut entry)?;
+
+                self.entries.push((name, entry.into_inner()?));
+            }
+
+            return Ok(());
+        }
+
+        let in_list = core::mem::replace(&mut self.in_list, false);
+
         let ty_serializer = FieldTypeSerializer::new(self);
-        value.serialize(ty_serializer)?;
+        let should_skip = value.serialize(ty_serializer)?;
+
+        if !should_skip {
+            // The key is collected first, since it may not be a string, reusing the buffer of
+            // `collect_str`.
+            let mut name = core::mem::take(&mut self.scratch);
+            name.clear();
+            let result = key
+                .serialize(KeySerializer(&mut name))
+                .and_then(|()| ser::Serializer::serialize_str(&mut **self, &name));
+            self.scratch = name;
+            result?;
+
+            value.serialize(&mut **self)?;
+        }
+        self.in_list = in_list;
 
-        key.serialize(&mut **self)?;
-        value.serialize(&mut **self)
+        Ok(())
     }
 
     #[inline]
     fn end(self) -> Result<(), NbtError> {
-        self.writer.write_u8(FieldType::End as u8)?;
-        Ok(())
+        self.end_compound()
     }
 }
 
 impl<W, M> SerializeStruct for &mut Serializer<W, M>
 where
-    W: WriteBytesExt,
+    W: Write,
     M: EndiannessImpl,
 {
     type Ok = ();
@@ -685,28 +1747,203 @@ where
     where
         V: ?Sized + Serialize,
     {
+        if self.sorted_keys {
+            let mut entry = self.entry_serializer();
+            if !value.serialize(FieldTypeSerializer::new(&mut entry))? {
+                ser::Serializer::serialize_str(&mut entry, key)?;
+                value.serialize(&mut entry)?;
+
+                self.entries.push((key.to_owned(), entry.into_inner()?));
+            }
+
+            return Ok(());
+        }
+
+        let in_list = core::mem::replace(&mut self.in_list, false);
+
         let ty_serializer = FieldTypeSerializer::new(self);
         let should_skip = value.serialize(ty_serializer)?;
 
         if !should_skip {
-            match M::AS_ENUM {
-                Variant::LittleEndian => self.writer.write_u16::<LittleEndian>(key.len() as u16),
-                Variant::BigEndian => self.writer.write_u16::<BigEndian>(key.len() as u16),
-                Variant::NetworkEndian => self.writer.write_u32_varint(key.len() as u32),
-            }?;
-
+            self.write_str_len(key.len())?;
             self.writer.write_all(key.as_bytes())?;
-            value.serialize(&mut **self)
-        } else {
-            Ok(())
+            value.serialize(&mut **self)?;
         }
+        self.in_list = in_list;
+
+        Ok(())
     }
 
     #[inline]
     fn end(self) -> Result<(), NbtError> {
-        self.writer.write_u8(FieldType::End as u8)?;
+        self.end_compound()
+    }
+}
+
+/// Returns an error for map keys that are not strings.
+macro_rules! forward_unsupported_key {
+    ($($ty: ident),+) => {
+        paste! {$(
+            #[inline]
+            fn [<serialize_ $ty>](self, _v: $ty) -> Result<(), NbtError> {
+                Err(NbtError::NonStringKey(stringify!($ty)))
+            }
+        )+}
+    }
+}
+
+/// Converts map keys to their string representation.
+macro_rules! stringify_key {
+    ($($ty: ident),+) => {
+        paste! {$(
+            #[inline]
+            fn [<serialize_ $ty>](self, v: $ty) -> Result<(), NbtError> {
+                self.collect_str(&v)
+            }
+        )+}
+    }
+}
+
+/// Serializer that appends the string of a compound key to the given buffer.
+///
+/// Compound keys are always strings. Integers, chars and other values that are serialized
+/// through [`Display`](core::fmt::Display) are converted to strings and unit variants are
+/// written as their name, so that maps such as `HashMap<i32, T>` can be serialized. Any other
+/// key results in [`NbtError::NonStringKey`].
+pub(crate) struct KeySerializer<'a>(pub(crate) &'a mut String);
+
+impl ser::Serializer for KeySerializer<'_> {
+    type Ok = ();
+    type Error = NbtError;
+    type SerializeSeq = Impossible<(), NbtError>;
+    type SerializeTuple = Impossible<(), NbtError>;
+    type SerializeTupleStruct = Impossible<(), NbtError>;
+    type SerializeTupleVariant = Impossible<(), NbtError>;
+    type SerializeMap = Impossible<(), NbtError>;
+    type SerializeStruct = Impossible<(), NbtError>;
+    type SerializeStructVariant = Impossible<(), NbtError>;
+
+    forward_unsupported_key!(bool, f32, f64);
+    stringify_key!(char, i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+
+    #[inline]
+    fn serialize_str(self, v: &str) -> Result<(), NbtError> {
+        self.0.push_str(v);
         Ok(())
     }
+
+    fn collect_str<T>(self, value: &T) -> Result<(), NbtError>
+    where
+        T: ?Sized + core::fmt::Display,
+    {
+        use core::fmt::Write as _;
+
+        if write!(self.0, "{value}").is_err() {
+            return Err(NbtError::Other(
+                "Display implementation returned an error".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), NbtError> {
+        Err(NbtError::NonStringKey("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<(), NbtError> {
+        Err(NbtError::NonStringKey("an option"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<(), NbtError> {
+        Err(NbtError::NonStringKey("an option"))
+    }
+
+    fn serialize_unit(self) -> Result<(), NbtError> {
+        Err(NbtError::NonStringKey("a unit"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), NbtError> {
+        Err(NbtError::NonStringKey("a unit struct"))
+    }
+
+    #[inline]
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), NbtError> {
+        self.0.push_str(variant);
+        Ok(())
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), NbtError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), NbtError> {
+        Err(NbtError::NonStringKey("a newtype variant"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, NbtError> {
+        Err(NbtError::NonStringKey("a sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, NbtError> {
+        Err(NbtError::NonStringKey("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, NbtError> {
+        Err(NbtError::NonStringKey("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, NbtError> {
+        Err(NbtError::NonStringKey("a tuple variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, NbtError> {
+        Err(NbtError::NonStringKey("a map"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, NbtError> {
+        Err(NbtError::NonStringKey("a struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, NbtError> {
+        Err(NbtError::NonStringKey("a struct variant"))
+    }
 }
 
 /// Separate serialiser that writes data types to the writer.
@@ -716,7 +1953,7 @@ where
 /// This serialiser writes the data type of the given value and does not consume it.
 struct FieldTypeSerializer<'a, W, F>
 where
-    W: WriteBytesExt,
+    W: Write,
     F: EndiannessImpl,
 {
     ser: &'a mut Serializer<W, F>,
@@ -724,7 +1961,7 @@ where
 
 impl<'a, W, F> FieldTypeSerializer<'a, W, F>
 where
-    W: WriteBytesExt,
+    W: Write,
     F: EndiannessImpl,
 {
     pub fn new(ser: &'a mut Serializer<W, F>) -> Self {
@@ -734,7 +1971,7 @@ where
 
 impl<W, F> ser::Serializer for FieldTypeSerializer<'_, W, F>
 where
-    W: WriteBytesExt,
+    W: Write,
     F: EndiannessImpl,
 {
     type Ok = bool; // Whether the field should be skipped
@@ -751,7 +1988,9 @@ where
 
     #[inline]
     fn serialize_bool(self, _v: bool) -> Result<bool, Self::Error> {
-        self.ser.writer.write_u8(FieldType::Byte as u8)?;
+        self.ser
+            .writer
+            .write_u8(self.ser.bool_tag.field_type() as u8)?;
         Ok(false)
     }
 
@@ -762,18 +2001,21 @@ where
     }
 
     #[inline]
-    fn serialize_i16(self, _v: i16) -> Result<Self::Ok, Self::Error> {
-        self.ser.writer.write_u8(FieldType::Short as u8)?;
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        let ty = self.ser.narrowed_tag(v as i64).unwrap_or(FieldType::Short);
+        self.ser.writer.write_u8(ty as u8)?;
         Ok(false)
     }
 
-    fn serialize_i32(self, _v: i32) -> Result<Self::Ok, Self::Error> {
-        self.ser.writer.write_u8(FieldType::Int as u8)?;
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        let ty = self.ser.narrowed_tag(v as i64).unwrap_or(FieldType::Int);
+        self.ser.writer.write_u8(ty as u8)?;
         Ok(false)
     }
 
-    fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Self::Error> {
-        self.ser.writer.write_u8(FieldType::Long as u8)?;
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        let ty = self.ser.narrowed_tag(v).unwrap_or(FieldType::Long);
+        self.ser.writer.write_u8(ty as u8)?;
         Ok(false)
     }
 
@@ -792,6 +2034,15 @@ where
         Ok(false)
     }
 
+    fn collect_str<T>(self, _value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + core::fmt::Display,
+    {
+        // Only the type is written here, so the value does not have to be formatted.
+        self.ser.writer.write_u8(FieldType::String as u8)?;
+        Ok(false)
+    }
+
     fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
         self.ser.writer.write_u8(FieldType::ByteArray as u8)?;
         Ok(false)
@@ -829,12 +2080,17 @@ where
 
     fn serialize_newtype_struct<T: Serialize + ?Sized>(
         self,
-        _name: &'static str,
-        _value: &T,
+        name: &'static str,
+        value: &T,
     ) -> Result<Self::Ok, Self::Error> {
-        Err(NbtError::Unsupported(
-            "Serializing newtype structs is not supported",
-        ))
+        let ty = match name {
+            INT_ARRAY_TOKEN => FieldType::IntArray,
+            LONG_ARRAY_TOKEN => FieldType::LongArray,
+            _ => return value.serialize(self),
+        };
+
+        self.ser.writer.write_u8(ty as u8)?;
+        Ok(false)
     }
 
     fn serialize_newtype_variant<T: Serialize + ?Sized>(
@@ -912,7 +2168,7 @@ where
 
 impl<W, F> SerializeSeq for FieldTypeSerializer<'_, W, F>
 where
-    W: WriteBytesExt,
+    W: Write,
     F: EndiannessImpl,
 {
     type Ok = bool;
@@ -934,7 +2190,7 @@ where
 
 impl<W, F> SerializeTuple for FieldTypeSerializer<'_, W, F>
 where
-    W: WriteBytesExt,
+    W: Write,
     F: EndiannessImpl,
 {
     type Ok = bool;
@@ -956,7 +2212,7 @@ where
 
 impl<W, F> SerializeMap for FieldTypeSerializer<'_, W, F>
 where
-    W: WriteBytesExt,
+    W: Write,
     F: EndiannessImpl,
 {
     type Ok = bool;
@@ -986,7 +2242,7 @@ where
 
 impl<W, F> SerializeStruct for FieldTypeSerializer<'_, W, F>
 where
-    W: WriteBytesExt,
+    W: Write,
     F: EndiannessImpl,
 {
     type Ok = bool;
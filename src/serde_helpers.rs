@@ -0,0 +1,171 @@
+//! Modules for use with `#[serde(with = "...")]` that change how a field is stored.
+
+/// Stores a [`SystemTime`](std::time::SystemTime) or [`Duration`](std::time::Duration) as a
+/// long holding a number of milliseconds.
+///
+/// Times are stored relative to the Unix epoch and may be negative. Any precision below a
+/// millisecond is truncated when serializing. Deserializing fails if the long does not fit in
+/// the field, such as a negative duration.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::{Duration, SystemTime};
+///
+/// #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+/// struct Player {
+///     #[serde(with = "nbtx::serde_helpers::as_long_millis")]
+///     last_played: SystemTime,
+///     #[serde(with = "nbtx::serde_helpers::as_long_millis")]
+///     play_time: Duration,
+/// }
+///
+/// let player = Player {
+///     last_played: SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_000),
+///     play_time: Duration::from_secs(3600),
+/// };
+/// let encoded = nbtx::to_be_bytes(&player).unwrap();
+///
+/// assert_eq!(nbtx::from_be_slice::<Player>(&encoded).unwrap(), player);
+/// ```
+#[cfg(feature = "std")]
+pub mod as_long_millis {
+    use std::time::{Duration, SystemTime};
+
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// A type that can be stored as a number of milliseconds.
+    pub trait Millis: Sized {
+        /// Returns the amount of milliseconds, or `None` if it does not fit in an `i64`.
+        fn to_millis(&self) -> Option<i64>;
+
+        /// Creates a value from an amount of milliseconds, or returns `None` if it is out of
+        /// range.
+        fn from_millis(millis: i64) -> Option<Self>;
+    }
+
+    impl Millis for Duration {
+        #[inline]
+        fn to_millis(&self) -> Option<i64> {
+            i64::try_from(self.as_millis()).ok()
+        }
+
+        #[inline]
+        fn from_millis(millis: i64) -> Option<Self> {
+            u64::try_from(millis).ok().map(Duration::from_millis)
+        }
+    }
+
+    impl Millis for SystemTime {
+        fn to_millis(&self) -> Option<i64> {
+            match self.duration_since(SystemTime::UNIX_EPOCH) {
+                Ok(after) => after.to_millis(),
+                Err(before) => before.duration().to_millis().map(|millis| -millis),
+            }
+        }
+
+        fn from_millis(millis: i64) -> Option<Self> {
+            let offset = Duration::from_millis(millis.unsigned_abs());
+            if millis < 0 {
+                SystemTime::UNIX_EPOCH.checked_sub(offset)
+            } else {
+                SystemTime::UNIX_EPOCH.checked_add(offset)
+            }
+        }
+    }
+
+    /// Serializes the value as a long.
+    pub fn serialize<T, S>(value: &T, ser: S) -> Result<S::Ok, S::Error>
+    where
+        T: Millis,
+        S: Serializer,
+    {
+        let millis = value
+            .to_millis()
+            .ok_or_else(|| S::Error::custom("milliseconds do not fit in a long"))?;
+        ser.serialize_i64(millis)
+    }
+
+    /// Deserializes the value from a long.
+    pub fn deserialize<'de, T, D>(de: D) -> Result<T, D::Error>
+    where
+        T: Millis,
+        D: Deserializer<'de>,
+    {
+        let millis = i64::deserialize(de)?;
+        T::from_millis(millis)
+            .ok_or_else(|| D::Error::custom(format!("{millis} milliseconds are out of range")))
+    }
+}
+
+/// Reads and writes a [`heapless::String`], which has a fixed capacity of `N` bytes.
+///
+/// Strings are read without allocating. Strings that are longer than the capacity fail with
+/// [`NbtError::CapacityExceeded`](crate::NbtError::CapacityExceeded) when using the deserializer
+/// of this crate, or with an invalid length error when using any other deserializer.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::NbtError;
+/// #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+/// struct Sign {
+///     #[serde(with = "nbtx::serde_helpers::heapless_string")]
+///     text: heapless::String<8>,
+/// }
+///
+/// let sign = Sign { text: "Hello".try_into().unwrap() };
+/// let encoded = nbtx::to_be_bytes(&sign).unwrap();
+///
+/// assert_eq!(nbtx::from_be_slice::<Sign>(&encoded).unwrap(), sign);
+/// ```
+#[cfg(feature = "heapless")]
+pub mod heapless_string {
+    use core::fmt;
+
+    use serde::de::{Error, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    /// Tuple struct name that makes the deserializer read a string of at most the given length.
+    pub(crate) const HEAPLESS_STRING_TOKEN: &str = "__nbtx_heapless_string";
+
+    /// Serializes the string as a string tag.
+    #[inline]
+    pub fn serialize<S, const N: usize>(
+        value: &heapless::String<N>,
+        ser: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        ser.serialize_str(value)
+    }
+
+    /// Deserializes the string from a string tag.
+    #[inline]
+    pub fn deserialize<'de, D, const N: usize>(de: D) -> Result<heapless::String<N>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de.deserialize_tuple_struct(HEAPLESS_STRING_TOKEN, N, StringVisitor)
+    }
+
+    struct StringVisitor<const N: usize>;
+
+    impl<const N: usize> Visitor<'_> for StringVisitor<N> {
+        type Value = heapless::String<N>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a string of at most {N} bytes")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: Error,
+        {
+            heapless::String::try_from(v).map_err(|_| E::invalid_length(v.len(), &self))
+        }
+    }
+}
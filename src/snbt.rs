@@ -0,0 +1,106 @@
+//! Writing [`Value`]s as stringified NBT (SNBT), the text format used by Minecraft commands.
+//!
+//! Compound keys are written without quotes if they only consist of the characters
+//! `0-9`, `a-z`, `A-Z`, `_`, `-`, `.` and `+`, matching the vanilla writer. Any other key,
+//! including the empty key, is wrapped in double quotes, with embedded `"` and `\` escaped.
+//! String values are always quoted.
+
+use std::fmt::{self, Write};
+
+use crate::Value;
+
+/// Returns whether the character can appear in an unquoted SNBT string.
+#[inline]
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+/// Returns whether the key has to be quoted when written as SNBT.
+pub fn needs_quotes(key: &str) -> bool {
+    key.is_empty() || !key.chars().all(is_unquoted_char)
+}
+
+/// Writes the string wrapped in double quotes, escaping any embedded quotes and backslashes.
+pub fn write_quoted<W: Write>(out: &mut W, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        if matches!(c, '"' | '\\') {
+            out.write_char('\\')?;
+        }
+        out.write_char(c)?;
+    }
+    out.write_char('"')
+}
+
+/// Writes a compound key, only quoting it if required.
+pub fn write_key<W: Write>(out: &mut W, key: &str) -> fmt::Result {
+    if needs_quotes(key) {
+        write_quoted(out, key)
+    } else {
+        out.write_str(key)
+    }
+}
+
+/// Writes the elements of a list or array, separated by commas.
+fn write_elements<W, T, I>(out: &mut W, prefix: &str, items: I, mut write: T) -> fmt::Result
+where
+    W: Write,
+    I: IntoIterator,
+    T: FnMut(&mut W, I::Item) -> fmt::Result,
+{
+    out.write_char('[')?;
+    out.write_str(prefix)?;
+    for (i, item) in items.into_iter().enumerate() {
+        if i != 0 {
+            out.write_char(',')?;
+        }
+        write(out, item)?;
+    }
+    out.write_char(']')
+}
+
+/// Writes the value as SNBT.
+///
+/// Compound keys are written in sorted order so the output is deterministic.
+pub fn write_value<W: Write>(out: &mut W, value: &Value) -> fmt::Result {
+    match value {
+        Value::Byte(v) => write!(out, "{v}b"),
+        Value::Short(v) => write!(out, "{v}s"),
+        Value::Int(v) => write!(out, "{v}"),
+        Value::Long(v) => write!(out, "{v}L"),
+        Value::Float(v) => write!(out, "{v:?}f"),
+        Value::Double(v) => write!(out, "{v:?}d"),
+        Value::String(s) => write_quoted(out, s),
+        Value::ByteArray(v) => write_elements(out, "B;", v, |out, v| write!(out, "{}b", *v as i8)),
+        Value::IntArray(v) => write_elements(out, "I;", v, |out, v| write!(out, "{v}")),
+        Value::LongArray(v) => write_elements(out, "L;", v, |out, v| write!(out, "{v}L")),
+        Value::List(v) => write_elements(out, "", v, write_value),
+        Value::Compound(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_unstable_by_key(|(k, _)| *k);
+
+            out.write_char('{')?;
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i != 0 {
+                    out.write_char(',')?;
+                }
+                write_key(out, key)?;
+                out.write_char(':')?;
+                write_value(out, value)?;
+            }
+            out.write_char('}')
+        }
+    }
+}
+
+impl Value {
+    /// Converts the value to stringified NBT.
+    ///
+    /// See the [`snbt`](crate::snbt) module for how keys are quoted.
+    pub fn to_snbt(&self) -> String {
+        let mut out = String::new();
+        // Writing to a `String` cannot fail.
+        let _ = write_value(&mut out, self);
+        out
+    }
+}
@@ -0,0 +1,44 @@
+//! The tag type bytes that precede each value in encoded NBT.
+//!
+//! These are the same as the discriminants of [`FieldType`], for code that works with the raw
+//! bytes instead.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nbtx::tag;
+//!
+//! # use std::collections::BTreeMap;
+//! let encoded = nbtx::to_be_bytes(&BTreeMap::<String, i32>::new()).unwrap();
+//! assert_eq!(encoded[0], tag::COMPOUND);
+//! ```
+
+#[cfg(doc)]
+use crate::FieldType;
+
+/// Type byte of [`FieldType::End`], which marks the end of a compound.
+pub const END: u8 = 0;
+/// Type byte of [`FieldType::Byte`].
+pub const BYTE: u8 = 1;
+/// Type byte of [`FieldType::Short`].
+pub const SHORT: u8 = 2;
+/// Type byte of [`FieldType::Int`].
+pub const INT: u8 = 3;
+/// Type byte of [`FieldType::Long`].
+pub const LONG: u8 = 4;
+/// Type byte of [`FieldType::Float`].
+pub const FLOAT: u8 = 5;
+/// Type byte of [`FieldType::Double`].
+pub const DOUBLE: u8 = 6;
+/// Type byte of [`FieldType::ByteArray`].
+pub const BYTE_ARRAY: u8 = 7;
+/// Type byte of [`FieldType::String`].
+pub const STRING: u8 = 8;
+/// Type byte of [`FieldType::List`].
+pub const LIST: u8 = 9;
+/// Type byte of [`FieldType::Compound`].
+pub const COMPOUND: u8 = 10;
+/// Type byte of [`FieldType::IntArray`].
+pub const INT_ARRAY: u8 = 11;
+/// Type byte of [`FieldType::LongArray`].
+pub const LONG_ARRAY: u8 = 12;
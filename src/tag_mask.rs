@@ -0,0 +1,131 @@
+use core::ops::{BitOr, BitOrAssign};
+
+use crate::FieldType;
+
+/// Set of [`FieldType`]s, stored as a bitset with one bit per tag type.
+///
+/// # Example
+///
+/// ```rust
+/// use nbtx::{FieldType, TagMask};
+///
+/// let mask = TagMask::ALL.without(FieldType::LongArray);
+///
+/// assert!(mask.contains(FieldType::Compound));
+/// assert!(!mask.contains(FieldType::LongArray));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct TagMask(u16);
+
+impl TagMask {
+    /// Mask that does not contain any tag type.
+    pub const NONE: TagMask = TagMask(0);
+    /// Mask that contains all 13 tag types.
+    pub const ALL: TagMask = TagMask((1 << 13) - 1);
+
+    /// Returns the mask as a bitset, where bit `n` is set if the tag with type byte `n` is
+    /// contained.
+    #[inline]
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    /// Returns whether the given tag type is contained in this mask.
+    #[inline]
+    pub const fn contains(self, ty: FieldType) -> bool {
+        self.0 & (1 << ty as u8) != 0
+    }
+
+    /// Returns this mask with the given tag type added.
+    #[inline]
+    pub const fn with(self, ty: FieldType) -> TagMask {
+        TagMask(self.0 | 1 << ty as u8)
+    }
+
+    /// Returns this mask with the given tag type removed.
+    #[inline]
+    pub const fn without(self, ty: FieldType) -> TagMask {
+        TagMask(self.0 & !(1 << ty as u8))
+    }
+
+    /// Adds the given tag type to this mask.
+    #[inline]
+    pub fn insert(&mut self, ty: FieldType) {
+        *self = self.with(ty);
+    }
+
+    /// Returns the mask containing the tag types of both masks.
+    #[inline]
+    pub const fn union(self, other: TagMask) -> TagMask {
+        TagMask(self.0 | other.0)
+    }
+
+    /// Returns whether this mask does not contain any tag type.
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns an iterator over the tag types in this mask, ordered by their type byte.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nbtx::{FieldType, TagMask};
+    ///
+    /// let mask = TagMask::from([FieldType::String, FieldType::Byte]);
+    ///
+    /// assert_eq!(
+    ///     mask.iter().collect::<Vec<_>>(),
+    ///     [FieldType::Byte, FieldType::String]
+    /// );
+    /// ```
+    pub fn iter(self) -> impl Iterator<Item = FieldType> {
+        (0..13)
+            .filter(move |i| self.0 & (1 << i) != 0)
+            .filter_map(FieldType::from_u8)
+    }
+}
+
+impl BitOr for TagMask {
+    type Output = TagMask;
+
+    #[inline]
+    fn bitor(self, rhs: TagMask) -> TagMask {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for TagMask {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: TagMask) {
+        *self = self.union(rhs);
+    }
+}
+
+impl From<FieldType> for TagMask {
+    #[inline]
+    fn from(ty: FieldType) -> Self {
+        TagMask::NONE.with(ty)
+    }
+}
+
+impl<const N: usize> From<[FieldType; N]> for TagMask {
+    #[inline]
+    fn from(tys: [FieldType; N]) -> Self {
+        tys.into_iter().collect()
+    }
+}
+
+impl From<&[FieldType]> for TagMask {
+    #[inline]
+    fn from(tys: &[FieldType]) -> Self {
+        tys.iter().copied().collect()
+    }
+}
+
+impl FromIterator<FieldType> for TagMask {
+    fn from_iter<I: IntoIterator<Item = FieldType>>(iter: I) -> Self {
+        iter.into_iter().fold(TagMask::NONE, TagMask::with)
+    }
+}
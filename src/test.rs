@@ -3,13 +3,18 @@
 use std::collections::HashMap;
 use std::io::Cursor;
 
-use byteorder::BigEndian;
+use byteorder::{BigEndian, LittleEndian};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    from_be_bytes, from_le_bytes, from_net_bytes,
-    ser::{to_be_bytes, to_bytes, to_le_bytes, to_net_bytes},
-    NbtError, NetworkLittleEndian, Value,
+    content_hash, from_be_bytes, from_be_slice, from_le_bytes, from_le_slice, from_net_bytes,
+    from_net_slice,
+    ser::{
+        to_be_bytes, to_bytes, to_bytes_in, to_bytes_with_capacity, to_le_bytes, to_net_bytes,
+        Serializer,
+    },
+    validate, AsFloat, ByteArrayList, ByteList, Compound, FieldType, FloatPolicy, ListDeserializer,
+    NbtError, NetworkLittleEndian, Scalar, StreamError, Value,
 };
 
 const BIG_TEST_NBT: &[u8] = include_bytes!("../test/bigtest.nbt");
@@ -43,7 +48,7 @@ fn read_write_option() {
 
 #[test]
 fn read_write_all() {
-    let value = Value::Compound(HashMap::from([
+    let value = Value::Compound(Compound::from_iter([
         ("byte".to_owned(), Value::Byte(42)),
         ("short".to_owned(), Value::Short(42)),
         ("int".to_owned(), Value::Int(42)),
@@ -58,11 +63,11 @@ fn read_write_all() {
         (
             "list".to_owned(),
             Value::List(vec![
-                Value::Compound(HashMap::from([(
+                Value::Compound(Compound::from_iter([(
                     "name".to_owned(),
                     Value::String("Compound 1".to_owned()),
                 )])),
-                Value::Compound(HashMap::from([(
+                Value::Compound(Compound::from_iter([(
                     "name".to_owned(),
                     Value::String("Compound 2".to_owned()),
                 )])),
@@ -70,7 +75,7 @@ fn read_write_all() {
         ),
         (
             "compound".to_owned(),
-            Value::Compound(HashMap::from([(
+            Value::Compound(Compound::from_iter([(
                 "name".to_owned(),
                 Value::String("Compound 3".to_owned()),
             )])),
@@ -138,14 +143,14 @@ fn read_write_bigtest() {
         short_test: i16,
     }
 
-    let mut big_test_nbt = Cursor::new(BIG_TEST_NBT.as_ref());
+    let mut big_test_nbt = Cursor::new(BIG_TEST_NBT);
     let decoded: AllTypes = from_be_bytes(&mut big_test_nbt).unwrap();
 
     let encoded = to_bytes::<BigEndian>(&decoded).unwrap();
     let mut encoded = Cursor::new(encoded.as_slice());
     let _decoded2: AllTypes = from_be_bytes(&mut encoded).unwrap();
 
-    let mut big_test_nbt = Cursor::new(BIG_TEST_NBT.as_ref());
+    let mut big_test_nbt = Cursor::new(BIG_TEST_NBT);
     let value: Value = from_be_bytes(&mut big_test_nbt).unwrap();
 
     let value_encoded = to_bytes::<NetworkLittleEndian>(&value).unwrap();
@@ -210,3 +215,5060 @@ fn read_write_player() {
     let value_encoded = to_be_bytes(&decoded2).unwrap();
     let _value_decoded: Value = from_be_bytes(&mut value_encoded.as_slice()).unwrap();
 }
+
+#[test]
+fn read_slice() {
+    fn as_byte_array<S: serde::Serializer>(v: &[u8], ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_bytes(v)
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Borrowed<'a> {
+        name: &'a str,
+        #[serde(serialize_with = "as_byte_array")]
+        data: &'a [u8],
+        level: i32,
+    }
+
+    let value: Value = from_be_bytes(&mut BIG_TEST_NBT).unwrap();
+    assert_eq!(from_be_slice::<Value>(BIG_TEST_NBT).unwrap(), value);
+
+    let encoded = to_net_bytes(&value).unwrap();
+    assert_eq!(from_net_slice::<Value>(&encoded).unwrap(), value);
+
+    let borrowed = Borrowed {
+        name: "Steve",
+        data: &[1, 2, 3],
+        level: -30,
+    };
+    let encoded = to_be_bytes(&borrowed).unwrap();
+    assert_eq!(from_be_slice::<Borrowed>(&encoded).unwrap(), borrowed);
+    let encoded = to_le_bytes(&borrowed).unwrap();
+    assert_eq!(from_le_slice::<Borrowed>(&encoded).unwrap(), borrowed);
+    let encoded = to_net_bytes(&borrowed).unwrap();
+    assert_eq!(from_net_slice::<Borrowed>(&encoded).unwrap(), borrowed);
+
+    let encoded = to_be_bytes(&borrowed).unwrap();
+    let err = from_be_slice::<Borrowed>(&encoded[..encoded.len() - 3]).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::ByteError(StreamError::UnexpectedEof {
+            expected: 4,
+            remaining: 2
+        })
+    ));
+    assert_eq!(err.path(), Some("level"));
+}
+
+#[test]
+fn lazy_list() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Entity {
+        id: i32,
+        name: String,
+    }
+
+    #[derive(Serialize)]
+    struct Entities<'a> {
+        list: &'a [Entity],
+    }
+
+    let entities: Vec<_> = (0..1000)
+        .map(|id| Entity {
+            id,
+            name: format!("entity_{id}"),
+        })
+        .collect();
+
+    let encoded = to_net_bytes(&Entities { list: &entities }).unwrap();
+    // Skip the root header (1 + 1 + 8) and the header of the list (1 + 1 + 4).
+    let mut reader = &encoded[16..];
+    let list = ListDeserializer::<NetworkLittleEndian, _, Entity>::new(&mut reader).unwrap();
+    assert_eq!(list.element_type(), FieldType::Compound);
+    assert_eq!(list.len(), 1000);
+
+    let mut count = 0;
+    for (i, entity) in list.enumerate() {
+        assert_eq!(entity.unwrap(), entities[i]);
+        count += 1;
+    }
+    assert_eq!(count, 1000);
+    // Only the end of the root compound is left.
+    assert_eq!(reader, [FieldType::End as u8]);
+
+    // Errors are reported with the index of the element and stop the iteration.
+    let encoded = to_be_bytes(&Entities {
+        list: &entities[..3],
+    })
+    .unwrap();
+    // Skip the root header (1 + 2 + 8) and the header of the list (1 + 2 + 4),
+    // then cut the stream off in the middle of the last name and the end tags.
+    let mut reader = &encoded[18..encoded.len() - 4];
+    let mut list = ListDeserializer::<BigEndian, _, Entity>::new(&mut reader).unwrap();
+    assert!(list.next().unwrap().is_ok());
+    assert!(list.next().unwrap().is_ok());
+    assert_eq!(list.next().unwrap().unwrap_err().path(), Some("[2].name"));
+    assert!(list.next().is_none());
+}
+
+#[test]
+fn error_position() {
+    #[derive(Serialize)]
+    struct Section {
+        palette: i32,
+    }
+
+    #[derive(Serialize)]
+    struct Level {
+        sections: Vec<Section>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TypedSection {
+        palette: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TypedLevel {
+        sections: Vec<TypedSection>,
+    }
+
+    let level = Level {
+        sections: vec![Section { palette: 1 }, Section { palette: 2 }],
+    };
+    let encoded = to_be_bytes(&level).unwrap();
+
+    let err = from_be_bytes::<TypedLevel, _>(&mut encoded.as_slice()).unwrap_err();
+    assert_eq!(err.path(), Some("sections[0].palette"));
+    // Root header (1 + 2 + 5), list header (1 + 2 + 8 + 1 + 4) and field header (1 + 2 + 7).
+    assert_eq!(err.offset(), Some(34));
+    assert!(matches!(err.kind(), NbtError::UnexpectedType { .. }));
+    assert_eq!(
+        err.to_string(),
+        "Expected tag of type String, received Int at offset 34 (sections[0].palette)"
+    );
+}
+
+#[test]
+fn error_progress() {
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Player {
+        level: i32,
+        name: String,
+        inventory: Vec<i32>,
+        health: f32,
+    }
+
+    let player = Player {
+        level: 30,
+        name: "Steve".to_owned(),
+        inventory: vec![1, 2, 3, 4],
+        health: 20.0,
+    };
+    let encoded = to_be_bytes(&player).unwrap();
+
+    // Cut the stream off after the second element of the inventory.
+    // Root header (1 + 2 + 6), level (1 + 2 + 5 + 4), name (1 + 2 + 4 + 2 + 5),
+    // inventory header (1 + 2 + 9 + 1 + 4) and two elements (2 * 4).
+    let truncated = &encoded[..60];
+
+    let err = from_be_bytes::<Player, _>(&mut &truncated[..]).unwrap_err();
+    let progress = err.progress().unwrap();
+    assert_eq!(progress.bytes, 60);
+    assert_eq!(progress.entries, 2);
+    assert_eq!(err.path(), Some("inventory[2]"));
+}
+
+#[test]
+fn validate_structure() {
+    validate::<BigEndian, _>(&mut BIG_TEST_NBT).unwrap();
+    validate::<BigEndian, _>(&mut HELLO_WORLD_NBT).unwrap();
+    validate::<BigEndian, _>(&mut PLAYER_NAN_VALUE_NBT).unwrap();
+
+    let value: Value = from_be_bytes(&mut BIG_TEST_NBT).unwrap();
+    validate::<NetworkLittleEndian, _>(&mut to_net_bytes(&value).unwrap().as_slice()).unwrap();
+    validate::<LittleEndian, _>(&mut to_le_bytes(&value).unwrap().as_slice()).unwrap();
+
+    // Every truncation of a valid document must be rejected.
+    for len in 0..HELLO_WORLD_NBT.len() {
+        let err = validate::<BigEndian, _>(&mut &HELLO_WORLD_NBT[..len]).unwrap_err();
+        assert!(err.offset().is_some());
+    }
+
+    // Multi-byte characters that are split across validation chunks.
+    let value = Value::Compound(Compound::from_iter([(
+        "text".to_owned(),
+        Value::String("é".repeat(300)),
+    )]));
+    validate::<BigEndian, _>(&mut to_be_bytes(&value).unwrap().as_slice()).unwrap();
+
+    // Invalid UTF-8 in the name of the root compound.
+    let mut invalid = HELLO_WORLD_NBT.to_vec();
+    invalid[3] = 0xff;
+    assert!(validate::<BigEndian, _>(&mut invalid.as_slice()).is_err());
+}
+
+#[test]
+fn error_source() {
+    use std::error::Error;
+
+    // Truncated input causes an IO error in the underlying reader.
+    let err = from_be_bytes::<Value, _>(&mut &HELLO_WORLD_NBT[..10]).unwrap_err();
+    assert!(err.source().is_some());
+
+    let io_error = std::iter::successors(err.source(), |&e| e.source())
+        .find_map(|e| e.downcast_ref::<std::io::Error>())
+        .expect("the source chain should contain an io::Error");
+    assert_eq!(io_error.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    let err = NbtError::from(std::io::Error::other("failure"));
+    assert!(err
+        .source()
+        .is_some_and(|e| e.downcast_ref::<std::io::Error>().is_some()));
+}
+
+#[test]
+fn untagged_enum() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum BlockEntity {
+        // `bool` cannot be used here, see `Deserializer::deserialize_any`.
+        Sign { text: String, glowing: i8 },
+        Chest { items: Vec<Value>, lock: i32 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Chunk {
+        entities: Vec<BlockEntity>,
+    }
+
+    let chunk = Chunk {
+        entities: vec![
+            BlockEntity::Chest {
+                items: vec![Value::Compound(Compound::from_iter([(
+                    "id".to_owned(),
+                    Value::String("minecraft:stone".to_owned()),
+                )]))],
+                lock: 3,
+            },
+            BlockEntity::Sign {
+                text: "Hello".to_owned(),
+                glowing: 1,
+            },
+        ],
+    };
+
+    // Serialize each variant independently, since untagged enums are written as the inner struct.
+    let sign = BlockEntity::Sign {
+        text: "Hello".to_owned(),
+        glowing: 0,
+    };
+    let encoded = to_le_bytes(&sign).unwrap();
+    let decoded: BlockEntity = from_le_bytes(&mut encoded.as_slice()).unwrap();
+    assert_eq!(decoded, sign);
+
+    let encoded = to_be_bytes(&chunk).unwrap();
+    let decoded: Chunk = from_be_bytes(&mut encoded.as_slice()).unwrap();
+    assert_eq!(decoded, chunk);
+
+    let encoded = to_net_bytes(&chunk).unwrap();
+    let decoded: Chunk = from_net_bytes(&mut encoded.as_slice()).unwrap();
+    assert_eq!(decoded, chunk);
+}
+
+#[test]
+fn content_hash_is_order_independent() {
+    /// Map that serializes its entries in the given order.
+    struct Ordered(Vec<(&'static str, Value)>);
+
+    impl Serialize for Ordered {
+        fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeMap;
+
+            let mut map = ser.serialize_map(Some(self.0.len()))?;
+            for (k, v) in &self.0 {
+                map.serialize_entry(k, v)?;
+            }
+            map.end()
+        }
+    }
+
+    let nested = |a: i32, b: i32| {
+        Value::Compound(Compound::from_iter([
+            ("a".to_owned(), Value::Int(a)),
+            ("b".to_owned(), Value::Int(b)),
+        ]))
+    };
+
+    let first = Ordered(vec![
+        ("name", Value::String("Steve".to_owned())),
+        ("health", Value::Float(f32::NAN)),
+        ("pos", nested(1, 2)),
+    ]);
+    let second = Ordered(vec![
+        ("pos", nested(1, 2)),
+        ("health", Value::Float(-f32::NAN)),
+        ("name", Value::String("Steve".to_owned())),
+    ]);
+    assert_ne!(to_le_bytes(&first).unwrap(), to_le_bytes(&second).unwrap());
+
+    let hash = content_hash::<LittleEndian>(&first).unwrap();
+    assert_eq!(hash, content_hash::<LittleEndian>(&second).unwrap());
+
+    let changed = Ordered(vec![
+        ("pos", nested(1, 3)),
+        ("health", Value::Float(f32::NAN)),
+        ("name", Value::String("Steve".to_owned())),
+    ]);
+    assert_ne!(hash, content_hash::<LittleEndian>(&changed).unwrap());
+}
+
+#[test]
+fn minimized_integer_width() {
+    let value = Value::Compound(Compound::from_iter([
+        ("small".to_owned(), Value::Long(5)),
+        ("medium".to_owned(), Value::Long(-300)),
+        ("large".to_owned(), Value::Long(70000)),
+        ("huge".to_owned(), Value::Long(i64::MAX)),
+        (
+            "list".to_owned(),
+            Value::List(vec![Value::Long(1), Value::Long(2)]),
+        ),
+    ]));
+
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new()).with_minimized_integer_width(true);
+    value.serialize(&mut ser).unwrap();
+    let bytes = ser.into_inner().unwrap();
+
+    let read: Value = from_be_bytes(&mut Cursor::new(&bytes)).unwrap();
+    let expected = Value::Compound(Compound::from_iter([
+        ("small".to_owned(), Value::Byte(5)),
+        ("medium".to_owned(), Value::Short(-300)),
+        ("large".to_owned(), Value::Int(70000)),
+        ("huge".to_owned(), Value::Long(i64::MAX)),
+        (
+            "list".to_owned(),
+            Value::List(vec![Value::Long(1), Value::Long(2)]),
+        ),
+    ]));
+    assert_eq!(read, expected);
+    assert!(bytes.len() < to_be_bytes(&value).unwrap().len());
+}
+
+#[test]
+fn list_elements_written_in_bulk() {
+    /// Writer that counts the amount of write calls.
+    struct Counting {
+        inner: Vec<u8>,
+        writes: usize,
+    }
+
+    impl std::io::Write for Counting {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.writes += 1;
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let value = Value::Compound(Compound::from_iter([
+        ("ints".to_owned(), Value::IntArray((0..100_000).collect())),
+        (
+            "nested".to_owned(),
+            Value::List(vec![
+                Value::List(vec![Value::Long(1), Value::Long(2)]),
+                Value::List(vec![Value::Long(3)]),
+            ]),
+        ),
+    ]));
+
+    let mut writer = Counting {
+        inner: Vec::new(),
+        writes: 0,
+    };
+    to_bytes_in::<BigEndian>(&mut writer, &value).unwrap();
+    assert!(writer.writes < 1000, "{} writes", writer.writes);
+    assert_eq!(writer.inner, to_be_bytes(&value).unwrap());
+
+    let decoded: Value = from_be_bytes(&mut writer.inner.as_slice()).unwrap();
+    assert_eq!(
+        decoded.as_compound().unwrap()["nested"],
+        value.as_compound().unwrap()["nested"]
+    );
+}
+
+#[test]
+fn byte_array_list() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Chunk {
+        sections: ByteArrayList,
+    }
+
+    let chunk = Chunk {
+        sections: ByteArrayList(vec![vec![1, 2, 3], vec![], vec![4; 300]]),
+    };
+
+    let encoded = to_be_bytes(&chunk).unwrap();
+    // Root header (1 + 2 + 5), field header (1 + 2 + 8) and the element type.
+    assert_eq!(encoded[19], FieldType::ByteArray as u8);
+    assert_eq!(
+        from_be_bytes::<Chunk, _>(&mut encoded.as_slice()).unwrap(),
+        chunk
+    );
+
+    let encoded = to_net_bytes(&chunk).unwrap();
+    assert_eq!(
+        from_net_bytes::<Chunk, _>(&mut encoded.as_slice()).unwrap(),
+        chunk
+    );
+
+    let encoded = to_le_bytes(&chunk).unwrap();
+    let value: Value = from_le_bytes(&mut encoded.as_slice()).unwrap();
+    assert_eq!(
+        value.as_compound().unwrap()["sections"],
+        Value::List(vec![
+            Value::ByteArray(vec![1, 2, 3]),
+            Value::ByteArray(vec![]),
+            Value::ByteArray(vec![4; 300]),
+        ])
+    );
+}
+
+#[test]
+fn scalar() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Field {
+        value: Scalar,
+    }
+
+    for value in [
+        Scalar::Byte(-5),
+        Scalar::Short(300),
+        Scalar::Int(70000),
+        Scalar::Long(i64::MIN),
+        Scalar::Float(1.5),
+        Scalar::Double(-2.25),
+        Scalar::String("Steve".to_owned()),
+    ] {
+        let field = Field { value };
+
+        let encoded = to_be_bytes(&field).unwrap();
+        assert_eq!(
+            from_be_bytes::<Field, _>(&mut encoded.as_slice()).unwrap(),
+            field
+        );
+
+        let encoded = to_net_bytes(&field).unwrap();
+        assert_eq!(
+            from_net_bytes::<Field, _>(&mut encoded.as_slice()).unwrap(),
+            field
+        );
+    }
+
+    let compound = Value::Compound(Compound::from_iter([(
+        "value".to_owned(),
+        Value::Compound(Compound::from_iter([("a".to_owned(), Value::Int(1))])),
+    )]));
+    let encoded = to_be_bytes(&compound).unwrap();
+    assert!(from_be_bytes::<Field, _>(&mut encoded.as_slice()).is_err());
+
+    let list = Value::Compound(Compound::from_iter([(
+        "value".to_owned(),
+        Value::List(vec![Value::Int(1)]),
+    )]));
+    let encoded = to_be_bytes(&list).unwrap();
+    assert!(from_be_bytes::<Field, _>(&mut encoded.as_slice()).is_err());
+}
+
+#[test]
+fn with_capacity() {
+    let value = Value::Compound(Compound::from_iter([(
+        "data".to_owned(),
+        Value::IntArray(vec![1; 64]),
+    )]));
+
+    let encoded = to_bytes_with_capacity::<BigEndian>(&value, 1024).unwrap();
+    assert!(encoded.capacity() >= 1024);
+    assert_eq!(encoded, to_be_bytes(&value).unwrap());
+}
+
+#[test]
+fn as_float() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Entity {
+        speed: AsFloat,
+    }
+
+    let entity = Entity {
+        speed: AsFloat(0.5),
+    };
+    let encoded = to_be_bytes(&entity).unwrap();
+    // Root header (1 + 2 + 6) and the field tag.
+    assert_eq!(encoded[9], FieldType::Float as u8);
+    assert_eq!(
+        from_be_bytes::<Entity, _>(&mut encoded.as_slice()).unwrap(),
+        entity
+    );
+
+    // Precision beyond that of an `f32` is lost.
+    let entity = Entity {
+        speed: AsFloat(0.1),
+    };
+    let encoded = to_net_bytes(&entity).unwrap();
+    let decoded: Entity = from_net_bytes(&mut encoded.as_slice()).unwrap();
+    assert_ne!(decoded.speed.0, 0.1);
+    assert_eq!(decoded.speed.0, 0.1f32 as f64);
+}
+
+#[test]
+fn list_length_mismatch() {
+    /// Sequence that declares a different length than the amount of elements it yields.
+    struct Lying {
+        declared: usize,
+        actual: usize,
+    }
+
+    impl Serialize for Lying {
+        fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeSeq;
+
+            let mut seq = ser.serialize_seq(Some(self.declared))?;
+            for i in 0..self.actual {
+                seq.serialize_element(&(i as i32))?;
+            }
+            seq.end()
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Root {
+        list: Lying,
+    }
+
+    for (declared, actual) in [(3, 2), (1, 2), (2, 0)] {
+        let err = to_be_bytes(&Root {
+            list: Lying { declared, actual },
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            NbtError::LengthMismatch { declared: d, actual: a } if d == declared && a == actual
+        ));
+    }
+
+    // Empty lists are written with an element type of `End`.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Empty {
+        list: Vec<i32>,
+        after: i8,
+    }
+
+    let empty = Empty {
+        list: Vec::new(),
+        after: 1,
+    };
+    let encoded = to_be_bytes(&empty).unwrap();
+    // Root header (1 + 2 + 5) and list header (1 + 2 + 4).
+    assert_eq!(&encoded[15..20], [FieldType::End as u8, 0, 0, 0, 0]);
+    assert_eq!(
+        from_be_bytes::<Empty, _>(&mut encoded.as_slice()).unwrap(),
+        empty
+    );
+}
+
+#[test]
+fn float_policy() {
+    #[derive(Serialize)]
+    struct Health {
+        value: f64,
+    }
+
+    let serialize = |policy: FloatPolicy, value: f64| {
+        let mut ser = Serializer::<_, BigEndian>::new(Vec::new()).with_float_policy(policy);
+        Health { value }
+            .serialize(&mut ser)
+            .and_then(|_| ser.into_inner())
+    };
+    // Root header (1 + 2 + 6) and field header (1 + 2 + 5).
+    let payload = |bytes: Vec<u8>| u64::from_be_bytes(bytes[17..25].try_into().unwrap());
+
+    let nan = f64::from_bits(0x7ff8_dead_beef_0001);
+    assert!(nan.is_nan());
+
+    let bytes = serialize(FloatPolicy::Passthrough, nan).unwrap();
+    assert_eq!(payload(bytes), nan.to_bits());
+
+    let bytes = serialize(FloatPolicy::Canonicalize, nan).unwrap();
+    assert_eq!(payload(bytes), f64::NAN.to_bits());
+    let bytes = serialize(FloatPolicy::Canonicalize, f64::INFINITY).unwrap();
+    assert_eq!(payload(bytes), f64::INFINITY.to_bits());
+
+    let err = serialize(FloatPolicy::Reject, f64::NAN).unwrap_err();
+    assert!(matches!(err, NbtError::NonFiniteFloat { value } if value.is_nan()));
+    assert!(serialize(FloatPolicy::Reject, f64::NEG_INFINITY).is_err());
+    assert!(serialize(FloatPolicy::Reject, 1.5).is_ok());
+}
+
+#[test]
+fn root_primitives() {
+    // Values other than compounds are written as a tag with an empty name.
+    assert_eq!(to_be_bytes(&42i32).unwrap(), [3, 0, 0, 0, 0, 0, 42]);
+    assert_eq!(to_le_bytes(&42i32).unwrap(), [3, 0, 0, 42, 0, 0, 0]);
+    assert_eq!(to_net_bytes(&42i32).unwrap(), [3, 0, 84]);
+
+    assert_eq!(
+        to_be_bytes("abc").unwrap(),
+        [8, 0, 0, 0, 3, b'a', b'b', b'c']
+    );
+    assert_eq!(to_net_bytes("abc").unwrap(), [8, 0, 3, b'a', b'b', b'c']);
+
+    assert_eq!(to_be_bytes(&true).unwrap(), [1, 0, 0, 1]);
+    assert_eq!(to_be_bytes(&false).unwrap(), [1, 0, 0, 0]);
+
+    let list = to_be_bytes(&vec![1i8, 2]).unwrap();
+    assert_eq!(
+        list,
+        [
+            FieldType::List as u8,
+            0,
+            0,
+            FieldType::Byte as u8,
+            0,
+            0,
+            0,
+            2,
+            1,
+            2
+        ]
+    );
+
+    fn round_trip<T>(value: T)
+    where
+        T: Serialize + for<'de> Deserialize<'de> + PartialEq + std::fmt::Debug,
+    {
+        let encoded = to_be_bytes(&value).unwrap();
+        assert_eq!(
+            from_be_bytes::<T, _>(&mut encoded.as_slice()).unwrap(),
+            value
+        );
+        assert_eq!(from_be_slice::<T>(&encoded).unwrap(), value);
+        assert!(validate::<BigEndian, _>(&mut encoded.as_slice()).is_ok());
+
+        let encoded = to_le_bytes(&value).unwrap();
+        assert_eq!(from_le_slice::<T>(&encoded).unwrap(), value);
+
+        let encoded = to_net_bytes(&value).unwrap();
+        assert_eq!(from_net_slice::<T>(&encoded).unwrap(), value);
+    }
+
+    round_trip(42i32);
+    round_trip(i32::MIN);
+    round_trip("Steve".to_owned());
+    round_trip(vec![1i32, -2, 3]);
+    round_trip(vec!["a".to_owned(), "b".to_owned()]);
+    round_trip(Value::Int(42));
+    round_trip(Value::String("Steve".to_owned()));
+    round_trip(Value::List(vec![Value::Short(1), Value::Short(2)]));
+
+    // A root value of the wrong type is reported.
+    let encoded = to_be_bytes(&42i32).unwrap();
+    assert!(from_be_slice::<String>(&encoded).is_err());
+}
+
+#[test]
+fn uuid_formats() {
+    use crate::uuid::{self, UuidFormat};
+
+    let id = 0xf81d4fae_7dec_11d0_a765_00a0c91e6bf6_u128;
+
+    assert_eq!(uuid::from_ints(uuid::to_ints(id)), id);
+    assert_eq!(
+        uuid::to_ints(id),
+        [
+            0xf81d4faeu32 as i32,
+            0x7dec11d0,
+            0xa76500a0u32 as i32,
+            0xc91e6bf6u32 as i32
+        ]
+    );
+
+    let (most, least) = uuid::to_most_least(id);
+    assert_eq!(uuid::from_most_least(most, least), id);
+
+    assert_eq!(uuid::format(id), "f81d4fae-7dec-11d0-a765-00a0c91e6bf6");
+    assert_eq!(
+        uuid::parse("F81D4FAE-7DEC-11D0-A765-00A0C91E6BF6"),
+        Some(id)
+    );
+    assert_eq!(uuid::parse("f81d4fae7dec11d0a76500a0c91e6bf6"), None);
+    assert_eq!(uuid::parse("f81d4fae-7dec-11d0-a765-00a0c91e6bfg"), None);
+
+    for format in [
+        UuidFormat::IntArray,
+        UuidFormat::MostLeast,
+        UuidFormat::String,
+    ] {
+        let mut compound = Compound::default();
+        uuid::write(&mut compound, "UUID", id, format);
+        assert_eq!(uuid::read(&compound, "UUID"), Some(id));
+
+        // Int arrays are read back as lists of ints.
+        let encoded = to_be_bytes(&Value::Compound(compound)).unwrap();
+        let decoded: Compound = from_be_bytes(&mut encoded.as_slice()).unwrap();
+        assert_eq!(uuid::read(&decoded, "UUID"), Some(id));
+    }
+
+    let mut compound = Compound::default();
+    uuid::write(&mut compound, "UUID", id, UuidFormat::MostLeast);
+    uuid::write(&mut compound, "UUID", id, UuidFormat::String);
+    assert_eq!(compound.len(), 1);
+}
+
+#[test]
+fn merge_values() {
+    let compound = |entries: Vec<(&str, Value)>| {
+        Value::Compound(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect(),
+        )
+    };
+
+    let mut base = compound(vec![
+        ("name", Value::String("Steve".to_owned())),
+        ("health", Value::Float(20.0)),
+        (
+            "abilities",
+            compound(vec![
+                ("flying", Value::Byte(0)),
+                ("walkSpeed", Value::Float(0.1)),
+            ]),
+        ),
+        (
+            "inventory",
+            Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(3)]),
+        ),
+    ]);
+
+    base.merge(compound(vec![
+        ("health", Value::Float(10.0)),
+        ("abilities", compound(vec![("flying", Value::Byte(1))])),
+        ("inventory", Value::List(vec![Value::Int(4)])),
+        ("level", Value::Int(30)),
+    ]));
+
+    let expected = compound(vec![
+        ("name", Value::String("Steve".to_owned())),
+        ("health", Value::Float(10.0)),
+        (
+            "abilities",
+            compound(vec![
+                ("flying", Value::Byte(1)),
+                ("walkSpeed", Value::Float(0.1)),
+            ]),
+        ),
+        ("inventory", Value::List(vec![Value::Int(4)])),
+        ("level", Value::Int(30)),
+    ]);
+    assert_eq!(base, expected);
+
+    // Merging a non-compound replaces the value.
+    base.merge(Value::Int(1));
+    assert_eq!(base, Value::Int(1));
+}
+
+#[test]
+fn display_tree() {
+    let value = Value::Compound(Compound::from_iter([
+        ("xPos".to_owned(), Value::Int(5)),
+        ("name".to_owned(), Value::String("Steve".to_owned())),
+        (
+            "items".to_owned(),
+            Value::List(vec![Value::Short(1), Value::Short(2)]),
+        ),
+        ("heights".to_owned(), Value::IntArray((0..10).collect())),
+        ("empty".to_owned(), Value::Compound(Compound::default())),
+    ]));
+
+    assert_eq!(
+        value.tree().with_name("Level").to_string(),
+        r#"TAG_Compound("Level"): 5 entries {
+  TAG_Compound("empty"): 0 entries {}
+  TAG_IntArray("heights"): 10 ints [0, 1, 2, 3, 4, 5, 6, 7, ...]
+  TAG_List("items"): 2 entries {
+    TAG_Short(None): 1
+    TAG_Short(None): 2
+  }
+  TAG_String("name"): "Steve"
+  TAG_Int("xPos"): 5
+}
+"#
+    );
+
+    assert_eq!(
+        Value::List(vec![Value::Byte(1)])
+            .tree()
+            .with_indent(4)
+            .to_string(),
+        "TAG_List(None): 1 entry {\n    TAG_Byte(None): 1\n}\n"
+    );
+    assert_eq!(Value::Long(7).display_tree(), "TAG_Long(None): 7\n");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_round_trip() {
+    use crate::json::IntegerWidth;
+
+    let value = Value::Compound(Compound::from_iter([
+        ("name".to_owned(), Value::String("Steve".to_owned())),
+        (
+            "stats".to_owned(),
+            Value::Compound(Compound::from_iter([
+                ("level".to_owned(), Value::Byte(30)),
+                ("xp".to_owned(), Value::Int(1_000_000)),
+                ("health".to_owned(), Value::Double(19.5)),
+                (
+                    "scores".to_owned(),
+                    Value::List(vec![Value::Short(300), Value::Short(-1)]),
+                ),
+            ])),
+        ),
+    ]));
+
+    let json = serde_json::Value::from(&value);
+    assert_eq!(json["stats"]["scores"], serde_json::json!([300, -1]));
+    assert_eq!(Value::try_from(json.clone()).unwrap(), value);
+
+    // Integers are widened to the widest element of a list.
+    let widened = Value::try_from(serde_json::json!([1, 70000])).unwrap();
+    assert_eq!(widened, Value::List(vec![Value::Int(1), Value::Int(70000)]));
+    let widened = Value::try_from(serde_json::json!([1, 2.5])).unwrap();
+    assert_eq!(
+        widened,
+        Value::List(vec![Value::Double(1.0), Value::Double(2.5)])
+    );
+
+    let long = Value::from_json(serde_json::json!(5), IntegerWidth::Long).unwrap();
+    assert_eq!(long, Value::Long(5));
+    let int = Value::from_json(serde_json::json!(5), IntegerWidth::Int).unwrap();
+    assert_eq!(int, Value::Int(5));
+
+    assert!(Value::try_from(serde_json::json!([1, "a"])).is_err());
+    assert!(Value::try_from(serde_json::json!({ "a": null })).is_err());
+}
+
+#[test]
+fn list_of_differing_compounds() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Palette {
+        entries: Vec<Value>,
+    }
+
+    let palette = Palette {
+        entries: vec![
+            Value::Compound(Compound::from_iter([
+                (
+                    "name".to_owned(),
+                    Value::String("minecraft:stone".to_owned()),
+                ),
+                ("version".to_owned(), Value::Int(1)),
+            ])),
+            Value::Compound(Compound::from_iter([(
+                "states".to_owned(),
+                Value::Compound(Compound::from_iter([(
+                    "facing".to_owned(),
+                    Value::String("north".to_owned()),
+                )])),
+            )])),
+        ],
+    };
+
+    let encoded = to_le_bytes(&palette).unwrap();
+    let decoded: Palette = from_le_bytes(&mut Cursor::new(&encoded)).unwrap();
+    assert_eq!(decoded, palette);
+
+    let value = Value::Compound(Compound::from_iter([
+        ("compounds".to_owned(), Value::List(palette.entries)),
+        (
+            "lists".to_owned(),
+            Value::List(vec![
+                Value::List(vec![Value::Int(1), Value::Int(2)]),
+                Value::List(vec![Value::Int(3)]),
+            ]),
+        ),
+        (
+            "ints".to_owned(),
+            Value::List(vec![Value::Int(4), Value::Int(5)]),
+        ),
+    ]));
+
+    let encoded = to_net_bytes(&value).unwrap();
+    let decoded: Value = from_net_bytes(&mut Cursor::new(&encoded)).unwrap();
+    assert_eq!(decoded, value);
+}
+
+#[test]
+fn snbt_key_quoting() {
+    use crate::snbt::{needs_quotes, write_key};
+
+    let key = |k: &str| {
+        let mut out = String::new();
+        write_key(&mut out, k).unwrap();
+        out
+    };
+
+    assert!(!needs_quotes("Health"));
+    assert_eq!(key("Health"), "Health");
+    assert_eq!(key("a-b_c.d+1"), "a-b_c.d+1");
+    assert_eq!(key("custom name"), r#""custom name""#);
+    assert_eq!(key("minecraft:stone"), r#""minecraft:stone""#);
+    assert_eq!(key(""), r#""""#);
+    assert_eq!(key(r#"say "hi""#), r#""say \"hi\"""#);
+    assert_eq!(key(r"back\slash"), r#""back\\slash""#);
+
+    let value = Value::Compound(Compound::from_iter([
+        ("Count".to_owned(), Value::Byte(1)),
+        ("minecraft:id".to_owned(), Value::String("stone".to_owned())),
+        (
+            "".to_owned(),
+            Value::List(vec![Value::Int(1), Value::Int(2)]),
+        ),
+        ("ints".to_owned(), Value::IntArray(vec![3, 4])),
+    ]));
+    assert_eq!(
+        value.to_snbt(),
+        r#"{"":[1,2],Count:1b,ints:[I;3,4],"minecraft:id":"stone"}"#
+    );
+}
+
+#[test]
+fn to_all_variants() {
+    let value: Value = from_be_bytes(&mut Cursor::new(BIG_TEST_NBT)).unwrap();
+    let (big, little, network) = value.to_all_variants().unwrap();
+
+    assert_eq!(from_be_slice::<Value>(&big).unwrap(), value);
+    assert_eq!(from_le_slice::<Value>(&little).unwrap(), value);
+    assert_eq!(from_net_slice::<Value>(&network).unwrap(), value);
+}
+
+#[test]
+fn byte_list() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct A {
+        data: ByteList,
+    }
+
+    #[derive(Serialize)]
+    struct B {
+        data: Value,
+    }
+
+    let list = A {
+        data: ByteList(vec![1, -2, 3]),
+    };
+    let array = B {
+        data: Value::ByteArray(vec![1, 254, 3]),
+    };
+
+    let list_encoded = to_be_bytes(&list).unwrap();
+    let array_encoded = to_be_bytes(&array).unwrap();
+    // Root header (1 + 2 + 1), followed by the field tag.
+    assert_eq!(list_encoded[4], FieldType::List as u8);
+    assert_eq!(array_encoded[4], FieldType::ByteArray as u8);
+    // The list additionally stores its element type before the length.
+    assert_eq!(list_encoded[11], FieldType::Byte as u8);
+    assert_eq!(list_encoded.len(), array_encoded.len() + 1);
+
+    assert_eq!(from_be_slice::<A>(&list_encoded).unwrap(), list);
+    assert_eq!(from_be_slice::<A>(&array_encoded).unwrap(), list);
+
+    let value: Value = from_be_slice(&list_encoded).unwrap();
+    assert_eq!(
+        value.as_compound().unwrap()["data"],
+        Value::List(vec![Value::Byte(1), Value::Byte(-2), Value::Byte(3)])
+    );
+}
+
+#[test]
+fn fuzz_corpus() {
+    use crate::{from_bytes, from_slice, EndiannessImpl};
+
+    fn check<F: EndiannessImpl + 'static>(name: &str, data: &[u8]) {
+        let value = from_bytes::<F, Value>(&mut Cursor::new(data));
+        assert!(value.is_err(), "{name} was read: {value:?}");
+        let value = from_slice::<F, Value>(data);
+        assert!(value.is_err(), "{name} was read from a slice: {value:?}");
+        assert!(
+            validate::<F, _>(&mut Cursor::new(data)).is_err(),
+            "{name} was validated"
+        );
+    }
+
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/test/fuzz_corpus");
+    let mut count = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+        let data = std::fs::read(&path).unwrap();
+
+        match name.split('_').next().unwrap() {
+            "be" => check::<BigEndian>(&name, &data),
+            "le" => check::<LittleEndian>(&name, &data),
+            "net" => check::<NetworkLittleEndian>(&name, &data),
+            prefix => panic!("{name} has an unknown variant prefix {prefix}"),
+        }
+        count += 1;
+    }
+    assert!(count > 0);
+}
+
+#[test]
+fn unknown_field_handler() {
+    use crate::Deserializer;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Stats {
+        level: i32,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Player {
+        name: String,
+        stats: Stats,
+    }
+
+    let value = Value::Compound(Compound::from_iter([
+        ("name".to_owned(), Value::String("Steve".to_owned())),
+        ("health".to_owned(), Value::Float(20.0)),
+        (
+            "stats".to_owned(),
+            Value::Compound(Compound::from_iter([
+                ("level".to_owned(), Value::Int(30)),
+                ("xp".to_owned(), Value::List(vec![Value::Long(1)])),
+            ])),
+        ),
+    ]));
+    let encoded = to_le_bytes(&value).unwrap();
+
+    let mut unknown = Vec::new();
+    let mut input = Cursor::new(&encoded);
+    let mut de = Deserializer::<LittleEndian, _>::new(&mut input)
+        .unwrap()
+        .with_unknown_field_handler(|path, value| unknown.push((path.to_owned(), value.clone())));
+    let player = Player::deserialize(&mut de).unwrap();
+    drop(de);
+
+    assert_eq!(
+        player,
+        Player {
+            name: "Steve".to_owned(),
+            stats: Stats { level: 30 },
+        }
+    );
+    unknown.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        unknown,
+        [
+            ("health".to_owned(), Value::Float(20.0)),
+            ("stats.xp".to_owned(), Value::List(vec![Value::Long(1)])),
+        ]
+    );
+}
+
+#[test]
+fn sets() {
+    use std::collections::{BTreeSet, HashSet};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Sets {
+        ids: HashSet<i32>,
+        tags: BTreeSet<String>,
+    }
+
+    let sets = Sets {
+        ids: HashSet::from([3, 1, 2]),
+        tags: BTreeSet::from(["b".to_owned(), "c".to_owned(), "a".to_owned()]),
+    };
+
+    let encoded = to_be_bytes(&sets).unwrap();
+    assert_eq!(from_be_slice::<Sets>(&encoded).unwrap(), sets);
+
+    let value: Value = from_be_slice(&encoded).unwrap();
+    let compound = value.as_compound().unwrap();
+
+    // The iteration order of a `HashSet` is not deterministic, so the elements are sorted first.
+    let Value::List(ids) = &compound["ids"] else {
+        panic!("ids is not a list: {:?}", compound["ids"]);
+    };
+    let mut ids = ids
+        .iter()
+        .map(|id| match id {
+            Value::Int(id) => *id,
+            other => panic!("id is not an int: {other:?}"),
+        })
+        .collect::<Vec<_>>();
+    ids.sort_unstable();
+    assert_eq!(ids, [1, 2, 3]);
+
+    // A `BTreeSet` is always written in sorted order.
+    assert_eq!(
+        compound["tags"],
+        Value::List(vec![
+            Value::String("a".to_owned()),
+            Value::String("b".to_owned()),
+            Value::String("c".to_owned()),
+        ])
+    );
+    let reordered: BTreeSet<String> = ["c", "a", "b"].into_iter().map(str::to_owned).collect();
+    assert_eq!(
+        to_be_bytes(&reordered).unwrap(),
+        to_be_bytes(&sets.tags).unwrap()
+    );
+}
+
+#[test]
+fn unknown_tag() {
+    for tag in FieldType::LongArray as u8 + 1..=u8::MAX {
+        assert!(matches!(
+            FieldType::try_from(tag),
+            Err(NbtError::UnknownTag(actual)) if actual == tag
+        ));
+
+        // Root header, followed by an entry with the invalid type.
+        let data = [0x0a, 0, 0, tag, 0, 1, b'a', 0];
+        let err = from_be_slice::<Value>(&data).unwrap_err();
+        assert!(matches!(err.kind(), NbtError::UnknownTag(actual) if *actual == tag));
+        assert_eq!(err.offset(), Some(4));
+
+        // Element type of a list.
+        let data = [0x0a, 0, 0, 9, 0, 1, b'a', tag, 0, 0, 0, 1, 0];
+        let err = from_be_slice::<Value>(&data).unwrap_err();
+        assert!(matches!(err.kind(), NbtError::UnknownTag(actual) if *actual == tag));
+    }
+
+    let err = from_be_slice::<Value>(&[0x0a, 0, 0, 0x13, 0, 1, b'a', 0]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unknown tag 0x13, it should be in the range 0x00-0x0c at offset 4"
+    );
+}
+
+#[test]
+fn from_bytes_with_extras() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Inventory {
+        size: i32,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Player {
+        name: String,
+        inventory: Inventory,
+    }
+
+    let value = Value::Compound(Compound::from_iter([
+        ("name".to_owned(), Value::String("Steve".to_owned())),
+        ("health".to_owned(), Value::Float(20.0)),
+        (
+            "pos".to_owned(),
+            Value::List(vec![Value::Double(1.0), Value::Double(64.0)]),
+        ),
+        (
+            "inventory".to_owned(),
+            Value::Compound(Compound::from_iter([
+                ("size".to_owned(), Value::Int(36)),
+                ("locked".to_owned(), Value::Byte(1)),
+            ])),
+        ),
+    ]));
+    let encoded = to_net_bytes(&value).unwrap();
+
+    let (player, extras) =
+        crate::from_bytes_with_extras::<NetworkLittleEndian, Player>(&encoded).unwrap();
+    assert_eq!(
+        player,
+        Player {
+            name: "Steve".to_owned(),
+            inventory: Inventory { size: 36 },
+        }
+    );
+    // Unknown fields of nested compounds are not collected.
+    assert_eq!(
+        extras,
+        Compound::from_iter([
+            ("health".to_owned(), Value::Float(20.0)),
+            (
+                "pos".to_owned(),
+                Value::List(vec![Value::Double(1.0), Value::Double(64.0)]),
+            ),
+        ])
+    );
+}
+
+#[test]
+fn invalid_list_type() {
+    // Root header and the header of a list named "a" with element type End.
+    let header = [0x0a, 0, 0, 9, 0, 1, b'a', 0];
+
+    let mut empty = header.to_vec();
+    empty.extend([0, 0, 0, 0, 0]);
+    let value: Value = from_be_slice(&empty).unwrap();
+    assert_eq!(value.as_compound().unwrap()["a"], Value::List(vec![]));
+    validate::<BigEndian, _>(&mut empty.as_slice()).unwrap();
+
+    let mut invalid = header.to_vec();
+    invalid.extend([0, 0, 0, 3, 0]);
+    let err = from_be_slice::<Value>(&invalid).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::InvalidListType { len: 3 }));
+    let err = from_be_bytes::<Value, _>(&mut invalid.as_slice()).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::InvalidListType { len: 3 }));
+    let err = validate::<BigEndian, _>(&mut invalid.as_slice()).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::InvalidListType { len: 3 }));
+
+    let err = ListDeserializer::<BigEndian, _, Value>::new(&mut &invalid[7..]).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::InvalidListType { len: 3 }));
+}
+
+#[test]
+fn runtime_variant() {
+    use crate::{from_bytes_dyn, to_bytes_dyn, Variant};
+
+    let value: Value = from_be_slice(BIG_TEST_NBT).unwrap();
+    for variant in [
+        Variant::BigEndian,
+        Variant::LittleEndian,
+        Variant::NetworkEndian,
+    ] {
+        let encoded = to_bytes_dyn(variant, &value).unwrap();
+        let expected = match variant {
+            Variant::BigEndian => to_be_bytes(&value),
+            Variant::LittleEndian => to_le_bytes(&value),
+            Variant::NetworkEndian => to_net_bytes(&value),
+        };
+        assert_eq!(encoded, expected.unwrap());
+
+        let decoded: Value = from_bytes_dyn(variant, &mut encoded.as_slice()).unwrap();
+        assert_eq!(decoded, value);
+    }
+}
+
+#[test]
+fn root_end() {
+    #[derive(Serialize)]
+    struct Header {
+        name: String,
+        inventory: Vec<HashMap<String, i32>>,
+    }
+
+    #[derive(Serialize)]
+    struct Stats {
+        level: i32,
+    }
+
+    let mut ser = Serializer::<_, LittleEndian>::new(Vec::new()).with_root_end(false);
+    Header {
+        name: "Steve".to_owned(),
+        inventory: vec![HashMap::from([("count".to_owned(), 1)])],
+    }
+    .serialize(&mut ser)
+    .unwrap();
+    Stats { level: 30 }.serialize(&mut ser).unwrap();
+
+    // Nested compounds are still terminated, only the root is left open.
+    let mut encoded = ser.into_inner().unwrap();
+    assert!(from_le_slice::<Value>(&encoded).is_err());
+
+    encoded.push(FieldType::End as u8);
+    let value: Value = from_le_slice(&encoded).unwrap();
+    assert_eq!(
+        value,
+        Value::Compound(Compound::from_iter([
+            ("name".to_owned(), Value::String("Steve".to_owned())),
+            (
+                "inventory".to_owned(),
+                Value::List(vec![Value::Compound(Compound::from_iter([(
+                    "count".to_owned(),
+                    Value::Int(1)
+                )]))]),
+            ),
+            ("level".to_owned(), Value::Int(30)),
+        ]))
+    );
+}
+
+#[cfg(feature = "indexmap")]
+#[test]
+fn preserve_compound_order() {
+    #[derive(Serialize)]
+    struct Item {
+        slot: i8,
+        id: String,
+        count: i8,
+    }
+
+    #[derive(Serialize)]
+    struct Player {
+        z_pos: f64,
+        name: String,
+        inventory: Vec<Item>,
+        abilities: HashMap<String, i8>,
+        a_pos: f64,
+    }
+
+    let player = Player {
+        z_pos: 1.5,
+        name: "Steve".to_owned(),
+        inventory: vec![
+            Item {
+                slot: 0,
+                id: "minecraft:stone".to_owned(),
+                count: 64,
+            },
+            Item {
+                slot: 8,
+                id: "minecraft:dirt".to_owned(),
+                count: 1,
+            },
+        ],
+        abilities: HashMap::from([("flying".to_owned(), 0), ("mayfly".to_owned(), 1)]),
+        a_pos: -3.0,
+    };
+
+    let original = to_be_bytes(&player).unwrap();
+    let value: Value = from_be_slice(&original).unwrap();
+    assert_eq!(
+        value.as_compound().unwrap().keys().collect::<Vec<_>>(),
+        ["z_pos", "name", "inventory", "abilities", "a_pos"]
+    );
+    // The name of the root compound is not stored in a `Value`, so only the payload is compared.
+    let reencoded = to_be_bytes(&value).unwrap();
+    assert_eq!(reencoded[1 + 2..], original[1 + 2 + "Player".len()..]);
+
+    let original = to_net_bytes(&player).unwrap();
+    let value: Value = from_net_slice(&original).unwrap();
+    let reencoded = to_net_bytes(&value).unwrap();
+    assert_eq!(reencoded[1 + 1..], original[1 + 1 + "Player".len()..]);
+}
+
+#[test]
+fn interior_mutability() {
+    use std::cell::RefCell;
+    use std::sync::Mutex;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Shared {
+        counter: RefCell<i32>,
+        name: Mutex<String>,
+        history: RefCell<Vec<i64>>,
+    }
+
+    let shared = Shared {
+        counter: RefCell::new(5),
+        name: Mutex::new("Steve".to_owned()),
+        history: RefCell::new(vec![1, 2]),
+    };
+    *shared.counter.borrow_mut() += 1;
+
+    let encoded = to_le_bytes(&shared).unwrap();
+    let value: Value = from_le_slice(&encoded).unwrap();
+    assert_eq!(
+        value,
+        Value::Compound(Compound::from_iter([
+            ("counter".to_owned(), Value::Int(6)),
+            ("name".to_owned(), Value::String("Steve".to_owned())),
+            (
+                "history".to_owned(),
+                Value::List(vec![Value::Long(1), Value::Long(2)]),
+            ),
+        ]))
+    );
+
+    let decoded: Shared = from_le_slice(&encoded).unwrap();
+    assert_eq!(*decoded.counter.borrow(), 6);
+    assert_eq!(*decoded.name.lock().unwrap(), "Steve");
+    assert_eq!(*decoded.history.borrow(), [1, 2]);
+
+    // A value that is mutably borrowed cannot be serialized.
+    let _guard = shared.counter.borrow_mut();
+    assert!(to_le_bytes(&shared).is_err());
+}
+
+#[test]
+fn find_all() {
+    let air = Value::String("minecraft:air".to_owned());
+    let block = |name: &str| {
+        Value::Compound(Compound::from_iter([
+            ("name".to_owned(), Value::String(name.to_owned())),
+            ("states".to_owned(), Value::Compound(Compound::default())),
+        ]))
+    };
+
+    let value = Value::Compound(Compound::from_iter([
+        (
+            "Sections".to_owned(),
+            Value::List(vec![
+                Value::Compound(Compound::from_iter([(
+                    "Palette".to_owned(),
+                    Value::List(vec![block("minecraft:air"), block("minecraft:stone")]),
+                )])),
+                Value::Compound(Compound::from_iter([(
+                    "Palette".to_owned(),
+                    Value::List(vec![block("minecraft:dirt"), block("minecraft:air")]),
+                )])),
+            ]),
+        ),
+        ("default".to_owned(), air.clone()),
+        ("count".to_owned(), Value::Int(2)),
+    ]));
+
+    let found = value.find_all(|_, v| *v == air);
+    assert_eq!(
+        found,
+        [
+            ("Sections[0].Palette[0].name".to_owned(), &air),
+            ("Sections[1].Palette[1].name".to_owned(), &air),
+            ("default".to_owned(), &air),
+        ]
+    );
+
+    // The predicate can also match on the path segments.
+    let found = value.find_all(|path, _| path.last() == Some(&"Palette"));
+    let paths: Vec<_> = found.into_iter().map(|(path, _)| path).collect();
+    assert_eq!(paths, ["Sections[0].Palette", "Sections[1].Palette"]);
+
+    let found = value.find_all(|path, _| path == ["Sections", "1"]);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].0, "Sections[1]");
+
+    let found = value.find_all(|path, _| path.is_empty());
+    assert_eq!(found, [(String::new(), &value)]);
+}
+
+#[test]
+fn sorted_keys() {
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct World {
+        players: HashMap<String, HashMap<String, i32>>,
+        seed: i64,
+    }
+
+    let world = || World {
+        players: (0..32)
+            .map(|i| {
+                let stats = (0..8).map(|j| (format!("stat{j}"), i * j)).collect();
+                (format!("player{i}"), stats)
+            })
+            .collect(),
+        seed: 42,
+    };
+    let encode = |world: &World| {
+        let mut ser = Serializer::<_, BigEndian>::new(Vec::new()).with_sorted_keys(true);
+        world.serialize(&mut ser).unwrap();
+        ser.into_inner().unwrap()
+    };
+
+    // Every `HashMap` uses a different random iteration order.
+    let a = encode(&world());
+    let b = encode(&world());
+    assert_eq!(a, b);
+
+    #[derive(Serialize)]
+    #[serde(rename = "World")]
+    struct SortedWorld {
+        players: BTreeMap<String, BTreeMap<String, i32>>,
+        seed: i64,
+    }
+
+    let sorted = SortedWorld {
+        players: world()
+            .players
+            .into_iter()
+            .map(|(k, v)| (k, v.into_iter().collect()))
+            .collect(),
+        seed: 42,
+    };
+    assert_eq!(a, to_be_bytes(&sorted).unwrap());
+}
+
+#[test]
+fn read_prefix() {
+    use crate::{from_net_bytes_prefix, from_slice_prefix};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Block<'a> {
+        name: &'a str,
+        version: i32,
+    }
+
+    let first = Block {
+        name: "minecraft:stone",
+        version: 17959425,
+    };
+    let second = Value::Compound(Compound::from_iter([(
+        "states".to_owned(),
+        Value::List(vec![Value::Int(1), Value::Int(2)]),
+    )]));
+
+    let mut packet = to_net_bytes(&first).unwrap();
+    let first_len = packet.len();
+    packet.extend(to_net_bytes(&second).unwrap());
+    let second_len = packet.len() - first_len;
+    packet.extend([1, 2, 3]);
+
+    let (block, len) = from_net_bytes_prefix::<Block>(&packet).unwrap();
+    assert_eq!(block, first);
+    assert_eq!(len, first_len);
+
+    let rest = &packet[len..];
+    let (value, len) = from_net_bytes_prefix::<Value>(rest).unwrap();
+    assert_eq!(value, second);
+    assert_eq!(len, second_len);
+    assert_eq!(&rest[len..], [1, 2, 3]);
+
+    let encoded = to_be_bytes(&first).unwrap();
+    let (block, len) = from_slice_prefix::<BigEndian, Block>(&encoded).unwrap();
+    assert_eq!(block, first);
+    assert_eq!(len, encoded.len());
+
+    assert!(from_net_bytes_prefix::<Value>(&packet[..first_len - 1]).is_err());
+}
+
+#[test]
+fn lenient_utf8() {
+    use crate::{Deserializer, SliceReader};
+
+    // Compound with a string `name` containing a lone surrogate, under an invalid key.
+    let encoded = [
+        10, 0, 0, 8, 4, 0, b'n', 0xff, b'm', b'e', 4, 0, b'S', 0xed, 0xa0, 0x80, 0,
+    ];
+
+    assert!(from_le_bytes::<Value, _>(&mut encoded.as_slice()).is_err());
+    assert!(from_le_slice::<Value>(&encoded).is_err());
+
+    let expected = Value::Compound(Compound::from_iter([(
+        "n\u{fffd}me".to_owned(),
+        Value::String("S\u{fffd}\u{fffd}\u{fffd}".to_owned()),
+    )]));
+
+    let mut input = encoded.as_slice();
+    let mut de = Deserializer::<LittleEndian, _>::new(&mut input)
+        .unwrap()
+        .with_lenient_utf8(true);
+    assert_eq!(Value::deserialize(&mut de).unwrap(), expected);
+
+    let mut input = SliceReader::new(&encoded);
+    let mut de = Deserializer::<LittleEndian, _>::new(&mut input)
+        .unwrap()
+        .with_lenient_utf8(true);
+    assert_eq!(Value::deserialize(&mut de).unwrap(), expected);
+}
+
+#[test]
+fn hashing_writer() {
+    use crate::HashingWriter;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
+    #[derive(Serialize)]
+    struct Data {
+        name: &'static str,
+        values: Vec<i32>,
+    }
+
+    let data = Data {
+        name: "hashed",
+        values: vec![1, 2, 3],
+    };
+
+    let mut writer = HashingWriter::new(DefaultHasher::new(), Vec::new());
+    to_bytes_in::<BigEndian>(&mut writer, &data).unwrap();
+    let hash = writer.finish();
+    let (_, bytes) = writer.into_parts();
+
+    assert_eq!(bytes, to_be_bytes(&data).unwrap());
+
+    let mut expected = DefaultHasher::new();
+    expected.write(&bytes);
+    assert_eq!(hash, expected.finish());
+}
+
+#[test]
+fn skip_value() {
+    use crate::{Deserializer, SliceReader};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Sibling {
+        id: i32,
+        name: String,
+    }
+
+    fn nested(depth: usize) -> Value {
+        let mut entries = Compound::from_iter([
+            ("bytes".to_owned(), Value::ByteArray(vec![1; 300])),
+            ("ints".to_owned(), Value::IntArray(vec![-1; 300])),
+            ("longs".to_owned(), Value::LongArray(vec![i64::MIN; 300])),
+            ("name".to_owned(), Value::String("nested".repeat(100))),
+            ("empty".to_owned(), Value::List(Vec::new())),
+            (
+                "list".to_owned(),
+                Value::List((0..100).map(|i| Value::Double(i as f64)).collect()),
+            ),
+        ]);
+        if depth > 0 {
+            entries.insert("child".to_owned(), nested(depth - 1));
+        }
+
+        Value::Compound(entries)
+    }
+
+    let large = nested(50);
+    let sibling = Sibling {
+        id: 12,
+        name: "sibling".to_owned(),
+    };
+
+    fn check<F: crate::EndiannessImpl>(large: &Value, sibling: &Sibling) {
+        let mut encoded = to_bytes::<F>(large).unwrap();
+        let len = encoded.len();
+        encoded.extend(to_bytes::<F>(sibling).unwrap());
+
+        let mut input = encoded.as_slice();
+        let mut de = Deserializer::<F, _>::new(&mut input).unwrap();
+        de.skip_value().unwrap();
+        assert_eq!(de.offset(), len as u64);
+        drop(de);
+        assert_eq!(
+            crate::from_bytes::<F, Sibling>(&mut input).unwrap(),
+            *sibling
+        );
+
+        let mut input = SliceReader::new(&encoded);
+        Deserializer::<F, _>::new(&mut input)
+            .unwrap()
+            .skip_value()
+            .unwrap();
+        assert_eq!(input.position(), len);
+        let mut de = Deserializer::<F, _>::new(&mut input).unwrap();
+        assert_eq!(Sibling::deserialize(&mut de).unwrap(), *sibling);
+
+        // Truncated input is reported instead of silently stopping.
+        let mut input = &encoded[..len - 1];
+        let mut de = Deserializer::<F, _>::new(&mut input).unwrap();
+        assert!(de.skip_value().is_err());
+    }
+
+    check::<BigEndian>(&large, &sibling);
+    check::<LittleEndian>(&large, &sibling);
+    check::<NetworkLittleEndian>(&large, &sibling);
+
+    // Fixed-width arrays are skipped at once, so a huge declared length fails right away.
+    let mut input: &[u8] = &[11, 0, 0, 0x7f, 0xff, 0xff, 0xff, 1, 2, 3, 4];
+    let mut de = Deserializer::<BigEndian, _>::new(&mut input).unwrap();
+    assert!(de.skip_value().is_err());
+}
+
+#[test]
+fn flatten_ordered_catch_all() {
+    use crate::OrderedCompound;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Entity {
+        id: i32,
+        name: String,
+        #[serde(flatten)]
+        rest: OrderedCompound,
+    }
+
+    let original = OrderedCompound(vec![
+        ("id".to_owned(), Value::Int(5)),
+        ("name".to_owned(), Value::String("zombie".to_owned())),
+        ("zeta".to_owned(), Value::Byte(1)),
+        ("alpha".to_owned(), Value::Double(2.5)),
+        (
+            "mid".to_owned(),
+            Value::List(vec![Value::Short(1), Value::Short(2)]),
+        ),
+        (
+            "nested".to_owned(),
+            Value::Compound(Compound::from_iter([("x".to_owned(), Value::Long(3))])),
+        ),
+        ("beta".to_owned(), Value::String("last".to_owned())),
+    ]);
+    let encoded = to_be_bytes(&original).unwrap();
+
+    let entity: Entity = from_be_slice(&encoded).unwrap();
+    assert_eq!(entity.id, 5);
+    assert_eq!(entity.name, "zombie");
+    let keys: Vec<_> = entity.rest.0.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, ["zeta", "alpha", "mid", "nested", "beta"]);
+    assert_eq!(entity.rest.0[..], original.0[2..]);
+
+    assert_eq!(to_be_bytes(&entity).unwrap(), encoded);
+    assert_eq!(
+        from_net_slice::<Entity>(&to_net_bytes(&entity).unwrap()).unwrap(),
+        entity
+    );
+}
+
+#[test]
+fn max_depth() {
+    use crate::max_depth;
+
+    // Root compound > compound > list > compound.
+    let value = Value::Compound(Compound::from_iter([
+        ("flat".to_owned(), Value::Int(1)),
+        (
+            "outer".to_owned(),
+            Value::Compound(Compound::from_iter([(
+                "entities".to_owned(),
+                Value::List(vec![
+                    Value::Compound(Compound::from_iter([("id".to_owned(), Value::Int(2))])),
+                    Value::Compound(Compound::default()),
+                ]),
+            )])),
+        ),
+        ("shallow".to_owned(), Value::List(vec![Value::Byte(1)])),
+    ]));
+
+    assert_eq!(
+        max_depth::<BigEndian>(&to_be_bytes(&value).unwrap()).unwrap(),
+        4
+    );
+    assert_eq!(
+        max_depth::<LittleEndian>(&to_le_bytes(&value).unwrap()).unwrap(),
+        4
+    );
+    assert_eq!(
+        max_depth::<NetworkLittleEndian>(&to_net_bytes(&value).unwrap()).unwrap(),
+        4
+    );
+
+    let empty = Value::Compound(Compound::default());
+    assert_eq!(
+        max_depth::<BigEndian>(&to_be_bytes(&empty).unwrap()).unwrap(),
+        1
+    );
+    assert_eq!(
+        max_depth::<BigEndian>(&to_be_bytes(&42i32).unwrap()).unwrap(),
+        0
+    );
+    assert_eq!(max_depth::<BigEndian>(BIG_TEST_NBT).unwrap(), 3);
+
+    let encoded = to_be_bytes(&value).unwrap();
+    assert!(max_depth::<BigEndian>(&encoded[..encoded.len() - 1]).is_err());
+}
+
+#[test]
+fn flatten_struct() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Base {
+        id: String,
+        health: f32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Zombie {
+        #[serde(flatten)]
+        base: Base,
+        age: i32,
+    }
+
+    // Structs with flattened fields are serialized as maps, which have no name.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(rename = "")]
+    struct FlatZombie {
+        id: String,
+        health: f32,
+        age: i32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct World<T> {
+        boss: T,
+        entities: Vec<T>,
+    }
+
+    let zombie = || Zombie {
+        base: Base {
+            id: "minecraft:zombie".to_owned(),
+            health: 20.0,
+        },
+        age: 3,
+    };
+    let flat = || FlatZombie {
+        id: "minecraft:zombie".to_owned(),
+        health: 20.0,
+        age: 3,
+    };
+
+    assert_eq!(
+        to_be_bytes(&zombie()).unwrap(),
+        to_be_bytes(&flat()).unwrap()
+    );
+
+    fn sorted(v: &impl Serialize) -> Vec<u8> {
+        let mut ser = Serializer::<_, LittleEndian>::new(Vec::new()).with_sorted_keys(true);
+        v.serialize(&mut ser).unwrap();
+        ser.into_inner().unwrap()
+    }
+    assert_eq!(sorted(&zombie()), sorted(&flat()));
+    let encoded = to_be_bytes(&zombie()).unwrap();
+    assert_eq!(from_be_slice::<FlatZombie>(&encoded).unwrap(), flat());
+    assert_eq!(from_be_slice::<Zombie>(&encoded).unwrap(), zombie());
+
+    let world = World {
+        boss: zombie(),
+        entities: vec![zombie(), zombie()],
+    };
+    let flat_world = World {
+        boss: flat(),
+        entities: vec![flat(), flat()],
+    };
+    let encoded = to_net_bytes(&world).unwrap();
+    assert_eq!(encoded, to_net_bytes(&flat_world).unwrap());
+    assert_eq!(from_net_slice::<World<Zombie>>(&encoded).unwrap(), world);
+}
+
+#[cfg(feature = "arena")]
+#[test]
+fn arena() {
+    use crate::arena::{from_slice_in, ValueRef, ValueSeed};
+    use crate::OrderedCompound;
+    use bumpalo::Bump;
+
+    let mut arena = Bump::new();
+    for variant in 0..3 {
+        let encoded = match variant {
+            0 => BIG_TEST_NBT.to_vec(),
+            1 => to_le_bytes(&from_be_slice::<Value>(BIG_TEST_NBT).unwrap()).unwrap(),
+            _ => to_net_bytes(&from_be_slice::<Value>(BIG_TEST_NBT).unwrap()).unwrap(),
+        };
+        let value = match variant {
+            0 => from_slice_in::<BigEndian>(&encoded, &arena).unwrap(),
+            1 => from_slice_in::<LittleEndian>(&encoded, &arena).unwrap(),
+            _ => from_slice_in::<NetworkLittleEndian>(&encoded, &arena).unwrap(),
+        };
+
+        assert_eq!(
+            value.to_value(),
+            from_be_slice::<Value>(BIG_TEST_NBT).unwrap()
+        );
+        assert_eq!(
+            value
+                .get("nested compound test")
+                .and_then(|v| v.get("egg"))
+                .and_then(|v| v.get("name"))
+                .and_then(|v| v.as_string()),
+            Some("Eggbert")
+        );
+        assert!(matches!(
+            value.get("intTest"),
+            Some(ValueRef::Int(2147483647))
+        ));
+        arena.reset();
+    }
+
+    // The tree does not borrow from the input and can be serialized again.
+    let original = OrderedCompound(vec![
+        (
+            "b".to_owned(),
+            Value::List(vec![Value::Int(1), Value::Int(2)]),
+        ),
+        ("a".to_owned(), Value::String("x".to_owned())),
+        ("bytes".to_owned(), Value::ByteArray(vec![1, 2, 3])),
+    ]);
+    let encoded = to_be_bytes(&original).unwrap();
+    let value = {
+        let copy = encoded.clone();
+        let mut input = copy.as_slice();
+        let mut de = crate::Deserializer::<BigEndian, _>::new(&mut input).unwrap();
+        serde::de::DeserializeSeed::deserialize(ValueSeed(&arena), &mut de).unwrap()
+    };
+    let keys: Vec<_> = value
+        .as_compound()
+        .unwrap()
+        .iter()
+        .map(|(k, _)| *k)
+        .collect();
+    assert_eq!(keys, ["b", "a", "bytes"]);
+    assert_eq!(value.get("bytes"), Some(&ValueRef::ByteArray(&[1, 2, 3])));
+    assert_eq!(to_be_bytes(&value).unwrap(), encoded);
+
+    assert!(from_slice_in::<BigEndian>(&encoded[..encoded.len() - 1], &arena).is_err());
+}
+
+#[test]
+fn string_list() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Sign {
+        lines: Vec<String>,
+    }
+
+    let lines = vec![
+        String::new(),
+        "plain".to_owned(),
+        "ünïcödé".to_owned(),
+        "🦀 crab".to_owned(),
+        String::new(),
+    ];
+
+    // The element type and length are written once, followed by every length-prefixed string.
+    let encoded = to_be_bytes(&lines).unwrap();
+    let mut expected = vec![
+        FieldType::List as u8,
+        0,
+        0,
+        FieldType::String as u8,
+        0,
+        0,
+        0,
+        5,
+    ];
+    for line in &lines {
+        expected.extend((line.len() as u16).to_be_bytes());
+        expected.extend(line.as_bytes());
+    }
+    assert_eq!(encoded, expected);
+
+    let encoded = to_net_bytes(&lines).unwrap();
+    let mut expected = vec![FieldType::List as u8, 0, FieldType::String as u8, 10];
+    for line in &lines {
+        expected.push(line.len() as u8);
+        expected.extend(line.as_bytes());
+    }
+    assert_eq!(encoded, expected);
+
+    let sign = Sign { lines };
+    let encoded = to_be_bytes(&sign).unwrap();
+    assert_eq!(from_be_slice::<Sign>(&encoded).unwrap(), sign);
+    assert_eq!(
+        from_be_bytes::<Sign, _>(&mut encoded.as_slice()).unwrap(),
+        sign
+    );
+    let encoded = to_le_bytes(&sign).unwrap();
+    assert_eq!(from_le_slice::<Sign>(&encoded).unwrap(), sign);
+    assert_eq!(
+        from_le_bytes::<Sign, _>(&mut encoded.as_slice()).unwrap(),
+        sign
+    );
+    let encoded = to_net_bytes(&sign).unwrap();
+    assert_eq!(from_net_slice::<Sign>(&encoded).unwrap(), sign);
+    assert_eq!(
+        from_net_bytes::<Sign, _>(&mut encoded.as_slice()).unwrap(),
+        sign
+    );
+
+    let empty = Sign { lines: Vec::new() };
+    let encoded = to_le_bytes(&empty).unwrap();
+    assert_eq!(from_le_slice::<Sign>(&encoded).unwrap(), empty);
+}
+
+#[test]
+fn value_write_to() {
+    use crate::Deserializer;
+
+    fn check<F: crate::EndiannessImpl>(value: &Value, name: &str) {
+        let mut encoded = Vec::new();
+        value.write_to::<F, _>(&mut encoded, name).unwrap();
+
+        let mut input = encoded.as_slice();
+        let mut de = Deserializer::<F, _>::new(&mut input).unwrap();
+        assert_eq!(de.root_name(), name);
+        assert_eq!(Value::deserialize(&mut de).unwrap(), *value);
+        drop(de);
+        assert!(input.is_empty());
+    }
+
+    let value = Value::Compound(Compound::from_iter([
+        ("name".to_owned(), Value::String("Steve".to_owned())),
+        (
+            "pos".to_owned(),
+            Value::List(vec![Value::Double(1.0), Value::Double(64.0)]),
+        ),
+        (
+            "nested".to_owned(),
+            Value::Compound(Compound::from_iter([("x".to_owned(), Value::Int(1))])),
+        ),
+    ]));
+
+    check::<BigEndian>(&value, "hello world");
+    check::<LittleEndian>(&value, "");
+    check::<NetworkLittleEndian>(&value, "BlockEntity");
+
+    let mut encoded = Vec::new();
+    value
+        .write_to::<BigEndian, _>(&mut encoded, "Data")
+        .unwrap();
+    assert_eq!(
+        encoded[..7],
+        [FieldType::Compound as u8, 0, 4, b'D', b'a', b't', b'a']
+    );
+    assert_eq!(encoded[7..], to_be_bytes(&value).unwrap()[3..]);
+
+    let err = Value::Int(5)
+        .write_to::<BigEndian, _>(&mut Vec::new(), "Data")
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        NbtError::UnexpectedType {
+            expected: FieldType::Compound,
+            actual: FieldType::Int
+        }
+    ));
+}
+
+#[test]
+fn int128() {
+    use crate::Int128;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Entity {
+        id: Int128,
+        history: Vec<Int128>,
+    }
+
+    // Root long array tag, empty name, length and both halves, most significant first.
+    let encoded = to_be_bytes(&Int128(1)).unwrap();
+    let mut expected = vec![FieldType::LongArray as u8, 0, 0, 0, 0, 0, 2];
+    expected.extend(0i64.to_be_bytes());
+    expected.extend(1i64.to_be_bytes());
+    assert_eq!(encoded, expected);
+    assert_eq!(from_be_slice::<Int128>(&encoded).unwrap(), Int128(1));
+
+    let entity = Entity {
+        id: Int128::from(u128::MAX),
+        history: vec![
+            Int128(-1),
+            Int128(i128::MIN),
+            Int128(-0x1234_5678_9abc_def0_1122),
+        ],
+    };
+    let encoded = to_be_bytes(&entity).unwrap();
+    assert_eq!(from_be_slice::<Entity>(&encoded).unwrap(), entity);
+    assert_eq!(
+        from_be_slice::<Entity>(&encoded).unwrap().id.to_u128(),
+        u128::MAX
+    );
+    let encoded = to_le_bytes(&entity).unwrap();
+    assert_eq!(
+        from_le_bytes::<Entity, _>(&mut encoded.as_slice()).unwrap(),
+        entity
+    );
+    let encoded = to_net_bytes(&entity).unwrap();
+    assert_eq!(from_net_slice::<Entity>(&encoded).unwrap(), entity);
+    assert!(validate::<NetworkLittleEndian, _>(&mut encoded.as_slice()).is_ok());
+
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new()).with_sorted_keys(true);
+    entity.serialize(&mut ser).unwrap();
+    assert_eq!(
+        from_be_slice::<Entity>(&ser.into_inner().unwrap()).unwrap(),
+        entity
+    );
+
+    // The halves are read back as a long array.
+    let value: Value = from_be_slice(&to_be_bytes(&entity).unwrap()).unwrap();
+    assert_eq!(
+        value.as_compound().unwrap()["id"],
+        Value::List(vec![Value::Long(-1), Value::Long(-1)])
+    );
+
+    // A list of two longs is accepted as well, but other lengths are not.
+    #[derive(Serialize)]
+    struct Halves {
+        id: Vec<i64>,
+    }
+    let halves = to_be_bytes(&Halves { id: vec![0, -5] }).unwrap();
+    #[derive(Deserialize, Debug)]
+    struct Id {
+        id: Int128,
+    }
+    assert_eq!(
+        from_be_slice::<Id>(&halves).unwrap().id,
+        Int128(u64::MAX as i128 - 4)
+    );
+    let three = to_be_bytes(&Halves { id: vec![0, 1, 2] }).unwrap();
+    assert!(from_be_slice::<Id>(&three).is_err());
+}
+
+#[test]
+fn uuid_wrapper() {
+    use crate::Uuid;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Player {
+        #[serde(rename = "UUID")]
+        uuid: Uuid,
+    }
+
+    // Notch's UUID, as stored in `player.dat` by Java 1.16+.
+    let player = Player {
+        uuid: Uuid(0x069a79f4_44e9_4726_a5be_fca90e38aaf5),
+    };
+    assert_eq!(
+        player.uuid.to_ints(),
+        [110787060, 1156138790, -1514210135, 238594805]
+    );
+    assert_eq!(
+        player.uuid.to_string(),
+        "069a79f4-44e9-4726-a5be-fca90e38aaf5"
+    );
+    assert_eq!(
+        crate::uuid::parse(&player.uuid.to_string()),
+        Some(player.uuid.0)
+    );
+
+    let encoded = to_be_bytes(&player).unwrap();
+    let mut expected = vec![
+        FieldType::Compound as u8,
+        0,
+        6,
+        b'P',
+        b'l',
+        b'a',
+        b'y',
+        b'e',
+        b'r',
+    ];
+    expected.extend([
+        FieldType::IntArray as u8,
+        0,
+        4,
+        b'U',
+        b'U',
+        b'I',
+        b'D',
+        0,
+        0,
+        0,
+        4,
+    ]);
+    expected.extend([
+        0x06, 0x9a, 0x79, 0xf4, 0x44, 0xe9, 0x47, 0x26, 0xa5, 0xbe, 0xfc, 0xa9, 0x0e, 0x38, 0xaa,
+        0xf5,
+    ]);
+    expected.push(FieldType::End as u8);
+    assert_eq!(encoded, expected);
+    assert_eq!(from_be_slice::<Player>(&encoded).unwrap(), player);
+
+    let value: Value = from_be_slice(&encoded).unwrap();
+    assert_eq!(
+        crate::uuid::read(value.as_compound().unwrap(), "UUID"),
+        Some(player.uuid.0)
+    );
+
+    let encoded = to_le_bytes(&player).unwrap();
+    assert_eq!(from_le_slice::<Player>(&encoded).unwrap(), player);
+    let encoded = to_net_bytes(&player).unwrap();
+    assert_eq!(from_net_slice::<Player>(&encoded).unwrap(), player);
+
+    assert_eq!(Uuid(0).to_string(), "00000000-0000-0000-0000-000000000000");
+    assert_eq!(Uuid::from_ints(Uuid(u128::MAX).to_ints()), Uuid(u128::MAX));
+}
+
+#[test]
+fn root_name_override() {
+    use crate::Serializer;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct LevelData {
+        seed: i64,
+    }
+
+    let data = LevelData { seed: 1 };
+    let encode = |name: Option<&str>| {
+        let mut ser = Serializer::<_, LittleEndian>::new(Vec::new()).with_root_name(name);
+        data.serialize(&mut ser).unwrap();
+        ser.into_inner().unwrap()
+    };
+
+    // The struct name is written by default.
+    let encoded = encode(None);
+    assert_eq!(encoded[..3], [FieldType::Compound as u8, 9, 0]);
+    assert_eq!(&encoded[3..12], b"LevelData");
+
+    let encoded = encode(Some(""));
+    assert_eq!(encoded[..3], [FieldType::Compound as u8, 0, 0]);
+    assert_eq!(encoded[3], FieldType::Long as u8);
+    assert_eq!(from_le_slice::<LevelData>(&encoded).unwrap(), data);
+
+    let encoded = encode(Some("Data"));
+    assert_eq!(
+        encoded[..7],
+        [FieldType::Compound as u8, 4, 0, b'D', b'a', b't', b'a']
+    );
+    assert_eq!(from_le_slice::<LevelData>(&encoded).unwrap(), data);
+
+    // Non-compound roots use the override as well.
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new()).with_root_name(Some("x"));
+    5i32.serialize(&mut ser).unwrap();
+    assert_eq!(
+        ser.into_inner().unwrap(),
+        [FieldType::Int as u8, 0, 1, b'x', 0, 0, 0, 5]
+    );
+}
+
+#[test]
+fn then_send() {
+    use std::io;
+
+    use crate::{to_net_bytes_in, to_net_bytes_then_send};
+
+    /// Writer that accepts a limited amount of bytes and then reports that it would block.
+    struct FailAfter {
+        written: Vec<u8>,
+        limit: usize,
+    }
+
+    impl io::Write for FailAfter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.limit - self.written.len());
+            if len == 0 && !buf.is_empty() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+
+            self.written.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Packet {
+        name: String,
+        payload: Vec<i32>,
+    }
+
+    #[derive(Serialize)]
+    struct Broken {
+        name: String,
+        unit: (),
+    }
+
+    let packet = Packet {
+        name: "Steve".to_owned(),
+        payload: vec![1, 2, 3],
+    };
+    let expected = to_net_bytes(&packet).unwrap();
+
+    // The writer fails after 4 bytes, which are left in the stream.
+    let mut writer = FailAfter {
+        written: Vec::new(),
+        limit: 4,
+    };
+    let err = to_net_bytes_in(&mut writer, &packet).unwrap_err();
+    assert!(
+        matches!(&err, NbtError::ByteError(StreamError::IoError(e)) if e.kind() == io::ErrorKind::WouldBlock),
+        "{err:?}"
+    );
+    assert_eq!(writer.written, expected[..4]);
+
+    let mut writer = FailAfter {
+        written: Vec::new(),
+        limit: 4,
+    };
+    assert!(to_net_bytes_then_send(&mut writer, &packet).is_err());
+    assert_eq!(writer.written, expected[..4]);
+
+    // A serialization error leaves part of the value in the writer, unless it is encoded first.
+    let broken = Broken {
+        name: "Steve".to_owned(),
+        unit: (),
+    };
+    let mut writer = FailAfter {
+        written: Vec::new(),
+        limit: usize::MAX,
+    };
+    assert!(to_net_bytes_in(&mut writer, &broken).is_err());
+    assert!(!writer.written.is_empty());
+
+    let mut writer = FailAfter {
+        written: Vec::new(),
+        limit: usize::MAX,
+    };
+    assert!(matches!(
+        to_net_bytes_then_send(&mut writer, &broken),
+        Err(NbtError::Unsupported(_))
+    ));
+    assert!(writer.written.is_empty());
+
+    let mut writer = FailAfter {
+        written: Vec::new(),
+        limit: usize::MAX,
+    };
+    let sent = to_net_bytes_then_send(&mut writer, &packet).unwrap();
+    assert_eq!(sent, expected);
+    assert_eq!(writer.written, expected);
+}
+
+#[test]
+fn arrays_into_vecs() {
+    use serde::Serializer;
+
+    use crate::wrapper::{INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN};
+
+    #[derive(Serialize)]
+    struct Arrays {
+        #[serde(serialize_with = "byte_array")]
+        bytes: Vec<u8>,
+        #[serde(serialize_with = "int_array")]
+        ints: Vec<i32>,
+        #[serde(serialize_with = "long_array")]
+        longs: Vec<i64>,
+    }
+
+    fn byte_array<S: Serializer>(v: &[u8], ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_bytes(v)
+    }
+
+    fn int_array<S: Serializer>(v: &[i32], ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_newtype_struct(INT_ARRAY_TOKEN, v)
+    }
+
+    fn long_array<S: Serializer>(v: &[i64], ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_newtype_struct(LONG_ARRAY_TOKEN, v)
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Vecs {
+        bytes: Vec<u8>,
+        ints: Vec<i32>,
+        longs: Vec<i64>,
+    }
+
+    let arrays = Arrays {
+        bytes: vec![0, 1, 127, 128, 255],
+        ints: vec![i32::MIN, -1, 0, 1, i32::MAX],
+        longs: vec![i64::MIN, 0, i64::MAX],
+    };
+    let expected = Vecs {
+        bytes: arrays.bytes.clone(),
+        ints: arrays.ints.clone(),
+        longs: arrays.longs.clone(),
+    };
+
+    let encoded = to_be_bytes(&arrays).unwrap();
+    assert_eq!(from_be_slice::<Vecs>(&encoded).unwrap(), expected);
+    assert_eq!(
+        from_be_bytes::<Vecs, _>(&mut encoded.as_slice()).unwrap(),
+        expected
+    );
+
+    let encoded = to_le_bytes(&arrays).unwrap();
+    assert_eq!(from_le_slice::<Vecs>(&encoded).unwrap(), expected);
+    assert_eq!(
+        from_le_bytes::<Vecs, _>(&mut encoded.as_slice()).unwrap(),
+        expected
+    );
+
+    let encoded = to_net_bytes(&arrays).unwrap();
+    assert_eq!(from_net_slice::<Vecs>(&encoded).unwrap(), expected);
+
+    // Elements can be widened, but not narrowed if they do not fit.
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Wide {
+        bytes: Vec<i16>,
+        ints: Vec<i64>,
+    }
+
+    let arrays = Arrays {
+        bytes: vec![1, 255],
+        ints: vec![-1],
+        longs: vec![],
+    };
+    let encoded = to_be_bytes(&arrays).unwrap();
+    assert_eq!(
+        from_be_slice::<Wide>(&encoded).unwrap(),
+        Wide {
+            bytes: vec![1, -1],
+            ints: vec![-1],
+        }
+    );
+
+    #[derive(Deserialize, Debug)]
+    struct Narrow {
+        #[allow(dead_code)]
+        ints: Vec<u8>,
+    }
+    assert!(from_be_slice::<Narrow>(&encoded).is_err());
+
+    // Fixed-size arrays check the length.
+    #[derive(Deserialize, Debug)]
+    struct Fixed {
+        #[allow(dead_code)]
+        bytes: [u8; 3],
+    }
+    assert!(from_be_slice::<Fixed>(&encoded).is_err());
+
+    // The declared length cannot exceed the input.
+    let mut truncated = encoded.clone();
+    truncated.truncate(20);
+    assert!(from_be_slice::<Vecs>(&truncated).is_err());
+}
+
+#[test]
+fn value_deserialize_into() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Item {
+        id: String,
+        count: i8,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Player {
+        name: String,
+        health: f32,
+        flying: bool,
+        pos: Vec<f64>,
+        inventory: Vec<Item>,
+        seed: Option<i64>,
+        spawn: Option<i32>,
+        data: Vec<u8>,
+        heights: Vec<i32>,
+    }
+
+    let item = |id: &str, count| {
+        Value::Compound(Compound::from_iter([
+            ("id".to_owned(), Value::String(id.to_owned())),
+            ("count".to_owned(), Value::Byte(count)),
+        ]))
+    };
+    let value = Value::Compound(Compound::from_iter([
+        ("name".to_owned(), Value::String("Steve".to_owned())),
+        ("health".to_owned(), Value::Float(20.0)),
+        ("flying".to_owned(), Value::Byte(1)),
+        (
+            "pos".to_owned(),
+            Value::List(vec![Value::Double(0.5), Value::Double(64.0)]),
+        ),
+        (
+            "inventory".to_owned(),
+            Value::List(vec![item("minecraft:stone", 64), item("minecraft:dirt", 1)]),
+        ),
+        ("seed".to_owned(), Value::Long(-5)),
+        ("data".to_owned(), Value::ByteArray(vec![0, 255])),
+        ("heights".to_owned(), Value::IntArray(vec![64, 65])),
+        ("unknown".to_owned(), Value::Int(1)),
+    ]));
+
+    let player: Player = value.deserialize_into().unwrap();
+    assert_eq!(
+        player,
+        Player {
+            name: "Steve".to_owned(),
+            health: 20.0,
+            flying: true,
+            pos: vec![0.5, 64.0],
+            inventory: vec![
+                Item {
+                    id: "minecraft:stone".to_owned(),
+                    count: 64
+                },
+                Item {
+                    id: "minecraft:dirt".to_owned(),
+                    count: 1
+                },
+            ],
+            seed: Some(-5),
+            spawn: None,
+            data: vec![0, 255],
+            heights: vec![64, 65],
+        }
+    );
+
+    // Extracting a subtree, and the same value through the encoded form.
+    let inventory = &value.as_compound().unwrap()["inventory"];
+    let items: Vec<Item> = inventory.deserialize_into().unwrap();
+    assert_eq!(items, player.inventory);
+    assert_eq!(inventory.deserialize_into::<Value>().unwrap(), *inventory);
+
+    // Types are checked.
+    assert!(Value::Int(1).deserialize_into::<String>().is_err());
+    assert!(Value::Int(1).deserialize_into::<Item>().is_err());
+    assert!(matches!(
+        item("a", 1).deserialize_into::<Player>(),
+        Err(NbtError::MissingField(_))
+    ));
+}
+
+#[test]
+fn to_value_round_trip() {
+    use crate::{to_value, Int128, Uuid};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Item {
+        id: String,
+        count: i8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Player {
+        name: String,
+        level: i16,
+        health: f32,
+        flying: bool,
+        pos: Vec<f64>,
+        inventory: Vec<Item>,
+        spawn: Option<i32>,
+        attributes: HashMap<String, f64>,
+    }
+
+    let player = Player {
+        name: "Steve".to_owned(),
+        level: 30,
+        health: 20.0,
+        flying: true,
+        pos: vec![0.5, 64.0, -3.5],
+        inventory: vec![Item {
+            id: "minecraft:stone".to_owned(),
+            count: 64,
+        }],
+        spawn: None,
+        attributes: HashMap::from([("speed".to_owned(), 0.1)]),
+    };
+
+    let value = to_value(&player).unwrap();
+    let compound = value.as_compound().unwrap();
+    assert_eq!(compound["level"], Value::Short(30));
+    assert_eq!(compound["flying"], Value::Byte(1));
+    assert!(!compound.contains_key("spawn"));
+    assert_eq!(
+        compound["inventory"],
+        Value::List(vec![Value::Compound(Compound::from_iter([
+            ("id".to_owned(), Value::String("minecraft:stone".to_owned())),
+            ("count".to_owned(), Value::Byte(64)),
+        ]))])
+    );
+
+    // The same value is produced by encoding and decoding.
+    assert_eq!(
+        value,
+        from_be_slice::<Value>(&to_be_bytes(&player).unwrap()).unwrap()
+    );
+    assert_eq!(value.deserialize_into::<Player>().unwrap(), player);
+
+    // Some values are written as the value they contain.
+    let player = Player {
+        spawn: Some(5),
+        ..player
+    };
+    let value = to_value(&player).unwrap();
+    assert_eq!(value.as_compound().unwrap()["spawn"], Value::Int(5));
+    assert_eq!(value.deserialize_into::<Player>().unwrap(), player);
+
+    // Wrappers produce arrays.
+    assert_eq!(
+        to_value(&Uuid::from_ints([1, 2, 3, 4])).unwrap(),
+        Value::IntArray(vec![1, 2, 3, 4])
+    );
+    assert_eq!(
+        to_value(&Int128(-1)).unwrap(),
+        Value::LongArray(vec![-1, -1])
+    );
+
+    // Unsupported types fail as they do when serializing.
+    assert!(matches!(to_value(&1u32), Err(NbtError::Unsupported(_))));
+    assert!(matches!(
+        to_value(&None::<i32>),
+        Err(NbtError::Unsupported(_))
+    ));
+    assert!(matches!(
+        to_value(&HashMap::from([((1, 2), 3)])),
+        Err(NbtError::NonStringKey(_))
+    ));
+}
+
+#[test]
+fn sequence_length_limits() {
+    use serde::ser::SerializeSeq;
+
+    /// Sequence that declares more elements than fit in a length prefix.
+    struct Huge;
+
+    impl Serialize for Huge {
+        fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+            let mut seq = ser.serialize_seq(Some(i32::MAX as usize + 1))?;
+            seq.serialize_element(&1i32)?;
+            seq.end()
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Holder {
+        list: Huge,
+    }
+
+    let err = to_be_bytes(&Holder { list: Huge }).unwrap_err();
+    assert!(
+        matches!(err, NbtError::LengthTooLarge { len } if len == i32::MAX as usize + 1),
+        "{err:?}"
+    );
+    assert!(matches!(
+        to_net_bytes(&Huge),
+        Err(NbtError::LengthTooLarge { .. })
+    ));
+
+    // A list of ints with a length of -1.
+    let mut data = vec![
+        FieldType::Compound as u8,
+        0,
+        0,
+        FieldType::List as u8,
+        0,
+        1,
+        b'a',
+    ];
+    data.push(FieldType::Int as u8);
+    data.extend((-1i32).to_be_bytes());
+    data.push(FieldType::End as u8);
+
+    let err = from_be_slice::<Value>(&data).unwrap_err();
+    assert!(
+        matches!(err.kind(), NbtError::NegativeLength { len: -1 }),
+        "{err:?}"
+    );
+    assert!(matches!(
+        crate::validate::<BigEndian, _>(&mut data.as_slice())
+            .unwrap_err()
+            .kind(),
+        NbtError::NegativeLength { len: -1 }
+    ));
+
+    // Byte arrays are checked as well, here in the network format where -1 is zigzag encoded as 1.
+    let data = [
+        FieldType::Compound as u8,
+        0,
+        FieldType::ByteArray as u8,
+        1,
+        b'a',
+        1,
+        FieldType::End as u8,
+    ];
+    let err = from_net_slice::<Value>(&data).unwrap_err();
+    assert!(
+        matches!(err.kind(), NbtError::NegativeLength { len: -1 }),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn field_type_names() {
+    let names = [
+        "TAG_End",
+        "TAG_Byte",
+        "TAG_Short",
+        "TAG_Int",
+        "TAG_Long",
+        "TAG_Float",
+        "TAG_Double",
+        "TAG_Byte_Array",
+        "TAG_String",
+        "TAG_List",
+        "TAG_Compound",
+        "TAG_Int_Array",
+        "TAG_Long_Array",
+    ];
+
+    for (byte, name) in (0u8..).zip(names) {
+        let ty = FieldType::from_u8(byte).unwrap();
+        assert_eq!(ty as u8, byte);
+        assert_eq!(u8::from(ty), byte);
+        assert_eq!(ty.as_str(), name);
+        assert_eq!(ty.to_string(), name);
+        assert_eq!(FieldType::try_from(byte).unwrap(), ty);
+    }
+
+    for byte in 13..=u8::MAX {
+        assert_eq!(FieldType::from_u8(byte), None);
+        assert!(matches!(
+            FieldType::try_from(byte),
+            Err(NbtError::UnknownTag(b)) if b == byte
+        ));
+    }
+}
+
+#[test]
+fn list_writer() {
+    use crate::Serializer;
+
+    #[derive(Serialize)]
+    struct Item {
+        id: String,
+    }
+
+    // Ints are written the same as through `serialize_seq`.
+    let ints: Vec<i32> = (0..10_000).collect();
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new());
+    let mut list = ser.begin_list(FieldType::Int, ints.len()).unwrap();
+    for i in &ints {
+        list.write_element(i).unwrap();
+    }
+    list.end_list().unwrap();
+    assert_eq!(ser.into_inner().unwrap(), to_be_bytes(&ints).unwrap());
+
+    // Compound elements in the network format.
+    let mut ser = Serializer::<_, NetworkLittleEndian>::new(Vec::new());
+    let mut list = ser.begin_list(FieldType::Compound, 2).unwrap();
+    for id in ["a", "b"] {
+        list.write_element(&Item { id: id.to_owned() }).unwrap();
+    }
+    list.end_list().unwrap();
+    let value: Value = from_net_slice(&ser.into_inner().unwrap()).unwrap();
+    assert_eq!(value.as_list().unwrap().len(), 2);
+
+    // Empty lists keep their element type.
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new());
+    ser.begin_list(FieldType::Long, 0)
+        .unwrap()
+        .end_list()
+        .unwrap();
+    assert_eq!(
+        ser.into_inner().unwrap(),
+        [
+            FieldType::List as u8,
+            0,
+            0,
+            FieldType::Long as u8,
+            0,
+            0,
+            0,
+            0
+        ]
+    );
+
+    // The first element must match the declared type.
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new());
+    let mut list = ser.begin_list(FieldType::Int, 1).unwrap();
+    assert!(matches!(
+        list.write_element(&1i64),
+        Err(NbtError::UnexpectedType {
+            expected: FieldType::Int,
+            actual: FieldType::Long
+        })
+    ));
+
+    // The amount of elements must match the declared length.
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new());
+    let mut list = ser.begin_list(FieldType::Int, 1).unwrap();
+    list.write_element(&1).unwrap();
+    assert!(matches!(
+        list.write_element(&2),
+        Err(NbtError::LengthMismatch {
+            declared: 1,
+            actual: 2
+        })
+    ));
+
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new());
+    let list = ser.begin_list(FieldType::Int, 2).unwrap();
+    assert!(matches!(
+        list.end_list(),
+        Err(NbtError::LengthMismatch {
+            declared: 2,
+            actual: 0
+        })
+    ));
+
+    // Elements of a list that is dropped without being finished are still written to the
+    // writer, both when the serializer is reset and when the writer is taken out of it, but
+    // taking out the writer reports the unfinished list.
+    let mut buf = Vec::new();
+    let mut ser = Serializer::<_, BigEndian>::new(&mut buf);
+    let mut list = ser.begin_list(FieldType::Int, 3).unwrap();
+    list.write_element(&1).unwrap();
+    ser.reset().unwrap();
+    let mut list = ser.begin_list(FieldType::Int, 3).unwrap();
+    list.write_element(&2).unwrap();
+    assert!(matches!(
+        ser.flush(),
+        Err(NbtError::UnfinishedList { open: 1 })
+    ));
+    assert!(matches!(
+        ser.into_inner(),
+        Err(NbtError::UnfinishedList { open: 1 })
+    ));
+
+    let header = [
+        FieldType::List as u8,
+        0,
+        0,
+        FieldType::Int as u8,
+        0,
+        0,
+        0,
+        3,
+    ];
+    assert_eq!(
+        buf,
+        [&header[..], &[0, 0, 0, 1], &header, &[0, 0, 0, 2]].concat()
+    );
+
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new());
+    assert!(matches!(
+        ser.begin_list(FieldType::End, 1),
+        Err(NbtError::InvalidListType { len: 1 })
+    ));
+    // Lengths that do not fit in the prefix are reported as they are, without truncation.
+    let len = u32::MAX as usize + 2;
+    assert!(matches!(
+        ser.begin_list(FieldType::End, len),
+        Err(NbtError::LengthTooLarge { len: l }) if l == len
+    ));
+}
+
+#[test]
+fn duplicate_key_policy() {
+    use crate::{Deserializer, DuplicateKeyPolicy};
+
+    // `{a: 1b, list: [1b], a: {b: 2b}, c: 3b, a: 4b}`
+    let mut data = vec![FieldType::Compound as u8, 0, 0];
+    data.extend([FieldType::Byte as u8, 0, 1, b'a', 1]);
+    data.extend([FieldType::List as u8, 0, 4, b'l', b'i', b's', b't']);
+    data.extend([FieldType::Byte as u8, 0, 0, 0, 1, 1]);
+    data.extend([FieldType::Compound as u8, 0, 1, b'a']);
+    data.extend([FieldType::Byte as u8, 0, 1, b'b', 2, FieldType::End as u8]);
+    data.extend([FieldType::Byte as u8, 0, 1, b'c', 3]);
+    data.extend([FieldType::Byte as u8, 0, 1, b'a', 4]);
+    data.push(FieldType::End as u8);
+
+    let read = |policy| {
+        let mut input = data.as_slice();
+        let mut de = Deserializer::<BigEndian, _>::new(&mut input)
+            .unwrap()
+            .with_duplicate_key_policy(policy);
+        Value::deserialize(&mut de)
+    };
+
+    let value = read(DuplicateKeyPolicy::Last).unwrap();
+    assert_eq!(value.as_compound().unwrap()["a"], Value::Byte(4));
+    assert_eq!(value, from_be_slice::<Value>(&data).unwrap());
+
+    // The compound that is skipped for `First` is consumed entirely.
+    let value = read(DuplicateKeyPolicy::First).unwrap();
+    let compound = value.as_compound().unwrap();
+    assert_eq!(compound["a"], Value::Byte(1));
+    assert_eq!(compound["c"], Value::Byte(3));
+    assert_eq!(compound.len(), 3);
+
+    let err = read(DuplicateKeyPolicy::Error).unwrap_err();
+    assert!(
+        matches!(err.kind(), NbtError::DuplicateKey(key) if key == "a"),
+        "{err:?}"
+    );
+
+    // The policy applies to maps and nested compounds as well.
+    let mut input = data.as_slice();
+    let mut de = Deserializer::<BigEndian, _>::new(&mut input)
+        .unwrap()
+        .with_duplicate_key_policy(DuplicateKeyPolicy::First);
+    let map = HashMap::<String, Value>::deserialize(&mut de).unwrap();
+    assert_eq!(map["a"], Value::Byte(1));
+
+    let nested = Value::Compound(Compound::from_iter([(
+        "inner".to_owned(),
+        Value::Compound(Compound::from_iter([("x".to_owned(), Value::Int(1))])),
+    )]));
+    let mut encoded = to_be_bytes(&nested).unwrap();
+    // Append a second `x` to the inner compound, before its end tag.
+    let end = encoded.len() - 2;
+    encoded.splice(end..end, [FieldType::Int as u8, 0, 1, b'x', 0, 0, 0, 2]);
+
+    let mut input = encoded.as_slice();
+    let mut de = Deserializer::<BigEndian, _>::new(&mut input)
+        .unwrap()
+        .with_duplicate_key_policy(DuplicateKeyPolicy::Error);
+    let err = Value::deserialize(&mut de).unwrap_err();
+    let err = de.locate(err);
+    assert_eq!(err.path(), Some("inner"));
+    assert!(matches!(err.kind(), NbtError::DuplicateKey(key) if key == "x"));
+}
+
+#[test]
+fn network_root_name() {
+    use crate::{Deserializer, Serializer};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Sign {
+        id: String,
+        x: i32,
+        #[serde(rename = "IsWaxed")]
+        is_waxed: bool,
+    }
+
+    let sign = Sign {
+        id: "Sign".to_owned(),
+        x: -3,
+        is_waxed: true,
+    };
+
+    // A minimal sign in the layout of the block actor data in a Bedrock `BlockActorData` packet,
+    // with an empty root name. These bytes were assembled by hand, not expected from a client.
+    #[rustfmt::skip]
+    let expected = [
+        0x0a, 0x00,
+        0x08, 0x02, b'i', b'd', 0x04, b'S', b'i', b'g', b'n',
+        0x03, 0x01, b'x', 0x05,
+        0x01, 0x07, b'I', b's', b'W', b'a', b'x', b'e', b'd', 0x01,
+        0x00,
+    ];
+
+    let mut ser = Serializer::<_, NetworkLittleEndian>::new(Vec::new()).with_root_name(Some(""));
+    sign.serialize(&mut ser).unwrap();
+    assert_eq!(ser.into_inner().unwrap(), expected);
+    assert_eq!(from_net_slice::<Sign>(&expected).unwrap(), sign);
+
+    // The root name uses the same varint length as keys and strings, so names of 128 bytes or
+    // more take two bytes.
+    let name = "n".repeat(200);
+    let mut ser = Serializer::<_, NetworkLittleEndian>::new(Vec::new()).with_root_name(Some(&name));
+    sign.serialize(&mut ser).unwrap();
+    let encoded = ser.into_inner().unwrap();
+    assert_eq!(encoded[..3], [0x0a, 0xc8, 0x01]);
+    assert_eq!(encoded[3 + 200..], expected[2..]);
+
+    let mut input = encoded.as_slice();
+    let mut de = Deserializer::<NetworkLittleEndian, _>::new(&mut input).unwrap();
+    assert_eq!(de.root_name(), name);
+    assert_eq!(Sign::deserialize(&mut de).unwrap(), sign);
+
+    // Without an override, the struct name is written.
+    let encoded = to_net_bytes(&sign).unwrap();
+    assert_eq!(encoded[..7], [0x0a, 0x04, b'S', b'i', b'g', b'n', 0x08]);
+}
+
+#[test]
+fn value_equality() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash(value: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let nested = |a: f64| {
+        Value::List(vec![Value::Compound(Compound::from_iter([(
+            "pos".to_owned(),
+            Value::List(vec![Value::Double(a), Value::Double(64.0)]),
+        )]))])
+    };
+    assert_eq!(nested(1.5), nested(1.5));
+    assert_ne!(nested(1.5), nested(2.5));
+    assert_ne!(Value::Int(1), Value::Long(1));
+
+    // Compounds are equal and hash the same regardless of the order of their keys.
+    let mut forward = Compound::default();
+    let mut backward = Compound::default();
+    for i in 0..16 {
+        forward.insert(format!("key{i}"), Value::Int(i));
+        backward.insert(format!("key{}", 15 - i), Value::Int(15 - i));
+    }
+    let (forward, backward) = (Value::Compound(forward), Value::Compound(backward));
+    assert_eq!(forward, backward);
+    assert_eq!(hash(&forward), hash(&backward));
+
+    // Floats use IEEE equality.
+    assert_ne!(Value::Float(f32::NAN), Value::Float(f32::NAN));
+    assert_ne!(nested(f64::NAN), nested(f64::NAN));
+    assert_eq!(Value::Double(0.0), Value::Double(-0.0));
+    assert_eq!(hash(&Value::Double(0.0)), hash(&Value::Double(-0.0)));
+
+    assert!(nested(0.1 + 0.2).approx_eq(&nested(0.3), 1e-9));
+    assert!(!nested(0.1 + 0.2).approx_eq(&nested(0.3), 0.0));
+    assert!(nested(f64::NAN).approx_eq(&nested(f64::NAN), 0.0));
+    assert!(Value::Float(1.0).approx_eq(&Value::Float(1.05), 0.1));
+    assert!(!Value::Float(1.0).approx_eq(&Value::Double(1.0), 0.1));
+    assert!(!forward.approx_eq(&Value::Compound(Compound::default()), 0.1));
+    assert!(forward.approx_eq(&backward, 0.0));
+}
+
+#[test]
+fn value_total_order() {
+    let compound = |entries: &[(&str, i32)]| {
+        Value::Compound(
+            entries
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), Value::Int(*v)))
+                .collect(),
+        )
+    };
+
+    let mut values = vec![
+        Value::LongArray(vec![1]),
+        compound(&[("b", 1), ("a", 2)]),
+        Value::List(vec![Value::Int(1), Value::Int(2)]),
+        Value::String("b".to_owned()),
+        Value::Double(f64::NAN),
+        Value::Double(0.0),
+        Value::Float(1.0),
+        Value::Long(-1),
+        Value::Int(3),
+        Value::Short(0),
+        Value::Byte(7),
+        Value::Double(-0.0),
+        Value::Double(f64::NEG_INFINITY),
+        Value::String("a".to_owned()),
+        Value::List(vec![Value::Int(1)]),
+        compound(&[("a", 1), ("c", 0)]),
+        Value::IntArray(vec![2]),
+        Value::IntArray(vec![1, 5]),
+        Value::ByteArray(vec![0]),
+        Value::Int(-3),
+    ];
+    values.sort_by(Value::total_cmp);
+
+    let expected = [
+        "7b",
+        "0s",
+        "-3",
+        "3",
+        "-1L",
+        "1.0f",
+        "-infd",
+        "-0.0d",
+        "0.0d",
+        "NaNd",
+        "[B;0b]",
+        "\"a\"",
+        "\"b\"",
+        "[1]",
+        "[1,2]",
+        // Entries are compared in key order, so `a:1` decides before `b` and `c` are looked at.
+        "{a:1,c:0}",
+        "{a:2,b:1}",
+        "[I;1,5]",
+        "[I;2]",
+        "[L;1L]",
+    ];
+    let actual = values.iter().map(Value::to_snbt).collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+
+    // Unlike `PartialEq`, identical NaNs are equal and the signs of zero are not.
+    use std::cmp::Ordering;
+    let nan = Value::Float(f32::NAN);
+    assert_eq!(nan.total_cmp(&nan), Ordering::Equal);
+    assert_eq!(
+        Value::Float(-0.0).total_cmp(&Value::Float(0.0)),
+        Ordering::Less
+    );
+}
+
+#[test]
+fn collect_str() {
+    use std::fmt;
+
+    struct Version(u8, u8);
+
+    impl fmt::Display for Version {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}.{}", self.0, self.1)
+        }
+    }
+
+    impl Serialize for Version {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Pack {
+        version: Version,
+        supported: Vec<Version>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(rename = "Pack")]
+    struct Strings {
+        version: String,
+        supported: Vec<String>,
+    }
+
+    let pack = Pack {
+        version: Version(1, 21),
+        supported: vec![Version(1, 20), Version(1, 9), Version(12, 100)],
+    };
+    let expected = Strings {
+        version: "1.21".to_owned(),
+        supported: vec!["1.20".to_owned(), "1.9".to_owned(), "12.100".to_owned()],
+    };
+
+    let encoded = to_be_bytes(&pack).unwrap();
+    assert_eq!(encoded, to_be_bytes(&expected).unwrap());
+    assert_eq!(from_be_slice::<Strings>(&encoded).unwrap(), expected);
+
+    let encoded = to_net_bytes(&pack).unwrap();
+    assert_eq!(from_net_slice::<Strings>(&encoded).unwrap(), expected);
+}
+
+#[test]
+fn allowed_tags() {
+    use crate::{to_value, Deserializer, TagMask};
+
+    fn deserialize<'a, T: Deserialize<'a>>(data: &'a [u8], mask: TagMask) -> Result<T, NbtError> {
+        let mut input = data;
+        let mut de = Deserializer::<BigEndian, _>::new(&mut input)?.with_allowed_tags(mask);
+        T::deserialize(&mut de).map_err(|e| de.locate(e))
+    }
+
+    // `Int128` is written as a long array, three compounds below the root.
+    let leaf = HashMap::from([("data", crate::Int128(-1))]);
+    let tree = HashMap::from([("level", HashMap::from([("sections", vec![leaf])]))]);
+    let encoded = to_be_bytes(&tree).unwrap();
+    assert!(deserialize::<Value>(&encoded, TagMask::ALL).is_ok());
+
+    let mask = TagMask::ALL.without(FieldType::LongArray);
+    let err = deserialize::<Value>(&encoded, mask).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::ForbiddenTag(FieldType::LongArray)
+    ));
+    assert_eq!(err.path(), Some("level.sections[0]"));
+
+    // Values that are skipped are checked as well.
+    #[derive(Deserialize, Debug)]
+    struct Empty {}
+
+    let err = deserialize::<Empty>(&encoded, mask).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::ForbiddenTag(FieldType::LongArray)
+    ));
+
+    let leaf = HashMap::from([("data", 3)]);
+    let tree = HashMap::from([("level", HashMap::from([("sections", vec![leaf])]))]);
+    let encoded = to_be_bytes(&tree).unwrap();
+
+    let mask = TagMask::from([FieldType::Compound, FieldType::List, FieldType::Int]);
+    assert_eq!(
+        deserialize::<Value>(&encoded, mask).unwrap(),
+        to_value(&tree).unwrap()
+    );
+
+    let err = deserialize::<Value>(&encoded, mask.without(FieldType::List)).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::ForbiddenTag(FieldType::List)
+    ));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_round_trip() {
+    use crate::{from_net_bytes_async, to_net_bytes_async};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Chunk {
+        name: String,
+        // Large enough to take multiple reads.
+        blocks: Vec<i32>,
+    }
+
+    let chunk = Chunk {
+        name: "overworld".to_owned(),
+        blocks: (0..10_000).collect(),
+    };
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let mut socket = Vec::new();
+        to_net_bytes_async(&mut socket, &chunk).await.unwrap();
+        assert_eq!(socket, to_net_bytes(&chunk).unwrap());
+
+        let mut reader = socket.as_slice();
+        let decoded: Chunk = from_net_bytes_async(&mut reader).await.unwrap();
+        assert_eq!(decoded, chunk);
+
+        let mut truncated = &socket[..socket.len() / 2];
+        assert!(from_net_bytes_async::<Chunk, _>(&mut truncated)
+            .await
+            .is_err());
+    });
+}
+
+#[test]
+fn int_and_long_arrays() {
+    use crate::{from_slice, IntArray, LongArray};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Section {
+        size: IntArray,
+        block_states: LongArray,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(rename = "Section")]
+    struct Lists {
+        size: Vec<i32>,
+        block_states: Vec<i64>,
+    }
+
+    let section = Section {
+        size: IntArray(vec![16, -8, i32::MAX]),
+        block_states: LongArray((0..300).map(|i| i * 0x0101_0101_0101 - 7).collect()),
+    };
+
+    fn round_trip<F: crate::EndiannessImpl>(section: &Section) {
+        let encoded = to_bytes::<F>(section).unwrap();
+        assert_eq!(from_slice::<F, Section>(&encoded).unwrap(), *section);
+        assert_eq!(
+            crate::from_bytes::<F, Section>(&mut encoded.as_slice()).unwrap(),
+            *section
+        );
+
+        // Lists are accepted as well.
+        let lists = Lists {
+            size: section.size.0.clone(),
+            block_states: section.block_states.0.clone(),
+        };
+        let encoded = to_bytes::<F>(&lists).unwrap();
+        assert_eq!(from_slice::<F, Section>(&encoded).unwrap(), *section);
+    }
+
+    round_trip::<LittleEndian>(&section);
+    round_trip::<BigEndian>(&section);
+    round_trip::<NetworkLittleEndian>(&section);
+
+    // The first entry follows the root tag and the root name `Section`.
+    let encoded = to_le_bytes(&section).unwrap();
+    assert_eq!(encoded[3 + 7], FieldType::IntArray as u8);
+
+    let value = from_le_slice::<Value>(&encoded).unwrap();
+    assert_eq!(value.deserialize_into::<Section>().unwrap(), section);
+}
+
+#[test]
+fn string_len_width() {
+    use crate::{Deserializer, EndiannessImpl, StringLenWidth};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Sign {
+        text: String,
+        color: i8,
+    }
+
+    fn encode<F: EndiannessImpl, T: Serialize>(
+        v: &T,
+        width: StringLenWidth,
+    ) -> Result<Vec<u8>, NbtError> {
+        let mut ser = Serializer::<_, F>::new(Vec::new()).with_string_len_width(width);
+        v.serialize(&mut ser)?;
+        ser.into_inner()
+    }
+
+    fn decode<F: EndiannessImpl>(mut data: &[u8], width: StringLenWidth) -> Sign {
+        let mut de = Deserializer::<F, _>::new_with_string_len_width(&mut data, width).unwrap();
+        Sign::deserialize(&mut de).unwrap()
+    }
+
+    let sign = Sign {
+        text: "Hello".to_owned(),
+        color: 3,
+    };
+
+    for width in [StringLenWidth::U8, StringLenWidth::U16, StringLenWidth::U32] {
+        let encoded = encode::<BigEndian, _>(&sign, width).unwrap();
+        assert_eq!(decode::<BigEndian>(&encoded, width), sign);
+
+        let encoded = encode::<LittleEndian, _>(&sign, width).unwrap();
+        assert_eq!(decode::<LittleEndian>(&encoded, width), sign);
+
+        // The network variant always uses varints.
+        let encoded = encode::<NetworkLittleEndian, _>(&sign, width).unwrap();
+        assert_eq!(encoded, to_net_bytes(&sign).unwrap());
+        assert_eq!(decode::<NetworkLittleEndian>(&encoded, width), sign);
+    }
+
+    // The root name `Sign` followed by the key `text`.
+    let encoded = encode::<BigEndian, _>(&sign, StringLenWidth::U8).unwrap();
+    assert_eq!(encoded[..8], [0x0a, 4, b'S', b'i', b'g', b'n', 0x08, 4]);
+    let encoded = encode::<BigEndian, _>(&sign, StringLenWidth::U32).unwrap();
+    assert_eq!(encoded[..6], [0x0a, 0, 0, 0, 4, b'S']);
+    let encoded = encode::<LittleEndian, _>(&sign, StringLenWidth::U32).unwrap();
+    assert_eq!(encoded[..6], [0x0a, 4, 0, 0, 0, b'S']);
+
+    // The default is unchanged.
+    let encoded = encode::<BigEndian, _>(&sign, StringLenWidth::default()).unwrap();
+    assert_eq!(encoded, to_be_bytes(&sign).unwrap());
+
+    let long = Sign {
+        text: "a".repeat(256),
+        color: 0,
+    };
+    let err = encode::<BigEndian, _>(&long, StringLenWidth::U8).unwrap_err();
+    assert!(matches!(
+        err,
+        NbtError::StringTooLong { len: 256, max: 255 }
+    ));
+
+    let encoded = encode::<LittleEndian, _>(&long, StringLenWidth::U32).unwrap();
+    assert_eq!(decode::<LittleEndian>(&encoded, StringLenWidth::U32), long);
+}
+
+#[test]
+fn byte_array_len_width() {
+    use crate::{ByteArrayLenWidth, Deserializer, EndiannessImpl};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Version {
+        version: i32,
+    }
+
+    fn encode<F: EndiannessImpl>(v: &Value, width: ByteArrayLenWidth) -> Result<Vec<u8>, NbtError> {
+        let mut ser = Serializer::<_, F>::new(Vec::new()).with_byte_array_len_width(width);
+        v.serialize(&mut ser)?;
+        ser.into_inner()
+    }
+
+    fn decode<'a, F: EndiannessImpl + 'a, T: Deserialize<'a>>(
+        mut data: &'a [u8],
+        width: ByteArrayLenWidth,
+    ) -> T {
+        let mut de = Deserializer::<F, _>::new(&mut data)
+            .unwrap()
+            .with_byte_array_len_width(width);
+        T::deserialize(&mut de).unwrap()
+    }
+
+    let value = Value::Compound(Compound::from_iter([
+        ("data".to_owned(), Value::ByteArray(vec![1, 2, 3])),
+        ("version".to_owned(), Value::Int(4)),
+    ]));
+
+    for width in [ByteArrayLenWidth::I16, ByteArrayLenWidth::I32] {
+        let encoded = encode::<BigEndian>(&value, width).unwrap();
+        assert_eq!(decode::<BigEndian, Value>(&encoded, width), value);
+        // Skipped byte arrays use the same prefix.
+        assert_eq!(
+            decode::<BigEndian, Version>(&encoded, width),
+            Version { version: 4 }
+        );
+
+        let encoded = encode::<LittleEndian>(&value, width).unwrap();
+        assert_eq!(decode::<LittleEndian, Value>(&encoded, width), value);
+
+        // The network variant always uses varints.
+        let encoded = encode::<NetworkLittleEndian>(&value, width).unwrap();
+        assert_eq!(encoded, to_net_bytes(&value).unwrap());
+        assert_eq!(decode::<NetworkLittleEndian, Value>(&encoded, width), value);
+    }
+
+    let array = Value::ByteArray(vec![5; 2]);
+    let encoded = encode::<BigEndian>(&array, ByteArrayLenWidth::I16).unwrap();
+    assert_eq!(encoded, [7, 0, 0, 0, 2, 5, 5]);
+    let encoded = encode::<LittleEndian>(&array, ByteArrayLenWidth::I16).unwrap();
+    assert_eq!(encoded, [7, 0, 0, 2, 0, 5, 5]);
+
+    // The default is unchanged.
+    let encoded = encode::<BigEndian>(&value, ByteArrayLenWidth::default()).unwrap();
+    assert_eq!(encoded, to_be_bytes(&value).unwrap());
+
+    let long = Value::ByteArray(vec![0; 32768]);
+    let err = encode::<BigEndian>(&long, ByteArrayLenWidth::I16).unwrap_err();
+    assert!(matches!(
+        err,
+        NbtError::ByteArrayTooLong {
+            len: 32768,
+            max: 32767
+        }
+    ));
+
+    // A negative short is rejected like any other negative length.
+    let mut input: &[u8] = &[7, 0, 0, 0xff, 0xff];
+    let err = Value::deserialize(
+        &mut Deserializer::<BigEndian, _>::new(&mut input)
+            .unwrap()
+            .with_byte_array_len_width(ByteArrayLenWidth::I16),
+    )
+    .unwrap_err();
+    assert!(matches!(err.kind(), NbtError::NegativeLength { len: -1 }));
+}
+
+#[test]
+fn max_list_len() {
+    use crate::Deserializer;
+
+    // A list of `i32::MAX` empty compounds, which is the longest length that can be declared.
+    // A length of `u32::MAX` is read as -1 and already fails with `NegativeLength`.
+    let mut encoded = vec![0x0a, 0, 0, 0x09, 0, 1, b'l', 0x0a];
+    encoded.extend_from_slice(&i32::MAX.to_be_bytes());
+    encoded.extend_from_slice(&[0; 16]);
+
+    let mut input = encoded.as_slice();
+    let mut de = Deserializer::<BigEndian, _>::new(&mut input)
+        .unwrap()
+        .with_max_list_len(1024);
+    let err = Value::deserialize(&mut de)
+        .map_err(|e| de.locate(e))
+        .unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::ListTooLong {
+            declared: 0x7fff_ffff,
+            max: 1024
+        }
+    ));
+    assert_eq!(err.path(), Some("l"));
+    drop(de);
+
+    encoded[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+    let mut input = encoded.as_slice();
+    let mut de = Deserializer::<BigEndian, _>::new(&mut input)
+        .unwrap()
+        .with_max_list_len(1024);
+    let err = Value::deserialize(&mut de).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::NegativeLength { len: -1 }));
+
+    // Arrays and skipped values are limited as well, and lists up to the limit are accepted.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Chunk {
+        heights: crate::IntArray,
+        entities: Vec<i8>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Empty {}
+
+    let chunk = Chunk {
+        heights: crate::IntArray(vec![64; 256]),
+        entities: vec![1; 16],
+    };
+    let encoded = to_le_bytes(&chunk).unwrap();
+
+    let deserialize = |max: u32| {
+        let mut input = encoded.as_slice();
+        let mut de = Deserializer::<LittleEndian, _>::new(&mut input)
+            .unwrap()
+            .with_max_list_len(max);
+        Chunk::deserialize(&mut de)
+    };
+    assert_eq!(deserialize(256).unwrap(), chunk);
+    assert!(matches!(
+        deserialize(255).unwrap_err().kind(),
+        NbtError::ListTooLong { declared: 256, .. }
+    ));
+
+    let mut input = encoded.as_slice();
+    let mut de = Deserializer::<LittleEndian, _>::new(&mut input)
+        .unwrap()
+        .with_max_list_len(8);
+    assert!(matches!(
+        Empty::deserialize(&mut de).unwrap_err().kind(),
+        NbtError::ListTooLong { .. }
+    ));
+}
+
+#[test]
+fn bedrock_leveldat() {
+    use crate::{from_bedrock_leveldat, to_bedrock_leveldat};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct LevelDat {
+        #[serde(rename = "LevelName")]
+        level_name: String,
+        #[serde(rename = "StorageVersion")]
+        storage_version: i32,
+    }
+
+    // Assembled by hand following the layout of the files written by the game, not taken from a
+    // real file: storage version 10, the length of the NBT data, and a root compound with an
+    // empty name.
+    #[rustfmt::skip]
+    let expected = [
+        0x0a, 0x00, 0x00, 0x00,
+        0x2c, 0x00, 0x00, 0x00,
+        0x0a, 0x00, 0x00,
+        0x08, 0x09, 0x00, b'L', b'e', b'v', b'e', b'l', b'N', b'a', b'm', b'e',
+        0x05, 0x00, b'W', b'o', b'r', b'l', b'd',
+        0x03, 0x0e, 0x00, b'S', b't', b'o', b'r', b'a', b'g', b'e',
+        b'V', b'e', b'r', b's', b'i', b'o', b'n', 0x0a, 0x00, 0x00, 0x00,
+        0x00,
+    ];
+    let level = LevelDat {
+        level_name: "World".to_owned(),
+        storage_version: 10,
+    };
+
+    assert_eq!(to_bedrock_leveldat(10, &level).unwrap(), expected);
+    assert_eq!(from_bedrock_leveldat(&expected).unwrap(), (10, level));
+
+    let err = from_bedrock_leveldat::<Value>(&expected[..20]).unwrap_err();
+    assert!(matches!(
+        err,
+        NbtError::FrameLengthMismatch {
+            declared: 0x2c,
+            actual: 12
+        }
+    ));
+
+    let mut trailing = expected.to_vec();
+    trailing.push(0);
+    assert!(matches!(
+        from_bedrock_leveldat::<Value>(&trailing).unwrap_err(),
+        NbtError::FrameLengthMismatch { actual: 45, .. }
+    ));
+
+    // The declared length covers more than the NBT value.
+    trailing[4] += 1;
+    assert!(matches!(
+        from_bedrock_leveldat::<Value>(&trailing).unwrap_err(),
+        NbtError::FrameLengthMismatch {
+            declared: 0x2d,
+            actual: 0x2c
+        }
+    ));
+
+    assert!(from_bedrock_leveldat::<Value>(&expected[..6]).is_err());
+}
+
+#[test]
+fn trailing_data() {
+    use crate::{from_be_bytes_exact, from_le_bytes_exact, from_net_bytes_exact};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Data {
+        value: i32,
+    }
+
+    let data = Data { value: 7 };
+
+    let mut encoded = to_be_bytes(&data).unwrap();
+    assert_eq!(
+        from_be_bytes_exact::<Data, _>(&mut encoded.as_slice()).unwrap(),
+        data
+    );
+
+    encoded.push(0);
+    let err = from_be_bytes_exact::<Data, _>(&mut encoded.as_slice()).unwrap_err();
+    assert!(matches!(err, NbtError::TrailingData { remaining: 1 }));
+
+    // The lenient variant leaves the byte in the reader.
+    let mut reader = encoded.as_slice();
+    assert_eq!(from_be_bytes::<Data, _>(&mut reader).unwrap(), data);
+    assert_eq!(reader, [0]);
+
+    let mut encoded = to_le_bytes(&data).unwrap();
+    encoded.extend([1, 2, 3]);
+    let err = from_le_bytes_exact::<Data, _>(&mut encoded.as_slice()).unwrap_err();
+    assert!(matches!(err, NbtError::TrailingData { remaining: 3 }));
+
+    let encoded = to_net_bytes(&data).unwrap();
+    assert_eq!(
+        from_net_bytes_exact::<Data, _>(&mut encoded.as_slice()).unwrap(),
+        data
+    );
+}
+
+#[test]
+fn btree_map_determinism() {
+    use std::collections::BTreeMap;
+
+    use crate::to_value;
+
+    let inner = BTreeMap::from([
+        ("b".to_owned(), vec![1i32, 2]),
+        ("a".to_owned(), Vec::new()),
+    ]);
+    let map = BTreeMap::from([
+        ("z".to_owned(), inner.clone()),
+        ("y".to_owned(), BTreeMap::new()),
+        ("x".to_owned(), inner),
+    ]);
+
+    let first = to_be_bytes(&map).unwrap();
+    let second = to_be_bytes(&map).unwrap();
+    assert_eq!(first, second);
+
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new()).with_sorted_keys(true);
+    map.serialize(&mut ser).unwrap();
+    assert_eq!(ser.into_inner().unwrap(), first);
+
+    let value: Value = from_be_slice(&first).unwrap();
+    assert_eq!(value, to_value(&map).unwrap());
+
+    // `None` entries are skipped, like `None` struct fields.
+    let options = BTreeMap::from([("a", None::<i32>), ("b", None)]);
+    let expected = to_be_bytes(&BTreeMap::<&str, i32>::new()).unwrap();
+    assert_eq!(to_be_bytes(&options).unwrap(), expected);
+
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new()).with_sorted_keys(true);
+    options.serialize(&mut ser).unwrap();
+    assert_eq!(ser.into_inner().unwrap(), expected);
+}
+
+#[test]
+fn serializer_reset() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Data {
+        value: i32,
+        name: String,
+    }
+
+    let first = Data {
+        value: 1,
+        name: "first".to_owned(),
+    };
+    let second = Data {
+        value: 2,
+        name: "second".to_owned(),
+    };
+
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new());
+    first.serialize(&mut ser).unwrap();
+    ser.reset().unwrap();
+    second.serialize(&mut ser).unwrap();
+    let encoded = ser.into_inner().unwrap();
+
+    let mut expected = to_be_bytes(&first).unwrap();
+    expected.extend(to_be_bytes(&second).unwrap());
+    assert_eq!(encoded, expected);
+
+    let mut reader = encoded.as_slice();
+    assert_eq!(from_be_bytes::<Data, _>(&mut reader).unwrap(), first);
+    assert_eq!(from_be_bytes::<Data, _>(&mut reader).unwrap(), second);
+    assert!(reader.is_empty());
+
+    // Resetting after a failed value clears whatever state was left of it.
+    let mut ser = Serializer::<_, NetworkLittleEndian>::new(Vec::new());
+    assert!(vec![1u32].serialize(&mut ser).is_err());
+    ser.reset().unwrap();
+    first.serialize(&mut ser).unwrap();
+    let encoded = ser.into_inner().unwrap();
+    assert!(encoded.ends_with(&to_net_bytes(&first).unwrap()));
+}
+
+#[test]
+fn tag_mask() {
+    use crate::{Deserializer, Int128, TagMask};
+
+    let mut mask = TagMask::NONE;
+    assert!(mask.is_empty());
+    mask.insert(FieldType::Int);
+    mask.insert(FieldType::Int);
+    assert_eq!(mask, TagMask::from(FieldType::Int));
+
+    let union = mask.union(TagMask::from([FieldType::String, FieldType::Byte]));
+    assert_eq!(
+        union,
+        mask | FieldType::String.into() | FieldType::Byte.into()
+    );
+    assert_eq!(
+        union.iter().collect::<Vec<_>>(),
+        [FieldType::Byte, FieldType::Int, FieldType::String]
+    );
+    assert_eq!(TagMask::ALL.iter().count(), 13);
+    assert_eq!(TagMask::ALL.iter().collect::<TagMask>(), TagMask::ALL);
+
+    let mut all = TagMask::NONE;
+    all |= TagMask::ALL;
+    assert_eq!(all, TagMask::ALL);
+
+    #[derive(Serialize)]
+    struct Mixed {
+        name: String,
+        pos: Vec<f64>,
+        data: Int128,
+        nested: HashMap<String, i16>,
+    }
+
+    #[derive(Deserialize)]
+    struct Partial {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    let encoded = to_be_bytes(&Mixed {
+        name: "mixed".to_owned(),
+        pos: vec![0.5, 64.0],
+        data: Int128(1),
+        nested: HashMap::from([("a".to_owned(), 1)]),
+    })
+    .unwrap();
+    let expected = TagMask::from([
+        FieldType::Compound,
+        FieldType::String,
+        FieldType::List,
+        FieldType::Double,
+        FieldType::LongArray,
+        FieldType::Short,
+    ]);
+
+    let mut input = encoded.as_slice();
+    let mut de = Deserializer::<BigEndian, _>::new(&mut input).unwrap();
+    Value::deserialize(&mut de).unwrap();
+    assert_eq!(de.observed_tags(), expected);
+
+    // Skipped fields are observed as well.
+    let mut input = encoded.as_slice();
+    let mut de = Deserializer::<BigEndian, _>::new(&mut input).unwrap();
+    Partial::deserialize(&mut de).unwrap();
+    assert_eq!(de.observed_tags(), expected);
+}
+
+#[test]
+fn bool_from_byte() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Flags {
+        on_ground: bool,
+        flying: bool,
+    }
+
+    let value = |on_ground, flying| {
+        Value::Compound(Compound::from_iter([
+            ("on_ground".to_owned(), Value::Byte(on_ground)),
+            ("flying".to_owned(), Value::Byte(flying)),
+        ]))
+    };
+
+    let lenient = value(2, 0);
+    let expected = Flags {
+        on_ground: true,
+        flying: false,
+    };
+    assert_eq!(
+        from_be_slice::<Flags>(&to_be_bytes(&lenient).unwrap()).unwrap(),
+        expected
+    );
+    assert_eq!(
+        from_net_slice::<Flags>(&to_net_bytes(&value(-1, 0)).unwrap()).unwrap(),
+        expected
+    );
+    assert_eq!(lenient.deserialize_into::<Flags>().unwrap(), expected);
+
+    // `true` is always written as 1.
+    let encoded = to_be_bytes(&expected).unwrap();
+    assert_eq!(from_be_slice::<Value>(&encoded).unwrap(), value(1, 0));
+}
+
+#[test]
+fn error_dump() {
+    use crate::debug_dump;
+
+    let data: Vec<u8> = (0..40).collect();
+
+    let dump = debug_dump(&data, 20);
+    assert_eq!(
+        dump.lines().collect::<Vec<_>>(),
+        [
+            "offset 20 (0x14):",
+            "00000000  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f",
+            "00000010  10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f",
+            "                      ^^",
+            "00000020  20 21 22 23 24 25 26 27",
+        ]
+    );
+
+    // Offsets at the end of the data mark the position after the last byte.
+    let dump = debug_dump(&data[..32], 32);
+    assert_eq!(
+        dump.lines().collect::<Vec<_>>(),
+        [
+            "offset 32 (0x20):",
+            "00000010  10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f",
+            "00000020",
+            "          ^^",
+        ]
+    );
+    assert!(debug_dump(&[], 5).starts_with("offset 5 (0x5):\n00000000\n"));
+
+    // Compound with an entry of an unknown tag type.
+    let data = [10, 0, 0, 99, 0, 1, b'a', 0];
+    let err = from_be_slice::<Value>(&data).unwrap_err();
+    let offset = err.offset().unwrap();
+    let dump = err.dump(&data).unwrap();
+    assert!(dump.starts_with(&format!("offset {offset} ")));
+    assert!(dump.contains("0a 00 00 63 00 01 61 00"));
+    assert!(dump.contains('^'));
+    assert_eq!(NbtError::UnknownTag(99).dump(&data), None);
+}
+
+#[test]
+fn skip_in_list() {
+    use crate::to_value;
+
+    #[derive(Serialize)]
+    struct Data {
+        values: Vec<Option<i32>>,
+        skipped: Option<i32>,
+        kept: Option<i32>,
+    }
+
+    let data = |values| Data {
+        values,
+        skipped: None,
+        kept: Some(5),
+    };
+
+    // `Some` elements and fields are written as the value they contain.
+    let encoded = to_be_bytes(&data(vec![Some(1), Some(2)])).unwrap();
+    assert_eq!(
+        from_be_slice::<Value>(&encoded).unwrap(),
+        Value::Compound(Compound::from_iter([
+            (
+                "values".to_owned(),
+                Value::List(vec![Value::Int(1), Value::Int(2)])
+            ),
+            ("kept".to_owned(), Value::Int(5)),
+        ]))
+    );
+
+    for values in [vec![Some(1), None], vec![None, Some(1)]] {
+        let err = to_be_bytes(&data(values.clone())).unwrap_err();
+        assert!(matches!(err, NbtError::CannotSkipInList));
+        let err = to_net_bytes(&values).unwrap_err();
+        assert!(matches!(err, NbtError::CannotSkipInList));
+        let err = to_value(&data(values)).unwrap_err();
+        assert!(matches!(err, NbtError::CannotSkipInList));
+    }
+
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new());
+    let mut list = ser.begin_list(FieldType::Int, 1).unwrap();
+    let err = list.write_element(&None::<i32>).unwrap_err();
+    assert!(matches!(err, NbtError::CannotSkipInList));
+
+    // A `None` root has nothing to be skipped from.
+    assert!(matches!(
+        to_be_bytes(&None::<i32>),
+        Err(NbtError::Unsupported(_))
+    ));
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn from_reader_auto() {
+    use std::io::Write;
+
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+
+    use crate::from_reader_auto;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Level {
+        name: String,
+        seed: i64,
+        spawn: Vec<i32>,
+    }
+
+    let level = Level {
+        name: "world".to_owned(),
+        seed: -42,
+        spawn: vec![0, 64, 0],
+    };
+    let raw = to_be_bytes(&level).unwrap();
+
+    let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+    gzip.write_all(&raw).unwrap();
+    let gzip = gzip.finish().unwrap();
+    assert_eq!(gzip[..2], [0x1f, 0x8b]);
+
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+    zlib.write_all(&raw).unwrap();
+    let zlib = zlib.finish().unwrap();
+    assert_eq!(zlib[0], 0x78);
+
+    for input in [&raw, &gzip, &zlib] {
+        assert_eq!(
+            from_reader_auto::<BigEndian, Level, _>(input.as_slice()).unwrap(),
+            level
+        );
+    }
+
+    let le = to_le_bytes(&level).unwrap();
+    assert_eq!(
+        from_reader_auto::<LittleEndian, Level, _>(le.as_slice()).unwrap(),
+        level
+    );
+
+    let err = from_reader_auto::<BigEndian, Value, _>(&[3, 0, 0, 0, 0, 0, 1][..]).unwrap_err();
+    assert!(matches!(err, NbtError::UnknownCompression(3)));
+    let err = from_reader_auto::<BigEndian, Value, _>(&[0x1f, 0x00][..]).unwrap_err();
+    assert!(matches!(err, NbtError::UnknownCompression(0x1f)));
+    let err = from_reader_auto::<BigEndian, Value, _>(&[][..]).unwrap_err();
+    assert!(matches!(err, NbtError::ByteError(_)));
+}
+
+#[test]
+fn missing_end_tag() {
+    use std::fmt;
+
+    use serde::de::{IgnoredAny, MapAccess, Visitor};
+
+    #[derive(Deserialize, Debug)]
+    struct HelloWorld {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    let truncated = &HELLO_WORLD_NBT[..HELLO_WORLD_NBT.len() - 1];
+
+    let err = from_be_slice::<Value>(truncated).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::MissingEndTag));
+    assert_eq!(err.offset(), Some(truncated.len() as u64));
+    let err = from_be_slice::<HelloWorld>(truncated).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::MissingEndTag));
+    let err = from_be_slice::<IgnoredAny>(truncated).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::MissingEndTag));
+    let err = from_be_bytes::<Value, _>(&mut Cursor::new(truncated)).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::MissingEndTag));
+    let err = validate::<BigEndian, _>(&mut Cursor::new(truncated)).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::MissingEndTag));
+
+    // Truncating a value inside the compound is still an ordinary end of file.
+    let err = from_be_slice::<Value>(&HELLO_WORLD_NBT[..HELLO_WORLD_NBT.len() - 2]).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::ByteError(_)));
+
+    /// Reads only the first entry of a compound.
+    #[derive(Debug)]
+    struct FirstEntry;
+
+    impl<'de> Deserialize<'de> for FirstEntry {
+        fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+            de.deserialize_map(FirstEntry)
+        }
+    }
+
+    impl<'de> Visitor<'de> for FirstEntry {
+        type Value = FirstEntry;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a compound")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<FirstEntry, A::Error> {
+            map.next_entry::<String, IgnoredAny>()?;
+            Ok(FirstEntry)
+        }
+    }
+
+    // The end tag is read even if the visitor stops at the last entry.
+    assert!(from_be_slice::<FirstEntry>(HELLO_WORLD_NBT).is_ok());
+
+    let value = Value::Compound(Compound::from_iter([
+        ("a".to_owned(), Value::Byte(1)),
+        ("b".to_owned(), Value::Byte(2)),
+    ]));
+    let err = from_be_slice::<FirstEntry>(&to_be_bytes(&value).unwrap()).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::MissingEndTag));
+}
+
+#[test]
+fn compound_hasher() {
+    use crate::CompoundHasher;
+
+    let mut compound = Compound::with_hasher(CompoundHasher::default());
+    compound.insert("name".to_owned(), Value::String("Steve".to_owned()));
+    compound.insert("health".to_owned(), Value::Float(20.0));
+    assert_eq!(compound.get("health"), Some(&Value::Float(20.0)));
+
+    let value = Value::Compound(compound);
+    let decoded: Value = from_le_slice(&to_le_bytes(&value).unwrap()).unwrap();
+    assert_eq!(decoded, value);
+    assert_eq!(
+        decoded.as_compound().unwrap()["name"],
+        Value::String("Steve".to_owned())
+    );
+
+    #[cfg(feature = "fxhash")]
+    assert_eq!(
+        std::any::type_name::<CompoundHasher>(),
+        std::any::type_name::<rustc_hash::FxBuildHasher>()
+    );
+}
+
+#[test]
+fn fixed_size_arrays() {
+    use crate::to_value;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Entity {
+        pos: [f64; 3],
+        rotation: [f32; 2],
+    }
+
+    #[derive(Serialize)]
+    struct Wrong {
+        pos: Vec<f64>,
+        rotation: [f32; 2],
+    }
+
+    let entity = Entity {
+        pos: [0.5, 64.0, -12.25],
+        rotation: [90.0, 0.0],
+    };
+
+    let encoded = to_be_bytes(&entity).unwrap();
+    assert_eq!(
+        from_be_slice::<Value>(&encoded).unwrap(),
+        Value::Compound(Compound::from_iter([
+            (
+                "pos".to_owned(),
+                Value::List(vec![
+                    Value::Double(0.5),
+                    Value::Double(64.0),
+                    Value::Double(-12.25)
+                ]),
+            ),
+            (
+                "rotation".to_owned(),
+                Value::List(vec![Value::Float(90.0), Value::Float(0.0)]),
+            ),
+        ]))
+    );
+    assert_eq!(from_be_slice::<Entity>(&encoded).unwrap(), entity);
+    assert_eq!(
+        from_net_slice::<Entity>(&to_net_bytes(&entity).unwrap()).unwrap(),
+        entity
+    );
+
+    for len in [2, 4] {
+        let wrong = Wrong {
+            pos: vec![1.0; len],
+            rotation: [0.0; 2],
+        };
+
+        let err = from_be_slice::<Entity>(&to_be_bytes(&wrong).unwrap()).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            NbtError::ArrayLengthMismatch { expected: 3, actual } if *actual == len
+        ));
+        assert_eq!(err.path(), Some("pos"));
+
+        let err = to_value(&wrong)
+            .unwrap()
+            .deserialize_into::<Entity>()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            NbtError::ArrayLengthMismatch { expected: 3, actual } if actual == len
+        ));
+    }
+
+    // Arrays of bytes are read from byte arrays, which are checked the same way.
+    let bytes = Value::Compound(Compound::from_iter([(
+        "data".to_owned(),
+        Value::ByteArray(vec![1, 2, 3]),
+    )]));
+    let err = from_be_slice::<HashMap<String, [i8; 4]>>(&to_be_bytes(&bytes).unwrap()).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::ArrayLengthMismatch {
+            expected: 4,
+            actual: 3
+        }
+    ));
+}
+
+#[test]
+fn missing_field() {
+    #[derive(Serialize)]
+    struct Stored {
+        name: String,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Player {
+        name: String,
+        #[serde(default)]
+        level: i32,
+        #[serde(default = "default_health")]
+        health: f32,
+        spawn: Option<i32>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Required {
+        #[allow(dead_code)]
+        name: String,
+        #[allow(dead_code)]
+        level: i32,
+    }
+
+    fn default_health() -> f32 {
+        20.0
+    }
+
+    let stored = Stored {
+        name: "Steve".to_owned(),
+    };
+    let encoded = to_le_bytes(&stored).unwrap();
+
+    let expected = Player {
+        name: "Steve".to_owned(),
+        level: 0,
+        health: 20.0,
+        spawn: None,
+    };
+    assert_eq!(from_le_slice::<Player>(&encoded).unwrap(), expected);
+
+    let err = from_le_slice::<Required>(&encoded).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::MissingField("level")));
+    assert_eq!(
+        err.kind().to_string(),
+        "Compound is missing the field \"level\""
+    );
+
+    let value = from_le_slice::<Value>(&encoded).unwrap();
+    assert_eq!(value.deserialize_into::<Player>().unwrap(), expected);
+    assert!(matches!(
+        value.deserialize_into::<Required>(),
+        Err(NbtError::MissingField("level"))
+    ));
+}
+
+#[test]
+fn float_bits_round_trip() {
+    #[derive(Serialize, Deserialize)]
+    struct Floats {
+        float: f32,
+        double: f64,
+        floats: Vec<f32>,
+        doubles: Vec<f64>,
+    }
+
+    fn check<F: crate::EndiannessImpl>(floats: &[f32], doubles: &[f64]) {
+        use crate::from_slice;
+
+        let bits32: Vec<_> = floats.iter().map(|v| v.to_bits()).collect();
+        let bits64: Vec<_> = doubles.iter().map(|v| v.to_bits()).collect();
+
+        for (&float, &double) in floats.iter().zip(doubles) {
+            let data = Floats {
+                float,
+                double,
+                floats: floats.to_vec(),
+                doubles: doubles.to_vec(),
+            };
+            let check_bits = |decoded: Floats| {
+                assert_eq!(decoded.float.to_bits(), float.to_bits());
+                assert_eq!(decoded.double.to_bits(), double.to_bits());
+                assert!(decoded
+                    .floats
+                    .iter()
+                    .map(|v| v.to_bits())
+                    .eq(bits32.iter().copied()));
+                assert!(decoded
+                    .doubles
+                    .iter()
+                    .map(|v| v.to_bits())
+                    .eq(bits64.iter().copied()));
+            };
+
+            let encoded = to_bytes::<F>(&data).unwrap();
+            check_bits(from_slice::<F, _>(&encoded).unwrap());
+
+            // The same bits survive a detour through `Value`, in both directions.
+            let value: Value = from_slice::<F, _>(&encoded).unwrap();
+            check_bits(value.deserialize_into().unwrap());
+            let encoded = to_bytes::<F>(&value).unwrap();
+            check_bits(from_slice::<F, _>(&encoded).unwrap());
+        }
+    }
+
+    // Signaling and quiet NaNs with payloads, infinities, zeros and subnormals.
+    let mut floats = vec![
+        f32::from_bits(0x7f80_0001),
+        f32::from_bits(0xff80_1234),
+        f32::from_bits(0x7fc0_0000),
+        f32::from_bits(0xffff_ffff),
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        0.0,
+        -0.0,
+        f32::from_bits(1),
+        f32::from_bits(0x807f_ffff),
+        f32::MIN_POSITIVE,
+    ];
+    let mut doubles = vec![
+        f64::from_bits(0x7ff0_0000_0000_0001),
+        f64::from_bits(0xfff0_0000_dead_beef),
+        f64::from_bits(0x7ff8_0000_0000_0000),
+        f64::from_bits(0xffff_ffff_ffff_ffff),
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        0.0,
+        -0.0,
+        f64::from_bits(1),
+        f64::from_bits(0x800f_ffff_ffff_ffff),
+        f64::MIN_POSITIVE,
+    ];
+
+    // Random bit patterns from a fixed seed, so that failures can be reproduced.
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for _ in 0..100 {
+        floats.push(f32::from_bits(next() as u32));
+        doubles.push(f64::from_bits(next()));
+    }
+
+    check::<BigEndian>(&floats, &doubles);
+    check::<LittleEndian>(&floats, &doubles);
+    check::<NetworkLittleEndian>(&floats, &doubles);
+}
+
+#[test]
+fn deserializer_options() {
+    use crate::{from_bytes_with, DeserializerOptions, FieldType, TagMask};
+
+    // A list of `i32::MAX` empty compounds, followed by just enough data to start collecting them.
+    let mut blob = vec![0x0a, 0, 0, 0x09, 0, 1, b'l', 0x0a];
+    blob.extend_from_slice(&i32::MAX.to_be_bytes());
+    blob.extend_from_slice(&[0; 16]);
+
+    let err =
+        from_bytes_with::<BigEndian, Value>(DeserializerOptions::hardened(), &blob).unwrap_err();
+    assert!(
+        matches!(err.kind(), NbtError::ListTooLong { declared, max: 65536 } if *declared == i32::MAX as u32),
+        "{err:?}"
+    );
+
+    // A byte array that is larger than the byte budget.
+    let large = Value::ByteArray(vec![0; 3 * 1024 * 1024]);
+    let encoded = to_be_bytes(&large).unwrap();
+    let options = DeserializerOptions::hardened().with_max_list_len(u32::MAX);
+    let err = from_bytes_with::<BigEndian, Value>(options, &encoded).unwrap_err();
+    assert!(
+        matches!(err.kind(), NbtError::ByteLimitExceeded { max: 2097152 }),
+        "{err:?}"
+    );
+    // The default options accept it.
+    let value: Value =
+        from_bytes_with::<BigEndian, _>(DeserializerOptions::default(), &encoded).unwrap();
+    assert_eq!(value, large);
+
+    // Compounds with repeated keys.
+    let blob = [10, 0, 0, 1, 0, 1, b'a', 1, 1, 0, 1, b'a', 2, 0];
+    let err =
+        from_bytes_with::<BigEndian, Value>(DeserializerOptions::hardened(), &blob).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::DuplicateKey(key) if key == "a"));
+
+    // Lists nested five levels deep.
+    let nested = (0..4).fold(Value::List(Vec::new()), |v, _| Value::List(vec![v]));
+    let encoded = to_le_bytes(&nested).unwrap();
+    let options = DeserializerOptions::hardened().with_max_depth(4);
+    assert!(from_bytes_with::<LittleEndian, Value>(options, &encoded).is_err());
+    let options = DeserializerOptions::hardened().with_max_depth(5);
+    assert_eq!(
+        from_bytes_with::<LittleEndian, Value>(options, &encoded).unwrap(),
+        nested
+    );
+
+    let options =
+        DeserializerOptions::default().with_allowed_tags(TagMask::ALL.without(FieldType::List));
+    let err = from_bytes_with::<LittleEndian, Value>(options, &encoded).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::ForbiddenTag(FieldType::List)
+    ));
+}
+
+#[test]
+fn likely_wrong_variant() {
+    use crate::Variant;
+
+    // Java Edition writes its files compressed with gzip, so they start with a gzip header.
+    let java = [0x1f, 0x8b, 0x08, 0, 0, 0, 0, 0, 0, 0xff];
+
+    let err = from_net_bytes::<Value, _>(&mut java.as_slice()).unwrap_err();
+    assert!(
+        matches!(
+            err.kind(),
+            NbtError::LikelyWrongVariant {
+                byte: 0x1f,
+                variant: Variant::NetworkEndian
+            }
+        ),
+        "{err:?}"
+    );
+    assert_eq!(
+        err.kind().to_string(),
+        "Unknown root tag 0x1f for the NetworkEndian variant, the data is likely compressed or in \
+         a different format"
+    );
+
+    let err = from_be_slice::<Value>(&java).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::LikelyWrongVariant {
+            byte: 0x1f,
+            variant: Variant::BigEndian
+        }
+    ));
+    let err = validate::<LittleEndian, _>(&mut java.as_slice()).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::LikelyWrongVariant {
+            byte: 0x1f,
+            variant: Variant::LittleEndian
+        }
+    ));
+
+    // Unknown tags after the root are still reported as such.
+    let err = from_be_slice::<Value>(&[0x0a, 0, 0, 0x1f, 0, 1, b'a', 0]).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::UnknownTag(0x1f)));
+}
+
+#[test]
+fn encoded_len() {
+    use crate::{encoded_len, EndiannessImpl};
+
+    #[derive(Serialize)]
+    struct Item {
+        name: String,
+        count: i8,
+        damage: Option<i16>,
+    }
+
+    #[derive(Serialize)]
+    struct Packet {
+        id: i32,
+        time: i64,
+        items: Vec<Item>,
+        data: Vec<i32>,
+    }
+
+    fn check<T: Serialize>(v: &T) {
+        fn check_variant<E: EndiannessImpl, T: Serialize>(v: &T) {
+            assert_eq!(
+                encoded_len::<E>(v).unwrap(),
+                to_bytes::<E>(v).unwrap().len()
+            );
+        }
+
+        check_variant::<BigEndian, _>(v);
+        check_variant::<LittleEndian, _>(v);
+        check_variant::<NetworkLittleEndian, _>(v);
+    }
+
+    // Values around the boundaries at which varints take another byte.
+    for (id, time, len) in [
+        (0, 0, 0),
+        (63, 63, 127),
+        (64, -65, 128),
+        (8191, 1 << 20, 16383),
+        (-8193, i64::MIN, 16384),
+        (i32::MAX, i64::MAX, 3),
+    ] {
+        let packet = Packet {
+            id,
+            time,
+            items: vec![
+                Item {
+                    name: "a".repeat(len),
+                    count: 1,
+                    damage: None,
+                },
+                Item {
+                    name: "minecraft:stone".to_owned(),
+                    count: 64,
+                    damage: Some(id as i16),
+                },
+            ],
+            data: vec![id; len],
+        };
+        check(&packet);
+    }
+
+    let value = from_be_slice::<Value>(BIG_TEST_NBT).unwrap();
+    check(&value);
+    check(&Value::Int(-1));
+
+    // Errors are reported like they are by `to_bytes`.
+    assert!(encoded_len::<BigEndian>(&1u32).is_err());
+}
+
+#[test]
+fn smart_pointers() {
+    use std::borrow::Cow;
+
+    use crate::to_value;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Health(f32);
+
+    #[allow(clippy::box_collection)]
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Entity<'a> {
+        data: Box<Vec<i32>>,
+        name: Cow<'a, str>,
+        health: Health,
+        tags: Vec<Box<str>>,
+        ids: Vec<Health>,
+    }
+
+    let entity = Entity {
+        data: Box::new(vec![1, 2, 3]),
+        name: Cow::Borrowed("zombie"),
+        health: Health(20.0),
+        tags: vec!["hostile".into()],
+        ids: vec![Health(1.0), Health(2.0)],
+    };
+
+    let encoded = to_be_bytes(&entity).unwrap();
+    assert_eq!(from_be_slice::<Entity>(&encoded).unwrap(), entity);
+    let encoded = to_net_bytes(&entity).unwrap();
+    assert_eq!(from_net_slice::<Entity>(&encoded).unwrap(), entity);
+
+    // Pointers and newtype structs are written like the value they contain.
+    #[derive(Serialize)]
+    #[serde(rename = "Entity")]
+    struct Plain<'a> {
+        data: Vec<i32>,
+        name: &'a str,
+        health: f32,
+        tags: Vec<String>,
+        ids: Vec<f32>,
+    }
+    let plain = Plain {
+        data: vec![1, 2, 3],
+        name: "zombie",
+        health: 20.0,
+        tags: vec!["hostile".to_owned()],
+        ids: vec![1.0, 2.0],
+    };
+    assert_eq!(to_be_bytes(&entity).unwrap(), to_be_bytes(&plain).unwrap());
+    assert_eq!(to_value(&entity).unwrap(), to_value(&plain).unwrap());
+
+    let value = to_value(&entity).unwrap();
+    assert_eq!(Entity::deserialize(&value).unwrap(), entity);
+
+    // As the root value.
+    let boxed: Box<Vec<i32>> = Box::new(vec![4, 5]);
+    let encoded = to_le_bytes(&boxed).unwrap();
+    assert_eq!(from_le_slice::<Box<Vec<i32>>>(&encoded).unwrap(), boxed);
+
+    let name: Cow<str> = Cow::Owned("skeleton".to_owned());
+    let encoded = to_le_bytes(&name).unwrap();
+    assert_eq!(from_le_slice::<Cow<str>>(&encoded).unwrap(), name);
+}
+
+#[test]
+fn ignored_fields_are_skipped() {
+    use crate::{Deserializer, EndiannessImpl};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Kept {
+        id: i32,
+        name: String,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Outer {
+        kept: Vec<Kept>,
+    }
+
+    let mut compound = Compound::default();
+    for i in 0..48 {
+        let value = match i % 6 {
+            0 => Value::String(format!("entry_{i}")),
+            1 => Value::ByteArray(vec![i as u8; 100]),
+            2 => Value::List(vec![Value::Compound(Compound::from_iter([(
+                "id".to_owned(),
+                Value::Int(-1),
+            )]))]),
+            3 => Value::IntArray(vec![i; 10]),
+            4 => Value::LongArray(vec![i as i64; 10]),
+            _ => Value::Compound(Compound::from_iter([(
+                "name".to_owned(),
+                Value::List(vec![Value::Double(1.5); 3]),
+            )])),
+        };
+        compound.insert(format!("field_{i}"), value);
+    }
+    compound.insert("id".to_owned(), Value::Int(300));
+    compound.insert("name".to_owned(), Value::String("kept".to_owned()));
+    let value = Value::Compound(compound);
+    let expected = Kept {
+        id: 300,
+        name: "kept".to_owned(),
+    };
+
+    fn check<F: EndiannessImpl>(value: &Value, expected: &Kept) {
+        let encoded = to_bytes::<F>(value).unwrap();
+        assert_eq!(&crate::from_slice::<F, Kept>(&encoded).unwrap(), expected);
+        assert_eq!(
+            &crate::from_bytes::<F, Kept>(&mut encoded.as_slice()).unwrap(),
+            expected
+        );
+
+        let outer = Value::Compound(Compound::from_iter([(
+            "kept".to_owned(),
+            Value::List(vec![value.clone(); 3]),
+        )]));
+        let encoded = to_bytes::<F>(&outer).unwrap();
+        let outer = crate::from_slice::<F, Outer>(&encoded).unwrap();
+        assert_eq!(outer.kept.len(), 3);
+        assert!(outer.kept.iter().all(|kept| kept == expected));
+    }
+
+    check::<BigEndian>(&value, &expected);
+    check::<LittleEndian>(&value, &expected);
+    check::<NetworkLittleEndian>(&value, &expected);
+
+    // Skipped fields are still passed to the unknown field handler.
+    let encoded = to_be_bytes(&value).unwrap();
+    let mut unknown = 0;
+    let mut input = encoded.as_slice();
+    let mut de = Deserializer::<BigEndian, _>::new(&mut input)
+        .unwrap()
+        .with_unknown_field_handler(|_, _| unknown += 1);
+    assert_eq!(Kept::deserialize(&mut de).unwrap(), expected);
+    drop(de);
+    assert_eq!(unknown, 48);
+
+    // Ignored strings are not decoded.
+    let data = [
+        0x0a, 0, 0, 8, 0, 1, b'x', 0, 2, 0xff, 0xfe, 3, 0, 2, b'i', b'd', 0, 0, 0, 1, 8, 0, 4,
+        b'n', b'a', b'm', b'e', 0, 1, b'a', 0,
+    ];
+    let kept = from_be_slice::<Kept>(&data).unwrap();
+    assert_eq!(kept.id, 1);
+    assert_eq!(kept.name, "a");
+
+    // Hardened options check ignored values as well, so the result does not depend on the
+    // fields of the target type.
+    let hardened = crate::DeserializerOptions::hardened();
+    let err = crate::from_bytes_with::<BigEndian, Kept>(hardened, &data).unwrap_err();
+    assert!(matches!(err.kind(), NbtError::ByteError(_)), "{err}");
+
+    #[rustfmt::skip]
+    let duplicate = [
+        0x0a, 0, 0,
+        3, 0, 2, b'i', b'd', 0, 0, 0, 1,
+        8, 0, 4, b'n', b'a', b'm', b'e', 0, 1, b'a',
+        0x0a, 0, 4, b'j', b'u', b'n', b'k', 1, 0, 1, b'x', 1, 1, 0, 1, b'x', 2, 0,
+        0,
+    ];
+    assert_eq!(from_be_slice::<Kept>(&duplicate).unwrap(), kept);
+    let err = crate::from_bytes_with::<BigEndian, Kept>(hardened, &duplicate).unwrap_err();
+    assert!(
+        matches!(err.kind(), NbtError::DuplicateKey(key) if key == "x"),
+        "{err}"
+    );
+    assert!(crate::from_bytes_with::<BigEndian, Value>(hardened, &duplicate).is_err());
+}
+
+#[test]
+fn to_value_heterogeneous_list() {
+    use crate::to_value;
+
+    #[derive(Serialize)]
+    #[serde(untagged)]
+    enum Number {
+        Small(i8),
+        Large(i32),
+    }
+
+    let numbers = vec![Number::Small(1), Number::Small(2), Number::Large(300)];
+    let err = to_value(&numbers).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            NbtError::HeterogeneousList {
+                index: 2,
+                expected: FieldType::Byte,
+                actual: FieldType::Int
+            }
+        ),
+        "{err:?}"
+    );
+    assert_eq!(
+        err.to_string(),
+        "List element 2 is of type Int, but the list contains Byte"
+    );
+
+    let err = to_value(&(1i8, 2i32)).unwrap_err();
+    assert!(matches!(err, NbtError::HeterogeneousList { index: 1, .. }));
+
+    // Elements of the same width are accepted.
+    let numbers = vec![Number::Large(1), Number::Large(300)];
+    assert_eq!(
+        to_value(&numbers).unwrap(),
+        Value::List(vec![Value::Int(1), Value::Int(300)])
+    );
+
+    // Nested lists only need to be lists, their elements may differ.
+    let nested = (vec![1i8], vec![1i64]);
+    assert_eq!(
+        to_value(&nested).unwrap(),
+        Value::List(vec![
+            Value::List(vec![Value::Byte(1)]),
+            Value::List(vec![Value::Long(1)])
+        ])
+    );
+}
+
+#[test]
+fn non_string_keys() {
+    use std::collections::BTreeMap;
+
+    use crate::{to_value, Serializer};
+
+    let names = HashMap::from([(1, "one".to_owned()), (-20, "minus twenty".to_owned())]);
+
+    let encoded = to_be_bytes(&names).unwrap();
+    let value = from_be_slice::<Value>(&encoded).unwrap();
+    let compound = value.as_compound().unwrap();
+    assert_eq!(compound["1"], Value::String("one".to_owned()));
+    assert_eq!(compound["-20"], Value::String("minus twenty".to_owned()));
+
+    assert_eq!(
+        from_be_slice::<HashMap<i32, String>>(&encoded).unwrap(),
+        names
+    );
+    let encoded = to_net_bytes(&names).unwrap();
+    assert_eq!(
+        from_net_bytes::<HashMap<i32, String>, _>(&mut encoded.as_slice()).unwrap(),
+        names
+    );
+    assert_eq!(to_value(&names).unwrap(), value);
+    assert_eq!(HashMap::<i32, String>::deserialize(&value).unwrap(), names);
+
+    // Sorted keys are compared as strings.
+    let mut ser = Serializer::<_, BigEndian>::new(Vec::new()).with_sorted_keys(true);
+    BTreeMap::from([(2u8, 0i8), (10, 1)])
+        .serialize(&mut ser)
+        .unwrap();
+    let sorted = ser.into_inner().unwrap();
+    assert_eq!(
+        sorted,
+        [10, 0, 0, 1, 0, 2, b'1', b'0', 1, 1, 0, 1, b'2', 0, 0]
+    );
+
+    // Chars and unit variants are written as strings as well.
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+    enum Slot {
+        Head,
+        Feet,
+    }
+    let armor = HashMap::from([(Slot::Head, 1i8), (Slot::Feet, 2)]);
+    let encoded = to_le_bytes(&armor).unwrap();
+    assert_eq!(
+        from_le_slice::<HashMap<String, i8>>(&encoded).unwrap()["Head"],
+        1
+    );
+    assert_eq!(from_le_slice::<HashMap<Slot, i8>>(&encoded).unwrap(), armor);
+    let encoded = to_le_bytes(&HashMap::from([('x', 1i8)])).unwrap();
+    assert_eq!(
+        from_le_slice::<HashMap<char, i8>>(&encoded).unwrap(),
+        HashMap::from([('x', 1)])
+    );
+
+    // Keys that cannot be converted to a string.
+    let err = to_be_bytes(&HashMap::from([((1, 2), 0i8)])).unwrap_err();
+    assert!(matches!(err, NbtError::NonStringKey("a tuple")), "{err:?}");
+    assert_eq!(
+        err.to_string(),
+        "Compound keys must be strings or integers, found a tuple"
+    );
+    assert!(matches!(
+        to_be_bytes(&HashMap::from([(true, 0i8)])),
+        Err(NbtError::NonStringKey("bool"))
+    ));
+
+    // Keys that are not numbers cannot be read as integers.
+    let encoded = to_be_bytes(&HashMap::from([("a", 0i8)])).unwrap();
+    assert!(from_be_slice::<HashMap<i32, i8>>(&encoded).is_err());
+}
+
+#[test]
+fn serializer_flush() {
+    use std::io::{self, BufWriter};
+
+    use crate::{to_be_bytes_in, to_bytes_buffered_in, to_net_bytes_then_send};
+
+    /// Writer that counts the amount of flush calls.
+    #[derive(Default)]
+    struct Flushes {
+        written: Vec<u8>,
+        flushes: usize,
+    }
+
+    impl io::Write for Flushes {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    let value = from_be_slice::<Value>(BIG_TEST_NBT).unwrap();
+    let expected = to_be_bytes(&value).unwrap();
+
+    let mut writer = Flushes::default();
+    to_be_bytes_in(&mut writer, &value).unwrap();
+    assert_eq!(writer.flushes, 1);
+    assert_eq!(writer.written, expected);
+
+    // Without flushing, a `BufWriter` only passes on full buffers.
+    let mut writer = BufWriter::with_capacity(64, Flushes::default());
+    to_be_bytes_in(&mut writer, &value).unwrap();
+    assert!(writer.buffer().is_empty());
+    assert_eq!(writer.get_ref().flushes, 1);
+    assert_eq!(writer.get_ref().written, expected);
+
+    let mut writer = Flushes::default();
+    to_bytes_buffered_in::<BigEndian>(&mut writer, &value).unwrap();
+    assert_eq!(writer.flushes, 1);
+    assert_eq!(writer.written, expected);
+
+    let mut writer = Flushes::default();
+    to_net_bytes_then_send(&mut writer, &value).unwrap();
+    assert_eq!(writer.flushes, 1);
+
+    // A serializer only flushes when asked to.
+    let mut ser = Serializer::<_, BigEndian>::new(BufWriter::new(Flushes::default()));
+    value.serialize(&mut ser).unwrap();
+    ser.flush().unwrap();
+    let writer = ser.into_inner().unwrap();
+    assert!(writer.buffer().is_empty());
+    assert_eq!(writer.get_ref().flushes, 1);
+    assert_eq!(writer.get_ref().written, expected);
+}
+
+#[test]
+fn tag_constants() {
+    use crate::tag;
+
+    let constants = [
+        (tag::END, FieldType::End),
+        (tag::BYTE, FieldType::Byte),
+        (tag::SHORT, FieldType::Short),
+        (tag::INT, FieldType::Int),
+        (tag::LONG, FieldType::Long),
+        (tag::FLOAT, FieldType::Float),
+        (tag::DOUBLE, FieldType::Double),
+        (tag::BYTE_ARRAY, FieldType::ByteArray),
+        (tag::STRING, FieldType::String),
+        (tag::LIST, FieldType::List),
+        (tag::COMPOUND, FieldType::Compound),
+        (tag::INT_ARRAY, FieldType::IntArray),
+        (tag::LONG_ARRAY, FieldType::LongArray),
+    ];
+
+    for (byte, ty) in constants {
+        assert_eq!(byte, ty as u8, "{ty:?}");
+        assert_eq!(FieldType::from_u8(byte), Some(ty));
+    }
+    // Every tag type has a constant.
+    assert_eq!(FieldType::from_u8(constants.len() as u8), None);
+}
+
+#[test]
+fn as_long_millis() {
+    use std::time::{Duration, SystemTime};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Session {
+        #[serde(with = "crate::serde_helpers::as_long_millis")]
+        started: SystemTime,
+        #[serde(with = "crate::serde_helpers::as_long_millis")]
+        length: Duration,
+    }
+
+    let session = Session {
+        started: SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+        length: Duration::from_millis(90_500),
+    };
+    let encoded = to_be_bytes(&session).unwrap();
+    assert_eq!(
+        from_be_slice::<Value>(&encoded).unwrap(),
+        Value::Compound(Compound::from_iter([
+            ("started".to_owned(), Value::Long(1_700_000_000_123)),
+            ("length".to_owned(), Value::Long(90_500)),
+        ]))
+    );
+    assert_eq!(from_be_slice::<Session>(&encoded).unwrap(), session);
+
+    // Times before the epoch are negative.
+    let before = Session {
+        started: SystemTime::UNIX_EPOCH - Duration::from_millis(5),
+        length: Duration::from_micros(1999),
+    };
+    let decoded = from_be_slice::<Session>(&to_be_bytes(&before).unwrap()).unwrap();
+    assert_eq!(decoded.started, before.started);
+    assert_eq!(decoded.length, Duration::from_millis(1));
+
+    // Durations can not be negative.
+    let negative = Value::Compound(Compound::from_iter([
+        ("started".to_owned(), Value::Long(0)),
+        ("length".to_owned(), Value::Long(-1)),
+    ]));
+    assert!(from_be_slice::<Session>(&to_be_bytes(&negative).unwrap()).is_err());
+}
+
+#[test]
+fn network_zigzag_varints() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Numbers {
+        i: i32,
+        l: i64,
+    }
+
+    // Zigzag varints, which map 0, -1, 1, -2, ... to 0, 1, 2, 3, ... before encoding them as
+    // LEB128. These were assembled by hand from that definition, not captured from a client, so
+    // they only check the encoder against the definition and not against Bedrock output.
+    #[rustfmt::skip]
+    let ints: [(i32, &[u8]); 7] = [
+        (0, &[0x00]),
+        (-1, &[0x01]),
+        (1, &[0x02]),
+        (-64, &[0x7f]),
+        (64, &[0x80, 0x01]),
+        (i32::MIN, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+        (i32::MAX, &[0xfe, 0xff, 0xff, 0xff, 0x0f]),
+    ];
+    #[rustfmt::skip]
+    let longs: [(i64, &[u8]); 5] = [
+        (-1, &[0x01]),
+        (-300, &[0xd7, 0x04]),
+        (i32::MIN as i64 - 1, &[0x81, 0x80, 0x80, 0x80, 0x10]),
+        (i64::MIN, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+        (i64::MAX, &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+    ];
+
+    for ((i, int_bytes), (l, long_bytes)) in ints.into_iter().zip(longs.into_iter().cycle()) {
+        let numbers = Numbers { i, l };
+
+        let mut expected = vec![0x0a, 0x00, 0x03, 0x01, b'i'];
+        expected.extend_from_slice(int_bytes);
+        expected.extend_from_slice(&[0x04, 0x01, b'l']);
+        expected.extend_from_slice(long_bytes);
+        expected.push(0x00);
+
+        let mut ser =
+            Serializer::<_, NetworkLittleEndian>::new(Vec::new()).with_root_name(Some(""));
+        numbers.serialize(&mut ser).unwrap();
+        assert_eq!(ser.into_inner().unwrap(), expected, "{numbers:?}");
+        assert_eq!(from_net_slice::<Numbers>(&expected).unwrap(), numbers);
+    }
+}
+
+#[test]
+fn bool_tag() {
+    use crate::{BoolTag, Deserializer, EndiannessImpl};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Flags {
+        on: bool,
+        list: Vec<bool>,
+    }
+
+    let flags = Flags {
+        on: true,
+        list: vec![false, true],
+    };
+
+    fn round_trip<F: EndiannessImpl>(flags: &Flags, tag: BoolTag) -> Vec<u8> {
+        let mut ser = Serializer::<_, F>::new(Vec::new()).with_bool_tag(tag);
+        flags.serialize(&mut ser).unwrap();
+        let encoded = ser.into_inner().unwrap();
+
+        let mut input = encoded.as_slice();
+        let mut de = Deserializer::<F, _>::new(&mut input)
+            .unwrap()
+            .with_bool_tag(tag);
+        assert_eq!(&Flags::deserialize(&mut de).unwrap(), flags);
+        drop(de);
+
+        encoded
+    }
+
+    let bytes = round_trip::<BigEndian>(&flags, BoolTag::Byte);
+    assert_eq!(bytes, to_be_bytes(&flags).unwrap());
+
+    let shorts = round_trip::<BigEndian>(&flags, BoolTag::Short);
+    #[rustfmt::skip]
+    assert_eq!(shorts[8..], [
+        FieldType::Short as u8, 0, 2, b'o', b'n', 0, 1,
+        FieldType::List as u8, 0, 4, b'l', b'i', b's', b't', FieldType::Short as u8, 0, 0, 0, 2,
+        0, 0, 0, 1,
+        0,
+    ]);
+    assert_eq!(
+        from_be_slice::<Value>(&shorts).unwrap(),
+        Value::Compound(Compound::from_iter([
+            ("on".to_owned(), Value::Short(1)),
+            (
+                "list".to_owned(),
+                Value::List(vec![Value::Short(0), Value::Short(1)])
+            ),
+        ]))
+    );
+
+    let le_shorts = round_trip::<LittleEndian>(&flags, BoolTag::Short);
+    assert_eq!(
+        le_shorts[8..15],
+        [FieldType::Short as u8, 2, 0, b'o', b'n', 1, 0]
+    );
+    round_trip::<NetworkLittleEndian>(&flags, BoolTag::Short);
+
+    // The tag has to match the option.
+    let err = from_be_slice::<Flags>(&shorts).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::UnexpectedType {
+            expected: FieldType::Byte,
+            actual: FieldType::Short,
+        }
+    ));
+}
+
+#[cfg(feature = "heapless")]
+#[test]
+fn heapless_string() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Player {
+        #[serde(with = "crate::serde_helpers::heapless_string")]
+        name: heapless::String<16>,
+    }
+
+    let player = Player {
+        name: "Steve".try_into().unwrap(),
+    };
+    let encoded = to_be_bytes(&player).unwrap();
+    assert_eq!(
+        from_be_slice::<Value>(&encoded).unwrap(),
+        Value::Compound(Compound::from_iter([(
+            "name".to_owned(),
+            Value::String("Steve".to_owned())
+        )]))
+    );
+    assert_eq!(from_be_slice::<Player>(&encoded).unwrap(), player);
+    assert_eq!(
+        from_be_bytes::<Player, _>(&mut encoded.as_slice()).unwrap(),
+        player
+    );
+
+    // Exactly the capacity still fits.
+    let full = Value::Compound(Compound::from_iter([(
+        "name".to_owned(),
+        Value::String("a".repeat(16)),
+    )]));
+    let decoded = from_net_slice::<Player>(&to_net_bytes(&full).unwrap()).unwrap();
+    assert_eq!(decoded.name.len(), 16);
+
+    let long = Value::Compound(Compound::from_iter([(
+        "name".to_owned(),
+        Value::String("Steve the very long name".to_owned()),
+    )]));
+    let err = from_be_slice::<Player>(&to_be_bytes(&long).unwrap()).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        NbtError::CapacityExceeded {
+            len: 24,
+            capacity: 16
+        }
+    ));
+
+    // Other deserializers report an invalid length instead.
+    assert!(Player::deserialize(&long).is_err());
+}
+
+#[test]
+fn value_builder() {
+    let built = Value::compound()
+        .with("name", Value::String("Steve".to_owned()))
+        .with("level", Value::Int(3))
+        .with("level", Value::Int(4))
+        .with(
+            "pos".to_owned(),
+            Value::compound().with("y", Value::Short(64)),
+        );
+    let expected = Value::Compound(Compound::from_iter([
+        ("name".to_owned(), Value::String("Steve".to_owned())),
+        ("level".to_owned(), Value::Int(4)),
+        (
+            "pos".to_owned(),
+            Value::Compound(Compound::from_iter([("y".to_owned(), Value::Short(64))])),
+        ),
+    ]));
+    assert_eq!(built, expected);
+
+    // Values that are not compounds are replaced.
+    assert_eq!(
+        Value::Int(1).with("a", Value::Byte(1)),
+        Value::compound().with("a", Value::Byte(1))
+    );
+
+    let mut value = built.clone();
+    value
+        .entry("level")
+        .and_modify(|v| *v = Value::Int(5))
+        .or_insert(Value::Int(0));
+    value.entry("xp").or_insert(Value::Float(0.5));
+    assert_eq!(value.as_compound().unwrap()["level"], 5);
+    assert_eq!(value.as_compound().unwrap()["xp"], 0.5f32);
+
+    let mut list = Value::List(vec![Value::Byte(1)]);
+    list.entry("a").or_insert(Value::Byte(2));
+    assert_eq!(list, Value::compound().with("a", Value::Byte(2)));
+
+    // Taking a nested value leaves an empty compound behind.
+    let mut value = built;
+    let pos = match &mut value {
+        Value::Compound(map) => map.get_mut("pos").unwrap().take(),
+        _ => unreachable!(),
+    };
+    assert_eq!(pos, Value::compound().with("y", Value::Short(64)));
+    assert_eq!(value.as_compound().unwrap()["pos"], Value::compound());
+    assert_eq!(Value::Int(7).take(), Value::Int(7));
+}
+
+#[test]
+fn overlong_varints() {
+    fn read(data: &[u8]) -> Result<Value, NbtError> {
+        from_net_slice::<Value>(data).map_err(|e| e.kind().clone())
+    }
+
+    assert_eq!(
+        read(&[3, 0, 0xff, 0xff, 0xff, 0xff, 0x0f]).unwrap(),
+        Value::Int(i32::MIN)
+    );
+    assert_eq!(
+        read(&[4, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]).unwrap(),
+        Value::Long(i64::MIN)
+    );
+
+    // Bits above the width of the type are rejected instead of being truncated.
+    let too_long = [
+        &[3, 0, 0xff, 0xff, 0xff, 0xff, 0x1f][..],
+        &[3, 0, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00],
+        &[
+            4, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x03,
+        ],
+        &[
+            4, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x81, 0x00,
+        ],
+        // The length of a string is an unsigned 32-bit varint.
+        &[8, 0, 0x80, 0x80, 0x80, 0x80, 0x10],
+    ];
+    for (data, bits) in too_long.into_iter().zip([32, 32, 64, 64, 32]) {
+        assert!(
+            matches!(read(data), Err(NbtError::VarintTooLong { bits: b }) if b == bits),
+            "{data:?}"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Conversions between the different ways UUIDs are stored in NBT.
+//!
+//! Minecraft has stored UUIDs in several forms over time:
+//!
+//! | Format                      | Example                                       |
+//! |-----------------------------|-----------------------------------------------|
+//! | [`UuidFormat::IntArray`]    | `UUID: [I; 1, 2, 3, 4]`, used by Java 1.16+   |
+//! | [`UuidFormat::MostLeast`]   | `UUIDMost: 1L, UUIDLeast: 2L`, older Java data |
+//! | [`UuidFormat::String`]      | `UUID: "00000001-0000-..."`, used by Bedrock   |
+//!
+//! All helpers normalize UUIDs to a `u128`, with the most significant bits first.
+//! To store a UUID as a field of a struct in the int array format, use [`Uuid`].
+
+use crate::{Compound, Uuid, Value};
+
+/// The representation that a UUID is stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UuidFormat {
+    /// Four ints, with the most significant int first.
+    IntArray,
+    /// Two longs, stored under the key with a `Most` and `Least` suffix respectively.
+    MostLeast,
+    /// A hyphenated hexadecimal string.
+    String,
+}
+
+/// Converts four ints, with the most significant int first, into a UUID.
+#[inline]
+pub fn from_ints(ints: [i32; 4]) -> u128 {
+    Uuid::from_ints(ints).0
+}
+
+/// Splits a UUID into four ints, with the most significant int first.
+#[inline]
+pub fn to_ints(uuid: u128) -> [i32; 4] {
+    Uuid(uuid).to_ints()
+}
+
+/// Combines the most and least significant halves of a UUID.
+#[inline]
+pub fn from_most_least(most: i64, least: i64) -> u128 {
+    ((most as u64 as u128) << 64) | least as u64 as u128
+}
+
+/// Splits a UUID into its most and least significant halves.
+#[inline]
+pub fn to_most_least(uuid: u128) -> (i64, i64) {
+    ((uuid >> 64) as i64, uuid as i64)
+}
+
+/// Parses a UUID from its hyphenated hexadecimal form, such as
+/// `f81d4fae-7dec-11d0-a765-00a0c91e6bf6`.
+pub fn parse(s: &str) -> Option<u128> {
+    let groups: Vec<&str> = s.split('-').collect();
+    let valid = groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit()));
+
+    if !valid {
+        return None;
+    }
+
+    u128::from_str_radix(&groups.concat(), 16).ok()
+}
+
+/// Formats a UUID in its lowercase hyphenated hexadecimal form.
+pub fn format(uuid: u128) -> String {
+    let hex = format!("{uuid:032x}");
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Reads the UUID stored under `key` in the given compound, in whichever format is present.
+///
+/// For [`UuidFormat::MostLeast`], the UUID is read from `{key}Most` and `{key}Least`.
+/// Int arrays may also be stored as a list of four ints.
+pub fn read(compound: &Compound, key: &str) -> Option<u128> {
+    match compound.get(key) {
+        Some(Value::IntArray(ints)) => return Some(from_ints(ints.as_slice().try_into().ok()?)),
+        Some(Value::List(list)) => {
+            let ints = list
+                .iter()
+                .map(|v| v.as_int().copied())
+                .collect::<Option<Vec<_>>>()?;
+
+            return Some(from_ints(ints.try_into().ok()?));
+        }
+        Some(Value::String(s)) => return parse(s),
+        Some(_) => return None,
+        None => {}
+    }
+
+    let most = compound.get(&format!("{key}Most"))?.as_long()?;
+    let least = compound.get(&format!("{key}Least"))?.as_long()?;
+
+    Some(from_most_least(*most, *least))
+}
+
+/// Writes a UUID under `key` in the given compound, using the given format.
+///
+/// Any representation of the UUID in another format is removed.
+pub fn write(compound: &mut Compound, key: &str, uuid: u128, format: UuidFormat) {
+    let most_key = format!("{key}Most");
+    let least_key = format!("{key}Least");
+
+    compound.retain(|k, _| k != key && *k != most_key && *k != least_key);
+
+    match format {
+        UuidFormat::IntArray => {
+            compound.insert(key.to_owned(), Value::IntArray(to_ints(uuid).to_vec()));
+        }
+        UuidFormat::MostLeast => {
+            let (most, least) = to_most_least(uuid);
+            compound.insert(most_key, Value::Long(most));
+            compound.insert(least_key, Value::Long(least));
+        }
+        UuidFormat::String => {
+            compound.insert(key.to_owned(), Value::String(self::format(uuid)));
+        }
+    }
+}
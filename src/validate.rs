@@ -0,0 +1,282 @@
+use std::borrow::Cow;
+use std::io::{self, Read};
+use std::marker::PhantomData;
+
+use byteorder::{BigEndian, LittleEndian};
+
+use crate::de::{is_eof, wrong_variant, PositionReader, MAX_DEPTH};
+use crate::error::StreamError;
+use crate::{EndiannessImpl, FieldType, NbtError, Variant};
+
+/// Size of the stack buffer used to validate strings in chunks.
+const STRING_CHUNK_SIZE: usize = 256;
+
+/// Verifies that the given reader contains a single well-formed NBT value, usually a compound.
+///
+/// Unlike deserializing into [`Value`](crate::Value) or `serde::de::IgnoredAny`, this performs a
+/// single structural pass over the data without allocating. Fixed-size values are skipped
+/// based on their size and strings are only checked for valid UTF-8.
+///
+/// On failure, the returned error carries the offset at which the problem was found.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::Compound;
+/// # fn main() {
+///  let value = nbtx::Value::Compound(Compound::from_iter([
+///     ("name".to_owned(), nbtx::Value::String("Steve".to_owned())),
+///  ]));
+///  let encoded = nbtx::to_le_bytes(&value).unwrap();
+///
+///  assert!(nbtx::validate::<nbtx::LittleEndian, _>(&mut encoded.as_slice()).is_ok());
+///  assert!(nbtx::validate::<nbtx::LittleEndian, _>(&mut &encoded[..4]).is_err());
+/// # }
+/// ```
+pub fn validate<F, R>(reader: &mut R) -> Result<(), NbtError>
+where
+    F: EndiannessImpl,
+    R: Read,
+{
+    let mut validator = Validator::<F, R> {
+        input: PositionReader::new(reader),
+        depth: 0,
+        max_depth: 0,
+        _marker: PhantomData,
+    };
+
+    validator.root().map_err(|e| e.at(validator.input.offset()))
+}
+
+/// Returns the maximum nesting depth of lists and compounds in the given NBT data.
+///
+/// The root compound counts as the first level, so an empty compound has a depth of 1 and a
+/// root value that is neither a list nor a compound has a depth of 0. Like [`validate`], this
+/// walks the data without deserializing or allocating it, which makes it suitable for rejecting
+/// overly nested input before parsing it.
+///
+/// Data nested deeper than 512 levels results in an error, since it cannot be deserialized.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::Compound;
+/// # fn main() {
+///  let value = nbtx::Value::Compound(Compound::from_iter([(
+///     "list".to_owned(),
+///     nbtx::Value::List(vec![nbtx::Value::Int(1)]),
+///  )]));
+///  let encoded = nbtx::to_be_bytes(&value).unwrap();
+///
+///  assert_eq!(nbtx::max_depth::<nbtx::BigEndian>(&encoded).unwrap(), 2);
+/// # }
+/// ```
+pub fn max_depth<F>(data: &[u8]) -> Result<usize, NbtError>
+where
+    F: EndiannessImpl,
+{
+    let mut reader = data;
+    let mut validator = Validator::<F, _> {
+        input: PositionReader::new(&mut reader),
+        depth: 0,
+        max_depth: 0,
+        _marker: PhantomData,
+    };
+
+    validator
+        .root()
+        .map_err(|e| e.at(validator.input.offset()))?;
+    Ok(validator.max_depth)
+}
+
+/// Structural walker over raw NBT data.
+struct Validator<'re, F, R>
+where
+    F: EndiannessImpl,
+    R: Read,
+{
+    input: PositionReader<'re, R>,
+    /// Amount of lists and compounds that are currently open.
+    depth: usize,
+    /// Largest value that `depth` has reached.
+    max_depth: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F, R> Validator<'_, F, R>
+where
+    F: EndiannessImpl,
+    R: Read,
+{
+    fn root(&mut self) -> Result<(), NbtError> {
+        let ty = FieldType::try_from(self.input.read_u8()?).map_err(wrong_variant::<F>)?;
+        if ty == FieldType::End {
+            return Err(NbtError::Other(Cow::Borrowed(
+                "Encountered end tag in place of the root value",
+            )));
+        }
+
+        self.string()?;
+        self.payload(ty)
+    }
+
+    /// Skips over the payload of a tag of the given type.
+    fn payload(&mut self, ty: FieldType) -> Result<(), NbtError> {
+        match ty {
+            FieldType::End => Err(NbtError::Other(Cow::Borrowed(
+                "Encountered unmatched end tag",
+            ))),
+            FieldType::Byte => self.skip(1),
+            FieldType::Short => self.skip(2),
+            FieldType::Int => match F::AS_ENUM {
+                Variant::NetworkEndian => {
+                    self.input.read_i32_varint()?;
+                    Ok(())
+                }
+                _ => self.skip(4),
+            },
+            FieldType::Long => match F::AS_ENUM {
+                Variant::NetworkEndian => {
+                    self.input.read_i64_varint()?;
+                    Ok(())
+                }
+                _ => self.skip(8),
+            },
+            FieldType::Float => self.skip(4),
+            FieldType::Double => self.skip(8),
+            FieldType::ByteArray => {
+                let len = self.len()?;
+                self.skip(len)
+            }
+            FieldType::IntArray => {
+                let len = self.len()?;
+                match F::AS_ENUM {
+                    Variant::NetworkEndian => {
+                        for _ in 0..len {
+                            self.input.read_i32_varint()?;
+                        }
+                        Ok(())
+                    }
+                    _ => self.skip(len * 4),
+                }
+            }
+            FieldType::LongArray => {
+                let len = self.len()?;
+                match F::AS_ENUM {
+                    Variant::NetworkEndian => {
+                        for _ in 0..len {
+                            self.input.read_i64_varint()?;
+                        }
+                        Ok(())
+                    }
+                    _ => self.skip(len * 8),
+                }
+            }
+            FieldType::String => self.string(),
+            FieldType::List => {
+                self.enter()?;
+                let ty = FieldType::try_from(self.input.read_u8()?)?;
+                let len = self.len()?;
+                if ty == FieldType::End && len > 0 {
+                    // Lengths are read from an i32, so this cannot truncate.
+                    return Err(NbtError::InvalidListType { len: len as u32 });
+                }
+
+                for _ in 0..len {
+                    self.payload(ty)?;
+                }
+
+                self.depth -= 1;
+                Ok(())
+            }
+            FieldType::Compound => {
+                self.enter()?;
+                loop {
+                    let ty = match self.input.read_u8() {
+                        Err(e) if is_eof(&e) => return Err(NbtError::MissingEndTag),
+                        ty => FieldType::try_from(ty?)?,
+                    };
+                    if ty == FieldType::End {
+                        self.depth -= 1;
+                        return Ok(());
+                    }
+
+                    self.string()?;
+                    self.payload(ty)?;
+                }
+            }
+        }
+    }
+
+    /// Enters a list or compound, failing if this exceeds [`MAX_DEPTH`].
+    #[inline]
+    fn enter(&mut self) -> Result<(), NbtError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(NbtError::Other(Cow::Borrowed(
+                "Exceeded maximum nesting depth of lists and compounds",
+            )));
+        }
+
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+        Ok(())
+    }
+
+    /// Reads a sequence length prefix.
+    fn len(&mut self) -> Result<u64, NbtError> {
+        let len = match F::AS_ENUM {
+            Variant::BigEndian => self.input.read_i32::<BigEndian>()?,
+            Variant::LittleEndian => self.input.read_i32::<LittleEndian>()?,
+            Variant::NetworkEndian => self.input.read_i32_varint()?,
+        };
+
+        u64::try_from(len).map_err(|_| NbtError::NegativeLength { len })
+    }
+
+    /// Skips over a string, verifying that it is valid UTF-8.
+    fn string(&mut self) -> Result<(), NbtError> {
+        let mut remaining = match F::AS_ENUM {
+            Variant::BigEndian => self.input.read_u16::<BigEndian>()? as usize,
+            Variant::LittleEndian => self.input.read_u16::<LittleEndian>()? as usize,
+            Variant::NetworkEndian => self.input.read_u32_varint()? as usize,
+        };
+
+        let mut buf = [0u8; STRING_CHUNK_SIZE];
+        // Amount of bytes at the start of the buffer belonging to a character
+        // that was split across two chunks.
+        let mut carry = 0;
+
+        while remaining > 0 {
+            let n = remaining.min(buf.len() - carry);
+            self.input.read_exact(&mut buf[carry..carry + n])?;
+            remaining -= n;
+
+            let chunk = &buf[..carry + n];
+            match std::str::from_utf8(chunk) {
+                Ok(_) => carry = 0,
+                Err(e) if e.error_len().is_none() && remaining > 0 => {
+                    let valid = e.valid_up_to();
+                    carry = chunk.len() - valid;
+                    buf.copy_within(valid..valid + carry, 0);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Skips the given amount of bytes.
+    fn skip(&mut self, n: u64) -> Result<(), NbtError> {
+        let skipped = io::copy(&mut (&mut self.input).take(n), &mut io::sink())?;
+        if skipped != n {
+            return Err(StreamError::UnexpectedEof {
+                expected: n as usize,
+                remaining: skipped as usize,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+}
@@ -1,15 +1,94 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fmt;
-use std::hash::{Hash, Hasher};
+use std::hash::{DefaultHasher, Hash, Hasher};
 
-use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::de::value::{BorrowedStrDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{DeserializeOwned, IntoDeserializer, MapAccess, SeqAccess, Visitor};
 use serde::ser::{SerializeMap, SerializeSeq};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::de::KeyDeserializer;
+use crate::io::{ByRef, Write};
+use crate::{EndiannessImpl, FieldType, NbtError};
+
+/// Map that stores the entries of a [`Value::Compound`].
+///
+/// This is a [`HashMap`](std::collections::HashMap) by default. With the `indexmap` feature
+/// enabled, it is an [`IndexMap`](indexmap::IndexMap) instead, which preserves the order in which
+/// entries were inserted or read, so that re-serializing a deserialized value produces identical
+/// bytes.
+///
+/// Keys are hashed using [`CompoundHasher`]. Since `new` and `From<[_; N]>` are only provided
+/// for the default hasher of the standard library, use [`Default`] and [`FromIterator`] to create
+/// compounds that work with the `fxhash` feature as well.
+#[cfg(not(feature = "indexmap"))]
+pub type Compound = std::collections::HashMap<String, Value, CompoundHasher>;
+
+/// Map that stores the entries of a [`Value::Compound`].
+///
+/// This is a [`HashMap`](std::collections::HashMap) by default. With the `indexmap` feature
+/// enabled, it is an [`IndexMap`](indexmap::IndexMap) instead, which preserves the order in which
+/// entries were inserted or read, so that re-serializing a deserialized value produces identical
+/// bytes.
+///
+/// Keys are hashed using [`CompoundHasher`]. Since `new` and `From<[_; N]>` are only provided
+/// for the default hasher of the standard library, use [`Default`] and [`FromIterator`] to create
+/// compounds that work with the `fxhash` feature as well.
+#[cfg(feature = "indexmap")]
+pub type Compound = indexmap::IndexMap<String, Value, CompoundHasher>;
+
+/// Entry of a [`Compound`], which is either occupied or vacant.
+///
+/// This is the entry type of the map that is used for [`Compound`]. Returned by
+/// [`Value::entry`].
+#[cfg(not(feature = "indexmap"))]
+pub type CompoundEntry<'a> = std::collections::hash_map::Entry<'a, String, Value>;
+
+/// Entry of a [`Compound`], which is either occupied or vacant.
+///
+/// This is the entry type of the map that is used for [`Compound`]. Returned by
+/// [`Value::entry`].
+#[cfg(feature = "indexmap")]
+pub type CompoundEntry<'a> = indexmap::map::Entry<'a, String, Value>;
+
+/// Hasher that is used for the keys of a [`Compound`].
+///
+/// This is the [`RandomState`](std::collections::hash_map::RandomState) of the standard library
+/// by default. With the `fxhash` feature enabled, it is
+/// [`FxBuildHasher`](rustc_hash::FxBuildHasher) instead, which is considerably faster for short
+/// keys but does not protect against collisions crafted by an attacker.
+#[cfg(not(feature = "fxhash"))]
+pub type CompoundHasher = std::collections::hash_map::RandomState;
+
+/// Hasher that is used for the keys of a [`Compound`].
+///
+/// This is the [`RandomState`](std::collections::hash_map::RandomState) of the standard library
+/// by default. With the `fxhash` feature enabled, it is
+/// [`FxBuildHasher`](rustc_hash::FxBuildHasher) instead, which is considerably faster for short
+/// keys but does not protect against collisions crafted by an attacker.
+#[cfg(feature = "fxhash")]
+pub type CompoundHasher = rustc_hash::FxBuildHasher;
+
 /// General NBT value type that can represent any value.
 ///
 /// In case the structure of some piece of NBT data is not known, this
 /// type can be used to deserialise it.
+///
+/// # Equality
+///
+/// Values are equal if they have the same type and contents. Compounds are compared entry by
+/// entry, regardless of the order of their keys. Floats and doubles use IEEE equality, so
+/// `NaN` is not equal to itself and `0.0` equals `-0.0`. Because of this, `Value` does not
+/// implement [`Eq`]. Its [`Hash`] implementation is consistent with this equality, so values
+/// without `NaN` can be used as keys of a `HashMap` or `HashSet`.
+///
+/// Use [`approx_eq`](Value::approx_eq) to compare values with rounding errors in their floats.
+///
+/// # Ordering
+///
+/// `Value` does not implement [`Ord`], since that requires [`Eq`]. Instead,
+/// [`total_cmp`](Value::total_cmp) defines a total order that can be used to sort values.
 #[derive(Debug, Clone)]
 pub enum Value {
     /// A signed byte.
@@ -35,7 +114,9 @@ pub enum Value {
     /// List of an arbitrary NBT value.
     List(Vec<Value>),
     /// Key-value map.
-    Compound(HashMap<String, Value>),
+    ///
+    /// See [`Compound`] for the type of map that is used.
+    Compound(Compound),
     /// An array of integers.
     IntArray(Vec<i32>),
     /// An array of longs.
@@ -60,6 +141,423 @@ impl Value {
             Self::LongArray(_) => 12,
         }
     }
+
+    /// Renders this value as an indented, human-readable tree.
+    ///
+    /// This is meant for inspecting the structure of NBT data, not for machine parsing.
+    /// See [`tree`](Self::tree) to configure the output.
+    #[inline]
+    pub fn display_tree(&self) -> String {
+        self.tree().to_string()
+    }
+
+    /// Returns a [`Display`](fmt::Display) implementation that renders this value as a tree,
+    /// such as
+    ///
+    /// ```text
+    /// TAG_Compound("Level"): 2 entries {
+    ///   TAG_Int("xPos"): 5
+    ///   TAG_IntArray("heights"): 256 ints [64, 64, 65, 65, 66, 66, 66, 67, ...]
+    /// }
+    /// ```
+    ///
+    /// Keys of compounds are sorted, and arrays only show a preview of their first elements.
+    #[inline]
+    pub fn tree(&self) -> TreeDisplay<'_> {
+        TreeDisplay {
+            value: self,
+            name: None,
+            indent: 2,
+        }
+    }
+
+    /// Serializes this value in every NBT variant, returning the big endian, little endian and
+    /// network little endian encodings, in that order.
+    ///
+    /// When the same data has to be written for both Java and Bedrock, converting it into a
+    /// [`Value`] once and encoding that value for each variant avoids walking the original
+    /// structure multiple times:
+    ///
+    /// ```rust
+    /// # use nbtx::{Compound, Value};
+    /// let value = Value::Compound(Compound::from_iter([("x".to_owned(), Value::Int(5))]));
+    /// let (java, bedrock, network) = value.to_all_variants().unwrap();
+    ///
+    /// assert_eq!(nbtx::from_be_slice::<Value>(&java).unwrap(), value);
+    /// assert_eq!(nbtx::from_le_slice::<Value>(&bedrock).unwrap(), value);
+    /// assert_eq!(nbtx::from_net_slice::<Value>(&network).unwrap(), value);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn to_all_variants(&self) -> Result<(Vec<u8>, Vec<u8>, Vec<u8>), NbtError> {
+        Ok((
+            crate::to_be_bytes(self)?,
+            crate::to_le_bytes(self)?,
+            crate::to_net_bytes(self)?,
+        ))
+    }
+
+    /// Writes this value as a root compound with the given name.
+    ///
+    /// A [`Value`] does not store the name of its root compound, so serializing it normally
+    /// writes an empty name. This returns [`NbtError::UnexpectedType`] if this value is not a
+    /// compound.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::{Compound, Value};
+    /// let value = Value::Compound(Compound::from_iter([("x".to_owned(), Value::Int(5))]));
+    ///
+    /// let mut encoded = Vec::new();
+    /// value.write_to::<nbtx::BigEndian, _>(&mut encoded, "Level").unwrap();
+    ///
+    /// let mut input = encoded.as_slice();
+    /// let de = nbtx::Deserializer::<nbtx::BigEndian, _>::new(&mut input).unwrap();
+    /// assert_eq!(de.root_name(), "Level");
+    /// ```
+    pub fn write_to<E, W>(&self, writer: &mut W, root_name: &str) -> Result<(), NbtError>
+    where
+        E: EndiannessImpl,
+        W: Write,
+    {
+        if !matches!(self, Value::Compound(_)) {
+            return Err(NbtError::UnexpectedType {
+                expected: FieldType::Compound,
+                actual: FieldType::try_from(self.discriminant())?,
+            });
+        }
+
+        let mut ser = crate::Serializer::<_, E>::new(ByRef(writer)).with_root_name(Some(root_name));
+        self.serialize(&mut ser)
+    }
+
+    /// Returns whether this value equals `other`, allowing floats and doubles to differ by at
+    /// most `epsilon`.
+    ///
+    /// This is meant for tests that compare values after lossy conversions. Lists and compounds
+    /// are compared recursively, and `NaN` is considered approximately equal to `NaN`. Any other
+    /// value is compared exactly, and values of different types are never equal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::Value;
+    /// let a = Value::List(vec![Value::Double(0.1 + 0.2), Value::Int(1)]);
+    /// let b = Value::List(vec![Value::Double(0.3), Value::Int(1)]);
+    ///
+    /// assert_ne!(a, b);
+    /// assert!(a.approx_eq(&b, 1e-9));
+    /// ```
+    pub fn approx_eq(&self, other: &Value, epsilon: f64) -> bool {
+        let floats_eq = |a: f64, b: f64| (a.is_nan() && b.is_nan()) || (a - b).abs() <= epsilon;
+
+        match (self, other) {
+            (Value::Float(a), Value::Float(b)) => floats_eq(*a as f64, *b as f64),
+            (Value::Double(a), Value::Double(b)) => floats_eq(*a, *b),
+            (Value::List(a), Value::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, epsilon))
+            }
+            (Value::Compound(a), Value::Compound(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(k, a)| b.get(k).is_some_and(|b| a.approx_eq(b, epsilon)))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Compares this value with `other` using a total order, like [`f64::total_cmp`].
+    ///
+    /// This can be used with [`slice::sort_by`] to bring values into a canonical order. Values
+    /// are ordered as follows:
+    ///
+    /// - Values of different types are ordered by their tag, so every byte comes before every
+    ///   short, which come before every int, and so on, up to long arrays.
+    /// - Numbers are ordered by their value. Floats and doubles use [`f64::total_cmp`], so
+    ///   `-0.0` comes before `0.0`, and `NaN` with a positive sign comes after infinity.
+    /// - Strings are ordered by their bytes.
+    /// - Lists and arrays are ordered lexicographically by their elements.
+    /// - Compounds are ordered lexicographically by their entries sorted by key, where each
+    ///   entry is ordered by its key and then by its value.
+    ///
+    /// Because of the float ordering, this returns [`Equal`](Ordering::Equal) for `NaN`s with
+    /// the same bits, and not for `0.0` and `-0.0`, which is the reverse of [`PartialEq`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::Value;
+    /// let mut values = vec![Value::Int(2), Value::Byte(5), Value::Int(-1)];
+    /// values.sort_by(Value::total_cmp);
+    ///
+    /// assert_eq!(values, [Value::Byte(5), Value::Int(-1), Value::Int(2)]);
+    /// ```
+    pub fn total_cmp(&self, other: &Value) -> Ordering {
+        fn cmp_all<'a, T: 'a>(
+            a: impl IntoIterator<Item = &'a T>,
+            b: impl IntoIterator<Item = &'a T>,
+            cmp: impl Fn(&T, &T) -> Ordering,
+        ) -> Ordering {
+            let (mut a, mut b) = (a.into_iter(), b.into_iter());
+            loop {
+                match (a.next(), b.next()) {
+                    (Some(a), Some(b)) => match cmp(a, b) {
+                        Ordering::Equal => {}
+                        ord => return ord,
+                    },
+                    (a, b) => return a.is_some().cmp(&b.is_some()),
+                }
+            }
+        }
+
+        match (self, other) {
+            (Value::Byte(a), Value::Byte(b)) => a.cmp(b),
+            (Value::Short(a), Value::Short(b)) => a.cmp(b),
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::Long(a), Value::Long(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+            (Value::Double(a), Value::Double(b)) => a.total_cmp(b),
+            (Value::ByteArray(a), Value::ByteArray(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::List(a), Value::List(b)) => cmp_all(a, b, Value::total_cmp),
+            (Value::Compound(a), Value::Compound(b)) => {
+                let mut a = a.iter().collect::<Vec<_>>();
+                let mut b = b.iter().collect::<Vec<_>>();
+                a.sort_unstable_by_key(|(k, _)| *k);
+                b.sort_unstable_by_key(|(k, _)| *k);
+
+                cmp_all(&a, &b, |(ka, va), (kb, vb)| {
+                    ka.cmp(kb).then_with(|| va.total_cmp(vb))
+                })
+            }
+            (Value::IntArray(a), Value::IntArray(b)) => a.cmp(b),
+            (Value::LongArray(a), Value::LongArray(b)) => a.cmp(b),
+            _ => self.discriminant().cmp(&other.discriminant()),
+        }
+    }
+
+    /// Deserializes this value into a typed structure, like `serde_json::from_value`.
+    ///
+    /// The value is read directly, without encoding it first. This is useful to extract a
+    /// known part of a document that was loaded as a [`Value`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::{Compound, Value};
+    /// #[derive(serde::Deserialize)]
+    /// struct Pos {
+    ///     x: i32,
+    ///     z: i32,
+    /// }
+    ///
+    /// let value = Value::Compound(Compound::from_iter([
+    ///     ("x".to_owned(), Value::Int(1)),
+    ///     ("z".to_owned(), Value::Int(-2)),
+    /// ]));
+    ///
+    /// let pos: Pos = value.deserialize_into().unwrap();
+    /// assert_eq!((pos.x, pos.z), (1, -2));
+    /// ```
+    #[inline]
+    pub fn deserialize_into<T>(&self) -> Result<T, NbtError>
+    where
+        T: DeserializeOwned,
+    {
+        T::deserialize(self)
+    }
+
+    /// Returns an empty compound.
+    ///
+    /// Entries can be added using [`with`](Self::with) and [`entry`](Self::entry).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::Value;
+    /// let player = Value::compound()
+    ///     .with("name", Value::String("Steve".to_owned()))
+    ///     .with("health", Value::Float(20.0))
+    ///     .with("pos", Value::compound().with("x", Value::Int(1)).with("z", Value::Int(-2)));
+    ///
+    /// assert_eq!(player.as_compound().unwrap().len(), 3);
+    /// assert_eq!(player.as_compound().unwrap()["pos"].as_compound().unwrap()["z"], -2);
+    /// ```
+    #[inline]
+    pub fn compound() -> Value {
+        Self::Compound(Compound::default())
+    }
+
+    /// Inserts the given entry into this compound and returns it, replacing any existing entry
+    /// with the same key.
+    ///
+    /// If this value is not a compound, it is first replaced by an empty compound.
+    #[inline]
+    pub fn with(mut self, key: impl Into<String>, value: Value) -> Value {
+        self.compound_mut().insert(key.into(), value);
+        self
+    }
+
+    /// Returns the entry of this compound with the given key, for in-place manipulation.
+    ///
+    /// If this value is not a compound, it is first replaced by an empty compound.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::Value;
+    /// let mut counts = Value::compound();
+    /// for word in ["stone", "dirt", "stone"] {
+    ///     let count = counts.entry(word).or_insert(Value::Int(0));
+    ///     if let Value::Int(n) = count {
+    ///         *n += 1;
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(counts.as_compound().unwrap()["stone"], 2);
+    /// ```
+    #[inline]
+    pub fn entry(&mut self, key: impl Into<String>) -> CompoundEntry<'_> {
+        self.compound_mut().entry(key.into())
+    }
+
+    /// Replaces this value with an empty compound and returns the previous value.
+    ///
+    /// This is useful to move a value out of a tree that is only borrowed mutably.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::Value;
+    /// let mut value = Value::compound().with("a", Value::Byte(1));
+    /// let taken = value.take();
+    ///
+    /// assert_eq!(taken.as_compound().unwrap().len(), 1);
+    /// assert!(value.as_compound().unwrap().is_empty());
+    /// ```
+    #[inline]
+    pub fn take(&mut self) -> Value {
+        std::mem::replace(self, Self::compound())
+    }
+
+    /// Returns the entries of this compound, replacing this value with an empty compound if it
+    /// is not a compound.
+    #[inline]
+    fn compound_mut(&mut self) -> &mut Compound {
+        if !self.is_compound() {
+            *self = Self::compound();
+        }
+        match self {
+            Self::Compound(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Overlays `other` onto this value.
+    ///
+    /// If both values are compounds, the keys of `other` are merged into this compound.
+    /// Nested compounds that exist in both are merged recursively, while any other value in
+    /// `other` replaces the existing value for that key.
+    ///
+    /// Lists are not merged element-wise: a list in `other` replaces the existing list as a whole.
+    ///
+    /// If either value is not a compound, this value is simply replaced by `other`.
+    pub fn merge(&mut self, other: Value) {
+        match (self, other) {
+            (Self::Compound(base), Self::Compound(other)) => {
+                for (key, value) in other {
+                    match base.get_mut(&key) {
+                        Some(existing) => existing.merge(value),
+                        None => {
+                            base.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (this, other) => *this = other,
+        }
+    }
+
+    /// Walks the whole tree and returns every value for which `predicate` returns `true`, along
+    /// with its path.
+    ///
+    /// The predicate receives the segments of the path to the value, which are the keys of
+    /// compounds and the indices of list elements. The returned paths have the same format as
+    /// [`Deserializer::path`](crate::Deserializer::path), such as `Level.Sections[2].Palette`.
+    ///
+    /// This value itself is visited first, with an empty path. Entries of compounds are visited
+    /// in sorted key order. Elements of arrays are not visited.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::{Compound, Value};
+    /// let air = Value::String("minecraft:air".to_owned());
+    /// let value = Value::Compound(Compound::from_iter([(
+    ///     "palette".to_owned(),
+    ///     Value::List(vec![Value::String("minecraft:stone".to_owned()), air.clone()]),
+    /// )]));
+    ///
+    /// let found = value.find_all(|_, v| *v == air);
+    /// assert_eq!(found, [("palette[1]".to_owned(), &air)]);
+    /// ```
+    pub fn find_all<P>(&self, predicate: P) -> Vec<(String, &Value)>
+    where
+        P: Fn(&[&str], &Value) -> bool,
+    {
+        let mut found = Vec::new();
+        self.find_all_in(&predicate, &mut Vec::new(), &mut String::new(), &mut found);
+        found
+    }
+
+    fn find_all_in<'a, P>(
+        &'a self,
+        predicate: &P,
+        segments: &mut Vec<Cow<'a, str>>,
+        path: &mut String,
+        found: &mut Vec<(String, &'a Value)>,
+    ) where
+        P: Fn(&[&str], &Value) -> bool,
+    {
+        let parts: Vec<&str> = segments.iter().map(AsRef::as_ref).collect();
+        if predicate(&parts, self) {
+            found.push((path.clone(), self));
+        }
+
+        let mark = path.len();
+        match self {
+            Self::List(list) => {
+                for (i, value) in list.iter().enumerate() {
+                    let index = i.to_string();
+                    path.push('[');
+                    path.push_str(&index);
+                    path.push(']');
+
+                    segments.push(Cow::Owned(index));
+                    value.find_all_in(predicate, segments, path, found);
+                    segments.pop();
+                    path.truncate(mark);
+                }
+            }
+            Self::Compound(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_unstable_by_key(|(k, _)| *k);
+
+                for (key, value) in entries {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(key);
+
+                    segments.push(Cow::Borrowed(key));
+                    value.find_all_in(predicate, segments, path, found);
+                    segments.pop();
+                    path.truncate(mark);
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 macro_rules! impl_access_fns {
@@ -110,7 +608,7 @@ impl Value {
         Double = f64,
         String = String,
         List = Vec<Self>,
-        Compound = HashMap<String, Self>,
+        Compound = Compound,
         ByteArray = Vec<u8>,
         IntArray = Vec<i32>,
         LongArray = Vec<i64>
@@ -326,23 +824,23 @@ impl PartialEq<&[Value]> for &mut Value {
     }
 }
 
-impl PartialEq<HashMap<String, Value>> for Value {
+impl PartialEq<Compound> for Value {
     #[inline]
-    fn eq(&self, rhs: &HashMap<String, Value>) -> bool {
+    fn eq(&self, rhs: &Compound) -> bool {
         self.as_compound() == Some(rhs)
     }
 }
 
-impl PartialEq<HashMap<String, Value>> for &Value {
+impl PartialEq<Compound> for &Value {
     #[inline]
-    fn eq(&self, rhs: &HashMap<String, Value>) -> bool {
+    fn eq(&self, rhs: &Compound) -> bool {
         self.as_compound() == Some(rhs)
     }
 }
 
-impl PartialEq<HashMap<String, Value>> for &mut Value {
+impl PartialEq<Compound> for &mut Value {
     #[inline]
-    fn eq(&self, rhs: &HashMap<String, Value>) -> bool {
+    fn eq(&self, rhs: &Compound) -> bool {
         self.as_compound() == Some(rhs)
     }
 }
@@ -421,10 +919,18 @@ impl Hash for Value {
                 state.write(&bytes);
             }
             Value::Compound(map) => {
+                // Equal compounds can iterate over their entries in a different order, so the
+                // hashes of the entries are combined in a way that does not depend on it.
+                let mut entries = 0u64;
                 for (k, v) in map {
-                    state.write(k.as_bytes());
-                    v.hash(state);
+                    let mut hasher = DefaultHasher::new();
+                    k.hash(&mut hasher);
+                    v.hash(&mut hasher);
+                    entries = entries.wrapping_add(hasher.finish());
                 }
+
+                state.write_usize(map.len());
+                state.write_u64(entries);
             }
             Value::List(v) => Self::hash_slice(v, state),
             Value::ByteArray(v) => u8::hash_slice(v, state),
@@ -567,6 +1073,14 @@ impl<'de> Visitor<'de> for ValueVisitor {
         Ok(Value::String(v))
     }
 
+    #[inline]
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::ByteArray(v.to_vec()))
+    }
+
     #[inline]
     fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
     where
@@ -597,7 +1111,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         A: MapAccess<'de>,
     {
-        let mut out: HashMap<String, Value> = HashMap::new();
+        let mut out = Compound::default();
         if let Some(hint) = map.size_hint() {
             out.reserve(hint);
         }
@@ -609,3 +1123,337 @@ impl<'de> Visitor<'de> for ValueVisitor {
         Ok(Value::Compound(out))
     }
 }
+
+/// Deserializes a typed structure from a [`Value`] tree.
+///
+/// Like the NBT deserializer, booleans are read from bytes and optional fields that are present
+/// are always `Some`. Elements of byte arrays are unsigned, so that they can be read into a
+/// `Vec<u8>`.
+impl<'de> Deserializer<'de> for &'de Value {
+    type Error = NbtError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Byte(v) => visitor.visit_i8(*v),
+            Value::Short(v) => visitor.visit_i16(*v),
+            Value::Int(v) => visitor.visit_i32(*v),
+            Value::Long(v) => visitor.visit_i64(*v),
+            Value::Float(v) => visitor.visit_f32(*v),
+            Value::Double(v) => visitor.visit_f64(*v),
+            Value::ByteArray(v) => visitor.visit_borrowed_bytes(v),
+            Value::String(v) => visitor.visit_borrowed_str(v),
+            Value::List(v) => SeqDeserializer::new(v.iter()).deserialize_any(visitor),
+            Value::Compound(v) => MapDeserializer::new(
+                v.iter()
+                    .map(|(k, v)| (KeyDeserializer::new(k, BorrowedStrDeserializer::new(k)), v)),
+            )
+            .deserialize_any(visitor),
+            Value::IntArray(v) => SeqDeserializer::new(v.iter().copied()).deserialize_any(visitor),
+            Value::LongArray(v) => SeqDeserializer::new(v.iter().copied()).deserialize_any(visitor),
+        }
+    }
+
+    #[inline]
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Byte(v) => visitor.visit_bool(*v != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    #[inline]
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::ByteArray(v) => SeqDeserializer::new(v.iter().copied()).deserialize_any(visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        let actual = match self {
+            Value::ByteArray(v) => v.len(),
+            Value::List(v) => v.len(),
+            Value::IntArray(v) => v.len(),
+            Value::LongArray(v) => v.len(),
+            _ => len,
+        };
+        if actual != len {
+            return Err(NbtError::ArrayLengthMismatch {
+                expected: len,
+                actual,
+            });
+        }
+
+        self.deserialize_seq(visitor)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, NbtError>
+    where
+        V: Visitor<'de>,
+    {
+        Err(NbtError::Unsupported(
+            "Deserializing enums is not supported",
+        ))
+    }
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, NbtError> for &'de Value {
+    type Deserializer = Self;
+
+    #[inline]
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Compound entries that are kept in the order in which they were read.
+///
+/// Unlike [`Compound`], this preserves the order of its entries regardless of the `indexmap`
+/// feature. Combined with `#[serde(flatten)]`, it captures every field that is not part of a
+/// struct, so that re-serializing the struct writes unknown fields in their original order.
+/// Known fields are written first, followed by the captured entries.
+///
+/// Only the top-level order is preserved: compounds nested inside the captured values are
+/// stored as [`Compound`]s.
+///
+/// # Example
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use nbtx::{OrderedCompound, Value};
+/// #[derive(Serialize, Deserialize)]
+/// struct Entity {
+///     id: i32,
+///     #[serde(flatten)]
+///     rest: OrderedCompound,
+/// }
+///
+/// let original = OrderedCompound(vec![
+///     ("id".to_owned(), Value::Int(1)),
+///     ("z".to_owned(), Value::Byte(2)),
+///     ("a".to_owned(), Value::Byte(3)),
+/// ]);
+/// let encoded = nbtx::to_be_bytes(&original).unwrap();
+///
+/// let entity: Entity = nbtx::from_be_slice(&encoded).unwrap();
+/// assert_eq!(entity.rest.0[0].0, "z");
+/// assert_eq!(nbtx::to_be_bytes(&entity).unwrap(), encoded);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderedCompound(pub Vec<(String, Value)>);
+
+impl Serialize for OrderedCompound {
+    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map_ser = ser.serialize_map(Some(self.0.len()))?;
+        for (k, v) in &self.0 {
+            map_ser.serialize_entry(k, v)?;
+        }
+        map_ser.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for OrderedCompound {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(OrderedCompoundVisitor)
+    }
+}
+
+struct OrderedCompoundVisitor;
+
+impl<'de> Visitor<'de> for OrderedCompoundVisitor {
+    type Value = OrderedCompound;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a compound")
+    }
+
+    #[inline]
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut out = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(entry) = map.next_entry()? {
+            out.push(entry);
+        }
+
+        Ok(OrderedCompound(out))
+    }
+}
+
+/// Renders a [`Value`] as an indented tree.
+///
+/// Created by [`Value::tree`].
+#[derive(Debug, Clone)]
+pub struct TreeDisplay<'a> {
+    value: &'a Value,
+    name: Option<&'a str>,
+    indent: usize,
+}
+
+impl<'a> TreeDisplay<'a> {
+    /// Amount of array elements that are shown before the output is truncated.
+    const PREVIEW_LEN: usize = 8;
+
+    /// Sets the name that is displayed for the root tag.
+    #[inline]
+    pub fn with_name(mut self, name: &'a str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets the amount of spaces that each level of nesting is indented by.
+    #[inline]
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    fn write_tag(
+        &self,
+        f: &mut fmt::Formatter,
+        name: Option<&str>,
+        value: &Value,
+        depth: usize,
+    ) -> fmt::Result {
+        write!(f, "{:width$}", "", width = depth * self.indent)?;
+
+        let ty = crate::FieldType::try_from(value.discriminant()).map_err(|_| fmt::Error)?;
+        match name {
+            Some(name) => write!(f, "TAG_{ty:?}({name:?}): ")?,
+            None => write!(f, "TAG_{ty:?}(None): ")?,
+        }
+
+        match value {
+            Value::Byte(v) => writeln!(f, "{v}"),
+            Value::Short(v) => writeln!(f, "{v}"),
+            Value::Int(v) => writeln!(f, "{v}"),
+            Value::Long(v) => writeln!(f, "{v}"),
+            Value::Float(v) => writeln!(f, "{v}"),
+            Value::Double(v) => writeln!(f, "{v}"),
+            Value::String(v) => writeln!(f, "{v:?}"),
+            Value::ByteArray(v) => Self::write_array(f, v, "bytes"),
+            Value::IntArray(v) => Self::write_array(f, v, "ints"),
+            Value::LongArray(v) => Self::write_array(f, v, "longs"),
+            Value::List(list) => {
+                write!(f, "{} {}", list.len(), entries(list.len()))?;
+                self.write_children(f, list.iter().map(|v| (None, v)), depth)
+            }
+            Value::Compound(map) => {
+                let mut children: Vec<_> = map.iter().collect();
+                children.sort_unstable_by_key(|(k, _)| *k);
+
+                write!(f, "{} {}", map.len(), entries(map.len()))?;
+                self.write_children(
+                    f,
+                    children.into_iter().map(|(k, v)| (Some(k.as_str()), v)),
+                    depth,
+                )
+            }
+        }
+    }
+
+    fn write_children<'v>(
+        &self,
+        f: &mut fmt::Formatter,
+        children: impl ExactSizeIterator<Item = (Option<&'v str>, &'v Value)>,
+        depth: usize,
+    ) -> fmt::Result {
+        if children.len() == 0 {
+            return writeln!(f, " {{}}");
+        }
+
+        writeln!(f, " {{")?;
+        for (name, value) in children {
+            self.write_tag(f, name, value, depth + 1)?;
+        }
+        writeln!(f, "{:width$}}}", "", width = depth * self.indent)
+    }
+
+    fn write_array<T: fmt::Display>(
+        f: &mut fmt::Formatter,
+        array: &[T],
+        unit: &str,
+    ) -> fmt::Result {
+        write!(f, "{} {unit} [", array.len())?;
+        for (i, v) in array.iter().take(Self::PREVIEW_LEN).enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{v}")?;
+        }
+        if array.len() > Self::PREVIEW_LEN {
+            f.write_str(", ...")?;
+        }
+        writeln!(f, "]")
+    }
+}
+
+fn entries(len: usize) -> &'static str {
+    if len == 1 {
+        "entry"
+    } else {
+        "entries"
+    }
+}
+
+impl fmt::Display for TreeDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_tag(f, self.name, self.value, 0)
+    }
+}
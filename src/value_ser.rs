@@ -0,0 +1,419 @@
+use serde::ser::{self, Impossible, Serialize};
+
+use crate::ser::KeySerializer;
+use crate::wrapper::{INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN};
+use crate::{Compound, FieldType, NbtError, Value};
+
+/// Converts the given data into a [`Value`], without encoding it first.
+///
+/// This is the counterpart of [`Value::deserialize_into`] and is analogous to
+/// `serde_json::to_value`. Every value is converted to the tag that the
+/// [`Serializer`](crate::Serializer) would write for it, including int and long arrays
+/// produced by wrappers such as [`Uuid`](crate::Uuid). Fields that are `None` are left out of
+/// their compound, while `Some` values are converted to the value they contain. List elements
+/// cannot be left out, so a `None` element results in [`NbtError::CannotSkipInList`].
+///
+/// All elements of a list must convert to the same tag. Elements are not widened to a common
+/// type, so a tuple such as `(1i8, 2i32)` results in [`NbtError::HeterogeneousList`] rather
+/// than a list of ints.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::Value;
+/// #[derive(serde::Serialize)]
+/// struct Pos {
+///     x: i32,
+///     y: i16,
+/// }
+///
+/// let value = nbtx::to_value(&Pos { x: 1, y: 64 }).unwrap();
+/// let compound = value.as_compound().unwrap();
+///
+/// assert_eq!(compound["x"], Value::Int(1));
+/// assert_eq!(compound["y"], Value::Short(64));
+/// ```
+pub fn to_value<T>(v: &T) -> Result<Value, NbtError>
+where
+    T: ?Sized + Serialize,
+{
+    v.serialize(ValueSerializer)?.ok_or(NbtError::Unsupported(
+        "`None` can only be serialized as a compound entry",
+    ))
+}
+
+/// Serializer that builds a [`Value`].
+///
+/// Returns `None` for values that are skipped when they are a compound entry.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Option<Value>;
+    type Error = NbtError;
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = Impossible<Option<Value>, NbtError>;
+    type SerializeTupleVariant = Impossible<Option<Value>, NbtError>;
+    type SerializeMap = SerializeCompound;
+    type SerializeStruct = SerializeCompound;
+    type SerializeStructVariant = Impossible<Option<Value>, NbtError>;
+
+    #[inline]
+    fn serialize_bool(self, v: bool) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::Byte(v as i8)))
+    }
+
+    #[inline]
+    fn serialize_i8(self, v: i8) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::Byte(v)))
+    }
+
+    #[inline]
+    fn serialize_i16(self, v: i16) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::Short(v)))
+    }
+
+    #[inline]
+    fn serialize_i32(self, v: i32) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::Int(v)))
+    }
+
+    #[inline]
+    fn serialize_i64(self, v: i64) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::Long(v)))
+    }
+
+    #[inline]
+    fn serialize_f32(self, v: f32) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::Float(v)))
+    }
+
+    #[inline]
+    fn serialize_f64(self, v: f64) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::Double(v)))
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Option<Value>, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serialization of `u8` is not supported",
+        ))
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Option<Value>, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serialization of `u16` is not supported",
+        ))
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Option<Value>, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serialization of `u32` is not supported",
+        ))
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Option<Value>, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serialization of `u64` is not supported",
+        ))
+    }
+
+    fn serialize_i128(self, _v: i128) -> Result<Option<Value>, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serialization of `i128` is not supported",
+        ))
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Option<Value>, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serialization of `char` is not supported",
+        ))
+    }
+
+    #[inline]
+    fn serialize_str(self, v: &str) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::String(v.to_owned())))
+    }
+
+    #[inline]
+    fn serialize_bytes(self, v: &[u8]) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::ByteArray(v.to_vec())))
+    }
+
+    #[inline]
+    fn serialize_none(self) -> Result<Option<Value>, NbtError> {
+        Ok(None)
+    }
+
+    #[inline]
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Option<Value>, NbtError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<Value>, NbtError> {
+        Err(NbtError::Unsupported("Serializing () is not supported"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<Value>, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serializing unit structs is not supported",
+        ))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Option<Value>, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serializing unit variants is not supported",
+        ))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Option<Value>, NbtError> {
+        let ty = match name {
+            INT_ARRAY_TOKEN => FieldType::IntArray,
+            LONG_ARRAY_TOKEN => FieldType::LongArray,
+            _ => return value.serialize(self),
+        };
+
+        let elements = match to_value(value)? {
+            Value::List(elements) => elements,
+            v => {
+                return Err(NbtError::UnexpectedType {
+                    expected: FieldType::List,
+                    actual: FieldType::try_from(v.discriminant())?,
+                })
+            }
+        };
+
+        let array = if ty == FieldType::IntArray {
+            elements
+                .into_iter()
+                .map(|v| match v {
+                    Value::Int(v) => Ok(v),
+                    v => Err(array_element_error(FieldType::Int, &v)),
+                })
+                .collect::<Result<_, _>>()
+                .map(Value::IntArray)
+        } else {
+            elements
+                .into_iter()
+                .map(|v| match v {
+                    Value::Long(v) => Ok(v),
+                    v => Err(array_element_error(FieldType::Long, &v)),
+                })
+                .collect::<Result<_, _>>()
+                .map(Value::LongArray)
+        }?;
+
+        Ok(Some(array))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Option<Value>, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serializing newtype variants is not supported",
+        ))
+    }
+
+    #[inline]
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, NbtError> {
+        Ok(SerializeList {
+            elements: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    #[inline]
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, NbtError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serializing tuple structs is not supported",
+        ))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serializing tuple variants is not supported",
+        ))
+    }
+
+    #[inline]
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeCompound, NbtError> {
+        Ok(SerializeCompound {
+            entries: Compound::default(),
+        })
+    }
+
+    #[inline]
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeCompound, NbtError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, NbtError> {
+        Err(NbtError::Unsupported(
+            "Serializing struct variants is not supported",
+        ))
+    }
+}
+
+/// Returns the error for an element of an int or long array that has a different type.
+fn array_element_error(expected: FieldType, actual: &Value) -> NbtError {
+    match FieldType::try_from(actual.discriminant()) {
+        Ok(actual) => NbtError::UnexpectedType { expected, actual },
+        Err(e) => e,
+    }
+}
+
+/// Collects the elements of a list.
+struct SerializeList {
+    elements: Vec<Value>,
+}
+
+impl SerializeList {
+    /// Appends an element, checking that it has the same type as the first element.
+    fn push<T: Serialize + ?Sized>(&mut self, element: &T) -> Result<(), NbtError> {
+        let element = element
+            .serialize(ValueSerializer)?
+            .ok_or(NbtError::CannotSkipInList)?;
+
+        if let Some(first) = self.elements.first() {
+            if first.discriminant() != element.discriminant() {
+                return Err(NbtError::HeterogeneousList {
+                    index: self.elements.len(),
+                    expected: FieldType::try_from(first.discriminant())?,
+                    actual: FieldType::try_from(element.discriminant())?,
+                });
+            }
+        }
+
+        self.elements.push(element);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Option<Value>;
+    type Error = NbtError;
+
+    #[inline]
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, element: &T) -> Result<(), NbtError> {
+        self.push(element)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::List(self.elements)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Option<Value>;
+    type Error = NbtError;
+
+    #[inline]
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, element: &T) -> Result<(), NbtError> {
+        self.push(element)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::List(self.elements)))
+    }
+}
+
+/// Collects the entries of a compound, leaving out entries that are `None`.
+struct SerializeCompound {
+    entries: Compound,
+}
+
+impl ser::SerializeMap for SerializeCompound {
+    type Ok = Option<Value>;
+    type Error = NbtError;
+
+    /// This function *must* not be used. Use [`serialize_entry`](Self::serialize_entry) instead.
+    fn serialize_key<K: Serialize + ?Sized>(&mut self, _key: &K) -> Result<(), NbtError> {
+        Err(NbtError::Unsupported(
+            "Serializer::serialize_key is not supported. Use Serializer::serialize_entry instead",
+        ))
+    }
+
+    /// This function *must* not be used. Use [`serialize_entry`](Self::serialize_entry) instead.
+    fn serialize_value<V: Serialize + ?Sized>(&mut self, _value: &V) -> Result<(), NbtError> {
+        Err(NbtError::Unsupported(
+            "Serializer::serialize_value is not supported. Use Serializer::serialize_entry instead",
+        ))
+    }
+
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), NbtError>
+    where
+        K: Serialize + ?Sized,
+        V: Serialize + ?Sized,
+    {
+        let mut name = String::new();
+        key.serialize(KeySerializer(&mut name))?;
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            self.entries.insert(name, value);
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn end(self) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::Compound(self.entries)))
+    }
+}
+
+impl ser::SerializeStruct for SerializeCompound {
+    type Ok = Option<Value>;
+    type Error = NbtError;
+
+    fn serialize_field<V>(&mut self, key: &'static str, value: &V) -> Result<(), NbtError>
+    where
+        V: Serialize + ?Sized,
+    {
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            self.entries.insert(key.to_owned(), value);
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn end(self) -> Result<Option<Value>, NbtError> {
+        Ok(Some(Value::Compound(self.entries)))
+    }
+}
@@ -0,0 +1,616 @@
+//! Wrapper types that control how values are represented in NBT.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::de::{SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A list of byte arrays.
+///
+/// By default, `serde` serializes a `Vec<Vec<u8>>` as a list of lists of bytes.
+/// This type instead produces a list whose element type is [`ByteArray`](crate::FieldType::ByteArray).
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::ByteArrayList;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Chunk {
+///     sections: ByteArrayList,
+/// }
+///
+/// let chunk = Chunk {
+///     sections: ByteArrayList(vec![vec![1, 2, 3], vec![4]]),
+/// };
+/// let encoded = nbtx::to_be_bytes(&chunk).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ByteArrayList(pub Vec<Vec<u8>>);
+
+impl From<Vec<Vec<u8>>> for ByteArrayList {
+    #[inline]
+    fn from(value: Vec<Vec<u8>>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ByteArrayList> for Vec<Vec<u8>> {
+    #[inline]
+    fn from(value: ByteArrayList) -> Self {
+        value.0
+    }
+}
+
+/// Serializes a byte slice as a byte array.
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_bytes(self.0)
+    }
+}
+
+/// Deserializes a byte array.
+struct ByteBuf(Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        struct ByteBufVisitor;
+
+        impl<'de> Visitor<'de> for ByteBufVisitor {
+            type Value = ByteBuf;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a byte array")
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<ByteBuf, E> {
+                Ok(ByteBuf(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<ByteBuf, E> {
+                Ok(ByteBuf(v))
+            }
+        }
+
+        de.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+impl Serialize for ByteArrayList {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        let mut seq = ser.serialize_seq(Some(self.0.len()))?;
+        for array in &self.0 {
+            seq.serialize_element(&Bytes(array))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteArrayList {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        struct ByteArrayListVisitor;
+
+        impl<'de> Visitor<'de> for ByteArrayListVisitor {
+            type Value = ByteArrayList;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of byte arrays")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ByteArrayList, A::Error> {
+                let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(ByteBuf(array)) = seq.next_element()? {
+                    out.push(array);
+                }
+
+                Ok(ByteArrayList(out))
+            }
+        }
+
+        de.deserialize_seq(ByteArrayListVisitor)
+    }
+}
+
+/// A list of bytes.
+///
+/// Byte slices serialized through [`serialize_bytes`](Serializer::serialize_bytes), such as
+/// fields using [`serde_bytes`](https://crates.io/crates/serde_bytes), are written as a
+/// [`ByteArray`](crate::FieldType::ByteArray). That is usually what a format expects, but some
+/// formats instead store a [`List`](crate::FieldType::List) whose element type is
+/// [`Byte`](crate::FieldType::Byte). The two are different tags and are not interchangeable for
+/// readers that check the type.
+///
+/// This type always produces a list of bytes. When deserializing, both a list of bytes and a
+/// byte array are accepted.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::ByteList;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Sign {
+///     colors: ByteList,
+/// }
+///
+/// let sign = Sign { colors: ByteList(vec![1, -1, 3]) };
+/// let encoded = nbtx::to_be_bytes(&sign).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ByteList(pub Vec<i8>);
+
+impl From<Vec<i8>> for ByteList {
+    #[inline]
+    fn from(value: Vec<i8>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ByteList> for Vec<i8> {
+    #[inline]
+    fn from(value: ByteList) -> Self {
+        value.0
+    }
+}
+
+impl Serialize for ByteList {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        let mut seq = ser.serialize_seq(Some(self.0.len()))?;
+        for byte in &self.0 {
+            seq.serialize_element(byte)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteList {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        struct ByteListVisitor;
+
+        impl<'de> Visitor<'de> for ByteListVisitor {
+            type Value = ByteList;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of bytes")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ByteList, A::Error> {
+                let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    out.push(byte);
+                }
+
+                Ok(ByteList(out))
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<ByteList, E> {
+                Ok(ByteList(v.iter().map(|b| *b as i8).collect()))
+            }
+        }
+
+        de.deserialize_seq(ByteListVisitor)
+    }
+}
+
+/// A double that is stored as a [`Float`](crate::FieldType::Float).
+///
+/// The value is narrowed to an `f32` when serialized, so precision beyond that of an `f32` is
+/// lost. For example, `0.1` is read back as `0.10000000149011612`. Values outside of the range of
+/// an `f32` become infinite.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::AsFloat;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Entity {
+///     speed: AsFloat,
+/// }
+///
+/// let entity = Entity { speed: AsFloat(0.5) };
+/// let encoded = nbtx::to_be_bytes(&entity).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct AsFloat(pub f64);
+
+impl From<f64> for AsFloat {
+    #[inline]
+    fn from(value: f64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<AsFloat> for f64 {
+    #[inline]
+    fn from(value: AsFloat) -> Self {
+        value.0
+    }
+}
+
+impl Serialize for AsFloat {
+    #[inline]
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_f32(self.0 as f32)
+    }
+}
+
+impl<'de> Deserialize<'de> for AsFloat {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        struct AsFloatVisitor;
+
+        impl Visitor<'_> for AsFloatVisitor {
+            type Value = AsFloat;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a float")
+            }
+
+            fn visit_f32<E: serde::de::Error>(self, v: f32) -> Result<AsFloat, E> {
+                Ok(AsFloat(v as f64))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<AsFloat, E> {
+                Ok(AsFloat(v))
+            }
+        }
+
+        de.deserialize_f32(AsFloatVisitor)
+    }
+}
+
+/// Newtype struct name that makes the serializer write a sequence as an int array.
+pub(crate) const INT_ARRAY_TOKEN: &str = "__nbtx_int_array";
+
+/// Newtype struct name that makes the serializer write a sequence as a long array.
+pub(crate) const LONG_ARRAY_TOKEN: &str = "__nbtx_long_array";
+
+/// A 128-bit integer, stored as a [`LongArray`](crate::FieldType::LongArray) of two longs.
+///
+/// The first long holds the most significant 64 bits and the second long the least significant
+/// 64 bits, each as the two's complement of that half. In the big endian format, `Int128(1)` is
+/// therefore written as the long array tag (`0x0c`), the length `00 00 00 02`, followed by
+/// `00 00 00 00 00 00 00 00` and `00 00 00 00 00 00 00 01`. The network format writes the length
+/// and both longs as varints, like any other long array.
+///
+/// Unsigned values can be stored by converting from and to `u128`, which preserves all bits.
+/// When deserializing, a list of two longs is accepted as well.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::Int128;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Entity {
+///     id: Int128,
+/// }
+///
+/// let entity = Entity { id: Int128::from(u128::MAX) };
+/// let encoded = nbtx::to_be_bytes(&entity).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Int128(pub i128);
+
+impl Int128 {
+    /// Returns the value reinterpreted as an unsigned integer.
+    #[inline]
+    pub const fn to_u128(self) -> u128 {
+        self.0 as u128
+    }
+}
+
+impl From<i128> for Int128 {
+    #[inline]
+    fn from(value: i128) -> Self {
+        Self(value)
+    }
+}
+
+impl From<u128> for Int128 {
+    #[inline]
+    fn from(value: u128) -> Self {
+        Self(value as i128)
+    }
+}
+
+impl From<Int128> for i128 {
+    #[inline]
+    fn from(value: Int128) -> Self {
+        value.0
+    }
+}
+
+impl From<Int128> for u128 {
+    #[inline]
+    fn from(value: Int128) -> Self {
+        value.to_u128()
+    }
+}
+
+impl Serialize for Int128 {
+    #[inline]
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        let halves = [(self.0 >> 64) as i64, self.0 as i64];
+        ser.serialize_newtype_struct(LONG_ARRAY_TOKEN, &halves)
+    }
+}
+
+impl<'de> Deserialize<'de> for Int128 {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        struct Int128Visitor;
+
+        impl<'de> Visitor<'de> for Int128Visitor {
+            type Value = Int128;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array of two longs")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Int128, A::Error> {
+                let mut next = |index| {
+                    seq.next_element::<i64>()?
+                        .ok_or_else(|| serde::de::Error::invalid_length(index, &self))
+                };
+                let high = next(0)?;
+                let low = next(1)?;
+
+                if seq.next_element::<i64>()?.is_some() {
+                    return Err(serde::de::Error::invalid_length(3, &self));
+                }
+
+                Ok(Int128(((high as i128) << 64) | low as u64 as i128))
+            }
+        }
+
+        de.deserialize_seq(Int128Visitor)
+    }
+}
+
+/// A UUID, stored as an [`IntArray`](crate::FieldType::IntArray) of four ints like in Java 1.16+.
+///
+/// The most significant int is written first. For example, the UUID
+/// `069a79f4-44e9-4726-a5be-fca90e38aaf5` is stored as `[I; 110787060, 1156138790, -1514210135,
+/// 238594805]`. When deserializing, a list of four ints is accepted as well.
+///
+/// See the [`uuid`](crate::uuid) module for converting between other UUID representations.
+///
+/// # Example
+///
+/// ```rust
+/// # use nbtx::Uuid;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Entity {
+///     #[serde(rename = "UUID")]
+///     uuid: Uuid,
+/// }
+///
+/// let entity = Entity { uuid: Uuid(0x069a79f4_44e9_4726_a5be_fca90e38aaf5) };
+/// assert_eq!(entity.uuid.to_string(), "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+///
+/// let encoded = nbtx::to_be_bytes(&entity).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid(pub u128);
+
+impl Uuid {
+    /// Creates a UUID from four ints, with the most significant int first.
+    #[inline]
+    pub const fn from_ints(ints: [i32; 4]) -> Self {
+        Self(
+            (ints[0] as u32 as u128) << 96
+                | (ints[1] as u32 as u128) << 64
+                | (ints[2] as u32 as u128) << 32
+                | ints[3] as u32 as u128,
+        )
+    }
+
+    /// Splits the UUID into four ints, with the most significant int first.
+    #[inline]
+    pub const fn to_ints(self) -> [i32; 4] {
+        [
+            (self.0 >> 96) as i32,
+            (self.0 >> 64) as i32,
+            (self.0 >> 32) as i32,
+            self.0 as i32,
+        ]
+    }
+}
+
+impl From<u128> for Uuid {
+    #[inline]
+    fn from(value: u128) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Uuid> for u128 {
+    #[inline]
+    fn from(value: Uuid) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for Uuid {
+    /// Formats the UUID in its hyphenated hexadecimal form.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = self.0;
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            v >> 96,
+            (v >> 80) & 0xffff,
+            (v >> 64) & 0xffff,
+            (v >> 48) & 0xffff,
+            v & 0xffff_ffff_ffff
+        )
+    }
+}
+
+impl Serialize for Uuid {
+    #[inline]
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_newtype_struct(INT_ARRAY_TOKEN, &self.to_ints())
+    }
+}
+
+impl<'de> Deserialize<'de> for Uuid {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        struct UuidVisitor;
+
+        impl<'de> Visitor<'de> for UuidVisitor {
+            type Value = Uuid;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array of four ints")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Uuid, A::Error> {
+                let mut ints = [0; 4];
+                for (i, int) in ints.iter_mut().enumerate() {
+                    *int = seq
+                        .next_element()?
+                        .ok_or_else(|| serde::de::Error::invalid_length(i, &self))?;
+                }
+
+                if seq.next_element::<i32>()?.is_some() {
+                    return Err(serde::de::Error::invalid_length(5, &self));
+                }
+
+                Ok(Uuid::from_ints(ints))
+            }
+        }
+
+        de.deserialize_seq(UuidVisitor)
+    }
+}
+
+/// Defines a wrapper around a `Vec` that is stored as an int or long array.
+macro_rules! array_wrapper {
+    ($(#[$doc: meta])* $name: ident, $element: ty, $token: ident, $expecting: literal) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+        pub struct $name(pub Vec<$element>);
+
+        impl From<Vec<$element>> for $name {
+            #[inline]
+            fn from(value: Vec<$element>) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for Vec<$element> {
+            #[inline]
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl Serialize for $name {
+            #[inline]
+            fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+                ser.serialize_newtype_struct($token, &self.0)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            #[inline]
+            fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+                struct ArrayVisitor;
+
+                impl<'de> Visitor<'de> for ArrayVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str($expecting)
+                    }
+
+                    fn visit_newtype_struct<D: Deserializer<'de>>(
+                        self,
+                        de: D,
+                    ) -> Result<$name, D::Error> {
+                        de.deserialize_seq(self)
+                    }
+
+                    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<$name, A::Error> {
+                        let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+                        while let Some(element) = seq.next_element()? {
+                            out.push(element);
+                        }
+                        Ok($name(out))
+                    }
+
+                    /// Receives the payload of the array in native byte order.
+                    ///
+                    /// The NBT deserializer does this when the byte order of the data matches
+                    /// that of the host, so the elements can be copied all at once.
+                    #[cfg(feature = "bytemuck")]
+                    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<$name, E> {
+                        Ok($name(bytemuck::pod_collect_to_vec(v)))
+                    }
+                }
+
+                de.deserialize_newtype_struct($token, ArrayVisitor)
+            }
+        }
+    };
+}
+
+array_wrapper!(
+    /// A list of ints that is stored as an [`IntArray`](crate::FieldType::IntArray).
+    ///
+    /// By default, `serde` serializes a `Vec<i32>` as a list of ints. When deserializing, both
+    /// an int array and a list of ints are accepted.
+    ///
+    /// With the `bytemuck` feature, int arrays whose byte order matches the host, such as little
+    /// endian data on x86, are copied at once instead of decoding each element.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::IntArray;
+    /// #[derive(serde::Serialize, serde::Deserialize)]
+    /// struct Structure {
+    ///     size: IntArray,
+    /// }
+    ///
+    /// let structure = Structure { size: IntArray(vec![16, 8, 16]) };
+    /// let encoded = nbtx::to_le_bytes(&structure).unwrap();
+    /// ```
+    IntArray,
+    i32,
+    INT_ARRAY_TOKEN,
+    "an array of ints"
+);
+
+array_wrapper!(
+    /// A list of longs that is stored as a [`LongArray`](crate::FieldType::LongArray).
+    ///
+    /// By default, `serde` serializes a `Vec<i64>` as a list of longs. When deserializing, both
+    /// a long array and a list of longs are accepted.
+    ///
+    /// With the `bytemuck` feature, long arrays whose byte order matches the host, such as
+    /// little endian data on x86, are copied at once instead of decoding each element.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use nbtx::LongArray;
+    /// #[derive(serde::Serialize, serde::Deserialize)]
+    /// struct Section {
+    ///     block_states: LongArray,
+    /// }
+    ///
+    /// let section = Section { block_states: LongArray(vec![0; 256]) };
+    /// let encoded = nbtx::to_le_bytes(&section).unwrap();
+    /// ```
+    LongArray,
+    i64,
+    LONG_ARRAY_TOKEN,
+    "an array of longs"
+);
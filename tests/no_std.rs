@@ -0,0 +1,40 @@
+//! Verifies that serialization is usable from a `#![no_std]` crate.
+//!
+//! Run with `cargo test --no-default-features --test no_std`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(serde::Serialize)]
+struct Data {
+    name: String,
+    values: Vec<i32>,
+}
+
+#[test]
+fn serialize_without_std() {
+    let data = Data {
+        name: String::from("nbtx"),
+        values: vec![-1, 300],
+    };
+
+    let encoded: Vec<u8> = nbtx::to_net_bytes(&data).unwrap();
+    assert_eq!(
+        encoded,
+        [
+            10, 4, b'D', b'a', b't', b'a', // Root compound
+            8, 4, b'n', b'a', b'm', b'e', 4, b'n', b'b', b't', b'x', // name
+            9, 6, b'v', b'a', b'l', b'u', b'e', b's', 3, 4, 1, 0xd8, 0x04, // values
+            0,    // End
+        ]
+    );
+
+    let mut writer = Vec::new();
+    nbtx::to_le_bytes_in(&mut writer, &data).unwrap();
+    assert_eq!(writer, nbtx::to_le_bytes(&data).unwrap());
+}